@@ -0,0 +1,115 @@
+#![feature(proc_macro_hygiene)]
+
+use crate::HtmlMacroTest;
+use html_macro::html;
+use virtual_node::{IterableNodes, View, VirtualNode};
+
+struct Greeting {
+    name: &'static str,
+    excited: bool,
+}
+
+impl View for Greeting {
+    fn render(&self) -> VirtualNode {
+        let punctuation = if self.excited { "!" } else { "." };
+        let greeting = format!("{}{}", self.name, punctuation);
+
+        html! { <b>{ greeting }</b> }
+    }
+}
+
+struct Card {
+    title: String,
+    children: Vec<VirtualNode>,
+}
+
+impl View for Card {
+    fn render(&self) -> VirtualNode {
+        html! {
+          <section>
+            <h2>{ self.title.as_str() }</h2>
+            { self.children.clone() }
+          </section>
+        }
+    }
+}
+
+struct Input {
+    r#type: &'static str,
+}
+
+impl View for Input {
+    fn render(&self) -> VirtualNode {
+        html! { <input type=self.r#type /> }
+    }
+}
+
+#[test]
+fn self_closing_component() {
+    HtmlMacroTest {
+        desc: "Attributes are props",
+        generated: html! { <div><Greeting name="Percy" excited=true /></div> },
+        expected: html! { <div><b>Percy!</b></div> },
+    }
+    .test();
+}
+
+#[test]
+fn keyword_prop() {
+    HtmlMacroTest {
+        desc: "Props that are keywords become raw identifiers",
+        generated: html! { <div><Input type="text" /></div> },
+        expected: html! { <div><input type="text" /></div> },
+    }
+    .test();
+}
+
+#[test]
+fn component_with_children() {
+    let title = "Title".to_string();
+
+    HtmlMacroTest {
+        desc: "Nodes between the open and close tag are children",
+        generated: html! {
+          <div>
+            <Card title=title>
+              <p>Body</p>
+              <Greeting name="Percy" excited=false />
+            </Card>
+          </div>
+        },
+        expected: html! {
+          <div>
+            <section><h2>Title</h2><p>Body</p><b>Percy.</b></section>
+          </div>
+        },
+    }
+    .test();
+}
+
+#[test]
+fn component_as_root() {
+    HtmlMacroTest {
+        desc: "The root node is the component's view",
+        generated: html! { <Greeting name="Percy" excited=true /> },
+        expected: html! { <b>Percy!</b> },
+    }
+    .test();
+}
+
+#[test]
+fn components_in_a_loop() {
+    let names = vec!["a", "b"];
+
+    HtmlMacroTest {
+        desc: "One component per item",
+        generated: html! {
+          <ul>
+            { for name in names.iter() { <Greeting name=*name excited=false /> } }
+          </ul>
+        },
+        expected: html! { <ul><b>a.</b><b>b.</b></ul> },
+    }
+    .test();
+}
+
@@ -0,0 +1,202 @@
+#![feature(proc_macro_hygiene)]
+
+use crate::HtmlMacroTest;
+use html_macro::{html, html_tree};
+use virtual_node::{IntoTreeNodes, IterableNodes, VElement, VTree, VirtualNode};
+
+#[test]
+fn if_else() {
+    let render = |logged_in: bool| {
+        html! {
+          <div>
+            { if logged_in { <b>Welcome</b> } else { <button>Log in</button> } }
+          </div>
+        }
+    };
+
+    HtmlMacroTest {
+        desc: "If branch",
+        generated: render(true),
+        expected: html! { <div><b>Welcome</b></div> },
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "Else branch",
+        generated: render(false),
+        expected: html! { <div><button>Log in</button></div> },
+    }
+    .test();
+}
+
+#[test]
+fn if_without_else() {
+    let show = false;
+
+    HtmlMacroTest {
+        desc: "No nodes, not even an empty text node",
+        generated: html! { <div>{ if show { <b></b> } }</div> },
+        expected: VirtualNode::element("div"),
+    }
+    .test();
+}
+
+#[test]
+fn else_if_and_if_let() {
+    let render = |count: u32, name: Option<&str>| {
+        html! {
+          <div>
+            { if count == 0 { <i></i> } else if count == 1 { <b></b> } else { <em></em> } }
+            { if let Some(name) = name { <span>{ name }</span> } }
+          </div>
+        }
+    };
+
+    HtmlMacroTest {
+        desc: "Else if",
+        generated: render(1, None),
+        expected: html! { <div><b></b></div> },
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "If let",
+        generated: render(2, Some("Percy")),
+        expected: html! { <div><em></em><span>Percy</span></div> },
+    }
+    .test();
+}
+
+#[test]
+fn for_loop() {
+    let items = vec!["a", "b"];
+
+    HtmlMacroTest {
+        desc: "One node per item",
+        generated: html! { <ul>{ for item in items.iter() { <li>{ *item }</li> } }</ul> },
+        expected: html! { <ul><li>a</li><li>b</li></ul> },
+    }
+    .test();
+
+    let mut expected = VElement::new("dl");
+    expected.children = vec![
+        html! { <dt>a</dt> },
+        VirtualNode::text("0"),
+        html! { <dt>b</dt> },
+        VirtualNode::text("1"),
+        html! { <dd></dd> },
+    ];
+
+    HtmlMacroTest {
+        desc: "Several nodes per item, including blocks and nested loops",
+        generated: html! {
+          <dl>
+            {
+              for (idx, item) in items.iter().enumerate() {
+                <dt>{ *item }</dt>
+                { VirtualNode::text(idx.to_string()) }
+                { for _ in 0..idx { <dd></dd> } }
+              }
+            }
+          </dl>
+        },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+#[test]
+fn match_arms() {
+    let render = |count: Option<u32>| {
+        html! {
+          <div>
+            {
+              match count {
+                Some(0) => {}
+                Some(count) if count > 9 => { <b>Many</b> }
+                Some(count) => { <span>{ count.to_string() }</span> },
+                None => { <i>Loading</i> }
+              }
+            }
+          </div>
+        }
+    };
+
+    HtmlMacroTest {
+        desc: "Empty arm",
+        generated: render(Some(0)),
+        expected: VirtualNode::element("div"),
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "Guard",
+        generated: render(Some(10)),
+        expected: html! { <div><b>Many</b></div> },
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "Binding",
+        generated: render(Some(5)),
+        expected: html! { <div><span>5</span></div> },
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "Trailing arm",
+        generated: render(None),
+        expected: html! { <div><i>Loading</i></div> },
+    }
+    .test();
+}
+
+/// Bodies that don't start with a tag are still regular Rust code.
+#[test]
+fn rust_blocks() {
+    let show = true;
+    let node = html! { <b></b> };
+
+    HtmlMacroTest {
+        desc: "If with Rust bodies",
+        generated: html! { <div>{ if show { node } else { VirtualNode::text("") } }</div> },
+        expected: html! { <div><b></b></div> },
+    }
+    .test();
+}
+
+#[test]
+fn html_tree_control_flow() {
+    let render_tree = |items: &[&str], selected: Option<usize>| {
+        html_tree! {
+          <ul>
+            { for item in items.iter() { <li>{ *item }</li> } }
+            { if items.is_empty() { <li>Empty</li> } }
+            { match selected { Some(idx) => { <b>{ idx.to_string() }</b> } None => {} } }
+          </ul>
+        }
+    };
+    let render = |items: &[&str], selected: Option<usize>| {
+        html! {
+          <ul>
+            { for item in items.iter() { <li>{ *item }</li> } }
+            { if items.is_empty() { <li>Empty</li> } }
+            { match selected { Some(idx) => { <b>{ idx.to_string() }</b> } None => {} } }
+          </ul>
+        }
+    };
+
+    HtmlMacroTest {
+        desc: "Loop and match",
+        generated: VirtualNode::from(&render_tree(&["a", "b"], Some(1))),
+        expected: render(&["a", "b"], Some(1)),
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "If",
+        generated: VirtualNode::from(&render_tree(&[], None)),
+        expected: render(&[], None),
+    }
+    .test();
+}
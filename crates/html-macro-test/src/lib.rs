@@ -11,9 +11,14 @@
 
 use html_macro::html;
 use std::collections::HashMap;
-use virtual_node::{IterableNodes, VElement, VirtualNode};
+use virtual_node::{classes, IterableNodes, VElement, View, VirtualNode};
+use virtual_node::{InsertNativeEvent, NativeEvent, SkipNativeEvent};
+use wasm_bindgen::JsValue;
 
+mod components;
+mod control_flow;
 mod text;
+mod tree;
 
 struct HtmlMacroTest<'a> {
     desc: &'a str,
@@ -53,6 +58,95 @@ fn one_attr() {
     .test();
 }
 
+#[test]
+fn classes() {
+    let is_active = true;
+    let size = "big".to_string();
+
+    let mut expected = VElement::new("div");
+    expected.classes.insert("btn active big");
+
+    HtmlMacroTest {
+        desc: "Class tokens",
+        generated: html! { <div class=classes!["btn", "active" => is_active, "disabled" => !is_active, &size]></div> },
+        expected: expected.clone().into(),
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "A class string gets split into tokens",
+        generated: html! { <div class="btn  active big btn"></div> },
+        expected: expected.into(),
+    }
+    .test();
+
+    struct Row {
+        class: String,
+    }
+    let row = Row {
+        class: "row".to_string(),
+    };
+    let column = 3;
+
+    let mut expected = VElement::new("div");
+    expected.classes.insert("row");
+    let mut cell = VElement::new("span");
+    cell.classes.insert("3");
+    expected.children = vec![cell.into()];
+
+    HtmlMacroTest {
+        desc: "Any Display value works as a class, without being moved",
+        generated: html! { <div class=row.class><span class=column></span></div> },
+        expected: expected.into(),
+    }
+    .test();
+    assert_eq!(row.class, "row");
+}
+
+#[test]
+fn style_properties() {
+    let width = 50;
+    let color = "red".to_string();
+
+    let mut expected = VElement::new("div");
+    expected.styles.insert("width", "50px");
+    expected.styles.insert("color", "red");
+
+    HtmlMacroTest {
+        desc: "Style properties",
+        generated: html! { <div style={ ("width", format!("{}px", width)), ("color", color) }></div> },
+        expected: expected.into(),
+    }
+    .test();
+
+    let mut expected = VElement::new("div");
+    expected
+        .attrs
+        .insert("style".to_string(), "width: 50px;".to_string());
+
+    HtmlMacroTest {
+        desc: "A style string is a regular attribute",
+        generated: html! { <div style={ "width: 50px;" }></div> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+#[test]
+fn unsafe_inner_html() {
+    let html = "<b>Raw</b>".to_string();
+
+    let mut expected = VElement::new("div");
+    expected.unsafe_inner_html = Some("<b>Raw</b>".to_string());
+
+    HtmlMacroTest {
+        desc: "Raw HTML is kept out of the attributes",
+        generated: html! { <div unsafe_inner_html=html></div> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
 #[test]
 fn event() {
     HtmlMacroTest {
@@ -65,6 +159,21 @@ fn event() {
     .test();
 }
 
+#[test]
+fn native_event() {
+    let generated = html! { <div onclick=|_: JsValue|{}></div> };
+    assert!(
+        generated.as_velement_ref().unwrap().events.0.contains_key("onclick"),
+        "Events with a JsCast argument are kept as native closures in non wasm-32 targets"
+    );
+
+    let generated = html! { <div onclick=|_: u8|{} oninput=||{}></div> };
+    assert!(
+        generated.as_velement_ref().unwrap().events.0.is_empty(),
+        "Events that can only be called from JavaScript are dropped in non wasm-32 targets"
+    );
+}
+
 #[test]
 fn child_node() {
     let mut expected = VElement::new("div");
@@ -217,6 +326,143 @@ fn keyword_attribute() {
     html! { <link rel="stylesheet" type="text/css" href="/app.css" /> };
 }
 
+#[test]
+fn optional_nodes() {
+    let show = |visible: bool| {
+        if visible {
+            Some(html! { <em></em> })
+        } else {
+            None
+        }
+    };
+
+    let mut expected = VElement::new("div");
+    expected.children = vec![VirtualNode::element("em")];
+
+    HtmlMacroTest {
+        desc: "Some node",
+        generated: html! { <div> { show(true) } </div> },
+        expected: expected.into(),
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "No node, not even an empty text node",
+        generated: html! { <div> { show(false) } </div> },
+        expected: VirtualNode::element("div"),
+    }
+    .test();
+}
+
+#[test]
+fn iterator_of_nodes() {
+    let items = vec!["a", "b", "c"];
+
+    let mut expected = VElement::new("ul");
+    expected.children = vec![
+        html! { <li>a</li> },
+        html! { <li>b</li> },
+        html! { <li>c</li> },
+    ];
+
+    HtmlMacroTest {
+        desc: "Mapped iterator",
+        generated: html! {
+          <ul>
+            { items.iter().map(|item| html! { <li>{ *item }</li> }) }
+          </ul>
+        },
+        expected: expected.into(),
+    }
+    .test();
+
+    let mut expected = VElement::new("ul");
+    expected.children = vec![html! { <li>b</li> }];
+
+    HtmlMacroTest {
+        desc: "Filtered iterator",
+        generated: html! {
+          <ul>
+            {
+              items
+                .iter()
+                .filter(|item| **item == "b")
+                .map(|item| html! { <li>{ *item }</li> })
+            }
+          </ul>
+        },
+        expected: expected.into(),
+    }
+    .test();
+
+    let mut expected = VElement::new("ul");
+    expected.children = vec![html! { <li>c</li> }, html! { <li>b</li> }];
+
+    HtmlMacroTest {
+        desc: "Any iterator of nodes",
+        generated: html! {
+          <ul>
+            { items.iter().map(|item| html! { <li>{ *item }</li> }).skip(1).rev() }
+          </ul>
+        },
+        expected: expected.into(),
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "Empty iterator",
+        generated: html! { <ul> { items.iter().take(0).map(|item| html! { <li>{ *item }</li> }) } </ul> },
+        expected: VirtualNode::element("ul"),
+    }
+    .test();
+}
+
+#[test]
+fn number_nodes() {
+    let count: usize = 3;
+    let ratio = 0.5;
+
+    let mut expected = VElement::new("span");
+    expected.children = vec![VirtualNode::text("3"), VirtualNode::text("0.5")];
+
+    HtmlMacroTest {
+        desc: "Numbers become text nodes",
+        generated: html! { <span>{ count }{ ratio }</span> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+#[test]
+fn view_nodes() {
+    struct Greeting;
+
+    impl View for Greeting {
+        fn render(&self) -> VirtualNode {
+            html! { <b>Hello</b> }
+        }
+    }
+
+    let greeting = Greeting;
+
+    let mut expected = VElement::new("div");
+    expected.children = vec![html! { <b>Hello</b> }];
+
+    HtmlMacroTest {
+        desc: "A reference to a View",
+        generated: html! { <div> { &greeting } </div> },
+        expected: expected.clone().into(),
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "A View",
+        generated: html! { <div> { Greeting } </div> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
 // Verify that all of our self closing tags work as both.
 // Self closing tags can be written as either <tag> and <tag />
 #[test]
@@ -254,3 +500,71 @@ fn self_closing_tag() {
     }
     .test();
 }
+
+#[test]
+fn hyphenated_and_namespaced_attributes() {
+    let id = 5;
+
+    let mut expected = VElement::new("div");
+    expected.attrs.insert("data-id".to_string(), "5".to_string());
+    expected.attrs.insert("aria-label".to_string(), "Close".to_string());
+    expected.attrs.insert("data-long-name".to_string(), "x".to_string());
+
+    HtmlMacroTest {
+        desc: "Dash separated attribute names",
+        generated: html! { <div data-id=id aria-label="Close" data-long-name="x"></div> },
+        expected: expected.into(),
+    }
+    .test();
+
+    let mut expected = VElement::new("meta");
+    expected
+        .attrs
+        .insert("http-equiv".to_string(), "refresh".to_string());
+    expected.attrs.insert("content".to_string(), "30".to_string());
+
+    HtmlMacroTest {
+        desc: "A hyphenated attribute right after the tag name and before a trailing slash",
+        generated: html! { <meta http-equiv="refresh" content="30" /> },
+        expected: expected.into(),
+    }
+    .test();
+
+    let mut expected = VElement::new("a");
+    expected
+        .attrs
+        .insert("xlink:href".to_string(), "#icon".to_string());
+    expected.attrs.insert("data-type".to_string(), "a".to_string());
+
+    HtmlMacroTest {
+        desc: "Namespaced attributes and keywords within attribute names",
+        generated: html! { <a xlink:href="#icon" data-type="a"></a> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+#[test]
+fn custom_elements_and_svg_attributes() {
+    let mut expected = VElement::new("my-element");
+    expected.attrs.insert("data-id".to_string(), "1".to_string());
+
+    HtmlMacroTest {
+        desc: "Custom elements have a dash in their name",
+        generated: html! { <my-element data-id="1"></my-element> },
+        expected: expected.into(),
+    }
+    .test();
+
+    let mut link = VElement::new("a");
+    link.attrs.insert("fill".to_string(), "red".to_string());
+    let mut expected = VElement::new("svg");
+    expected.children = vec![link.into(), VirtualNode::element("use")];
+
+    HtmlMacroTest {
+        desc: "Elements within an svg accept any attribute",
+        generated: html! { <svg><a fill="red"></a><use /></svg> },
+        expected: expected.into(),
+    }
+    .test();
+}
@@ -0,0 +1,64 @@
+#![feature(proc_macro_hygiene)]
+
+use crate::HtmlMacroTest;
+use html_macro::{html, html_tree};
+use virtual_node::{IntoTreeNodes, IterableNodes, VTree, VirtualNode};
+
+/// `html_tree!` should create the same virtual dom as `html!`, just stored differently.
+#[test]
+fn same_nodes_as_html_macro() {
+    let count = 3;
+    let name = "Percy";
+
+    HtmlMacroTest {
+        desc: "Elements, attributes and classes",
+        generated: VirtualNode::from(
+            &html_tree! { <div id="app" class="btn big"><span></span><br /></div> },
+        ),
+        expected: html! { <div id="app" class="btn big"><span></span><br /></div> },
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "Text and attribute values",
+        generated: VirtualNode::from(&html_tree! { <p title=count>Hello { name }, welcome</p> }),
+        expected: html! { <p title=count>Hello { name }, welcome</p> },
+    }
+    .test();
+
+    HtmlMacroTest {
+        desc: "Spacing around braced text",
+        generated: VirtualNode::from(&html_tree! { <div> { name } { name }<b>!</b></div> }),
+        expected: html! { <div> { name } { name }<b>!</b></div> },
+    }
+    .test();
+}
+
+#[test]
+fn braced_trees() {
+    let items: Vec<VTree> = (0..3)
+        .map(|idx| html_tree! { <li>{ idx.to_string() }</li> })
+        .collect();
+    let title = Some(html_tree! { <h1>Title</h1> });
+
+    let tree = html_tree! {
+      <div>
+        { title }
+        <ul>{ items }</ul>
+      </div>
+    };
+
+    assert_eq!(
+        tree.to_string(),
+        "<div><h1>Title</h1><ul><li>0</li><li>1</li><li>2</li></ul></div>"
+    );
+    assert_eq!(tree.to_string(), VirtualNode::from(&tree).to_string());
+}
+
+#[test]
+fn text_root_node() {
+    let text = "Hello";
+
+    assert_eq!(html_tree! { { text } }.to_string(), "Hello");
+    assert_eq!(html_tree! { Hello world }.to_string(), "Hello world");
+}
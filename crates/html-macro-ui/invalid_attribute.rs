@@ -0,0 +1,11 @@
+#![feature(proc_macro_hygiene)]
+
+extern crate virtual_dom_rs;
+use virtual_dom_rs::prelude::*;
+
+// Misspelled the src attribute of an img
+fn main () {
+    html! {
+        <img scr="/logo.png" />
+    };
+}
\ No newline at end of file
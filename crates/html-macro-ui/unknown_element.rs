@@ -0,0 +1,11 @@
+#![feature(proc_macro_hygiene)]
+
+extern crate virtual_dom_rs;
+use virtual_dom_rs::prelude::*;
+
+// Misspelled the div tag
+fn main () {
+    html! {
+        <dvi></dvi>
+    };
+}
\ No newline at end of file
@@ -0,0 +1,21 @@
+#![feature(proc_macro_hygiene)]
+
+extern crate virtual_dom_rs;
+use virtual_dom_rs::prelude::*;
+
+struct Greeting {
+    name: &'static str,
+}
+
+impl View for Greeting {
+    fn render(&self) -> VirtualNode {
+        html! { <b>{ self.name }</b> }
+    }
+}
+
+// The Greeting component has no age prop of the Greeting component
+fn main () {
+    html! {
+        <div><Greeting name="Percy" age=3 /></div>
+    };
+}
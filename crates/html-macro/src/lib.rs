@@ -2,11 +2,14 @@ extern crate proc_macro;
 
 use crate::parser::HtmlParser;
 use crate::tag::Tag;
+use crate::tree::TreeParser;
 use syn::parse::{Parse, ParseStream, Result};
 use syn::parse_macro_input;
 
 mod parser;
 mod tag;
+mod tree;
+mod validation;
 
 /// Used to generate VirtualNode's from a TokenStream.
 ///
@@ -36,6 +39,23 @@ pub fn html(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     html_parser.finish().into()
 }
 
+/// Used to generate a `VTree` from a TokenStream, for views that are rebuilt and diffed often
+/// enough that allocating every `VirtualNode` shows up in a profile.
+///
+/// html_tree! { <div> Welcome to the html_tree! procedural macro! </div> }
+#[proc_macro]
+pub fn html_tree(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parsed = parse_macro_input!(input as Html);
+
+    let mut tree_parser = TreeParser::new();
+
+    for (idx, tag) in parsed.tags.iter().enumerate() {
+        tree_parser.push_tag(tag, parsed.tags.get(idx + 1));
+    }
+
+    tree_parser.finish().into()
+}
+
 #[derive(Debug)]
 struct Html {
     tags: Vec<Tag>,
@@ -57,6 +57,10 @@ impl HtmlParser {
             Some(Tag::Braced {
                 brace_span: next_brace_span,
                 ..
+            })
+            | Some(Tag::ControlFlow {
+                brace_span: next_brace_span,
+                ..
             }) => self.separated_by_whitespace(brace_span, &next_brace_span),
             _ => false,
         };
@@ -69,7 +73,7 @@ impl HtmlParser {
                 //
                 // html { { some_node }  }
                 let node = quote! {
-                    let node_0: VirtualNode = #stmt.into();
+                    let node_0: VirtualNode = (#stmt).into();
                 };
                 self.push_tokens(node);
             } else {
@@ -84,7 +88,7 @@ impl HtmlParser {
                     let node = self.current_virtual_node_ident(stmt.span());
 
                     let insert_whitespace = quote! {
-                        #node.first().insert_space_before_text();
+                        #node.insert_space_before_text();
                     };
 
                     self.push_tokens(insert_whitespace);
@@ -94,7 +98,7 @@ impl HtmlParser {
                     let node = self.current_virtual_node_ident(stmt.span());
 
                     let insert_whitespace = quote! {
-                        #node.last().insert_space_after_text();
+                        #node.insert_space_after_text();
                     };
 
                     self.push_tokens(insert_whitespace);
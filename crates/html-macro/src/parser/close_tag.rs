@@ -1,10 +1,10 @@
 use crate::parser::{is_self_closing, HtmlParser};
-use proc_macro2::Ident;
+use crate::tag::TagName;
 use quote::quote_spanned;
 
 impl HtmlParser {
     /// Parse an incoming Tag::Close
-    pub(crate) fn parse_close_tag(&mut self, name: &Ident) {
+    pub(crate) fn parse_close_tag(&mut self, name: &TagName) {
         let parent_stack = &mut self.parent_stack;
 
         let close_span = name.span();
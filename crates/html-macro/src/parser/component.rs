@@ -0,0 +1,97 @@
+use crate::parser::control_flow::push_body_nodes;
+use crate::parser::HtmlParser;
+use crate::tag::{Attr, Tag};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned};
+
+impl HtmlParser {
+    /// Parse an incoming Tag::Component
+    ///
+    /// The component's view gets rendered and its nodes are appended to the parent element,
+    /// the same as a braced block holding a `View`.
+    pub(crate) fn parse_component(
+        &mut self,
+        name: &Ident,
+        attrs: &Vec<Attr>,
+        children: &Option<Vec<Tag>>,
+    ) {
+        let view = component_view(name, attrs, children);
+
+        if self.current_node_idx == 0 {
+            let node_ident = self.new_virtual_node_ident(name.span());
+            self.push_tokens(quote! {
+                let #node_ident: VirtualNode = VirtualNode::from(&#view);
+            });
+            return;
+        }
+
+        self.push_iterable_nodes_expr(quote! { IterableNodes::from(&#view) }, name.span());
+    }
+}
+
+/// An expression that creates a component from its props.
+///
+/// `<Card title="Hi"> <p></p> </Card>` becomes `Card { title: "Hi", children: ... }`, so the
+/// compiler points at the attribute when a prop is missing, unknown or of the wrong type.
+pub(crate) fn component_view(
+    name: &Ident,
+    attrs: &[Attr],
+    children: &Option<Vec<Tag>>,
+) -> TokenStream {
+    let mut fields = vec![];
+
+    for attr in attrs.iter() {
+        let key = attr.key.to_string();
+        let key_span = attr.key.span();
+
+        if key.contains('-') || key.contains(':') {
+            let error = format!(
+                r#""{}" can't be the name of a prop. Try "{}""#,
+                key,
+                key.replace(&['-', ':'][..], "_")
+            );
+            return quote_spanned! {key_span=> compile_error!(#error) };
+        }
+
+        let field = match prop_field(&key, key_span) {
+            Some(field) => field,
+            None => {
+                let error = format!(r#""{}" can't be the name of a prop"#, key);
+                return quote_spanned! {key_span=> compile_error!(#error) };
+            }
+        };
+        let value = &attr.value;
+        fields.push(quote! { #field: #value });
+    }
+
+    if let Some(children) = children {
+        let field = Ident::new("children", name.span());
+        let push_children = push_body_nodes(children);
+
+        fields.push(quote! {
+            #field: {
+                let mut __nodes: Vec<VirtualNode> = Vec::new();
+                #push_children
+                __nodes
+            }
+        });
+    }
+
+    quote! {
+        #name { #(#fields),* }
+    }
+}
+
+/// The struct field for a prop. Keywords such as `type` become raw identifiers, `r#type`.
+///
+/// `Ident::new_raw` is semver exempt in proc-macro2 0.4, so we parse the raw identifier instead.
+/// `self`, `super`, `crate` and `Self` can't be raw identifiers.
+fn prop_field(key: &str, span: Span) -> Option<Ident> {
+    let mut field = match syn::parse_str::<Ident>(key) {
+        Ok(field) => field,
+        Err(_) => syn::parse_str::<Ident>(&format!("r#{}", key)).ok()?,
+    };
+    field.set_span(span);
+
+    Some(field)
+}
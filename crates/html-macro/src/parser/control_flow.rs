@@ -0,0 +1,214 @@
+use crate::parser::component::component_view;
+use crate::parser::{is_self_closing, HtmlParser};
+use crate::tag::{ControlFlow, Tag};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+
+impl HtmlParser {
+    /// Parse an incoming Tag::ControlFlow
+    ///
+    /// Just like a braced block the control flow evaluates to `IterableNodes` that get appended
+    /// to the parent element.
+    pub(crate) fn parse_control_flow(&mut self, control_flow: &ControlFlow, brace_span: &Span) {
+        if self.current_node_idx == 0 {
+            let error = "An if, for or match can't be the root node. Try wrapping it in an element";
+            self.push_tokens(quote_spanned! {*brace_span=>
+                let node_0: VirtualNode = compile_error!(#error);
+            });
+            return;
+        }
+
+        let nodes = control_flow_nodes(control_flow);
+        self.push_iterable_nodes_expr(nodes, *brace_span);
+
+        self.set_most_recent_block_start(brace_span.clone());
+    }
+}
+
+/// An expression that evaluates to the `IterableNodes` of an `if`, `for` or `match`.
+fn control_flow_nodes(control_flow: &ControlFlow) -> TokenStream {
+    match control_flow {
+        ControlFlow::If {
+            branches,
+            else_body,
+        } => {
+            let branches = branches.iter().map(|(cond, body)| {
+                let body = body_nodes(body);
+                quote! { if #cond { #body } }
+            });
+
+            let else_body = match else_body {
+                Some(body) => body_nodes(body),
+                None => quote! { IterableNodes::from(Vec::<VirtualNode>::new()) },
+            };
+
+            quote! {
+                #(#branches)else* else { #else_body }
+            }
+        }
+        ControlFlow::For { .. } => {
+            let push_nodes = push_control_flow_nodes(control_flow);
+
+            quote! {
+                {
+                    let mut __nodes: Vec<VirtualNode> = Vec::new();
+                    #push_nodes
+                    IterableNodes::from(__nodes)
+                }
+            }
+        }
+        ControlFlow::Match { expr, arms } => {
+            let arms = arms.iter().map(|(pat, body)| {
+                let body = body_nodes(body);
+                quote! { #pat => { #body } }
+            });
+
+            quote! {
+                match #expr {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+}
+
+/// Statements that push the nodes of an `if`, `for` or `match` onto `__nodes`.
+///
+/// A `for` pushes every node right onto `__nodes`, so a `for` within a `for` doesn't
+/// need a `Vec` of its own.
+fn push_control_flow_nodes(control_flow: &ControlFlow) -> TokenStream {
+    match control_flow {
+        ControlFlow::For { pat, iter, body } => {
+            let push_body_nodes = push_body_nodes(body);
+
+            quote! {
+                for #pat in #iter {
+                    #push_body_nodes
+                }
+            }
+        }
+        _ => {
+            let nodes = control_flow_nodes(control_flow);
+            quote! { __nodes.extend(#nodes); }
+        }
+    }
+}
+
+/// An expression that evaluates to the `IterableNodes` of a body.
+fn body_nodes(body: &[Tag]) -> TokenStream {
+    let roots = split_roots(body);
+
+    // A single element or text node doesn't need a `Vec` to collect into
+    if roots.len() == 1 {
+        if let Some(node) = root_virtual_node(roots[0], None) {
+            return quote! { IterableNodes::from(#node) };
+        }
+    }
+
+    let push_body_nodes = push_body_nodes(body);
+
+    quote! {
+        {
+            let mut __nodes: Vec<VirtualNode> = Vec::new();
+            #push_body_nodes
+            IterableNodes::from(__nodes)
+        }
+    }
+}
+
+/// Statements that push the nodes of a body onto `__nodes`.
+pub(crate) fn push_body_nodes(body: &[Tag]) -> TokenStream {
+    let roots = split_roots(body);
+    let mut tokens = vec![];
+
+    for (idx, root) in roots.iter().enumerate() {
+        let next_tag = roots.get(idx + 1).map(|next_root| &next_root[0]);
+
+        if let Some(node) = root_virtual_node(root, next_tag) {
+            tokens.push(quote! { __nodes.push(#node); });
+            continue;
+        }
+
+        match &root[0] {
+            Tag::Braced { block, .. } => {
+                for stmt in block.stmts.iter() {
+                    tokens.push(quote! {
+                        {
+                            let __braced = #stmt;
+                            __nodes.extend(IterableNodes::braced(__braced).iterable_nodes());
+                        }
+                    });
+                }
+            }
+            Tag::ControlFlow { control_flow, .. } => {
+                tokens.push(push_control_flow_nodes(control_flow));
+            }
+            Tag::Component {
+                name,
+                attrs,
+                children,
+                ..
+            } => {
+                let view = component_view(name, attrs, children);
+                tokens.push(quote! { __nodes.push(VirtualNode::from(&#view)); });
+            }
+            Tag::Close { name, .. } => {
+                let error = format!(r#"Found "</{}>" without a matching open tag"#, name);
+                let close_span = name.span();
+                tokens.push(quote_spanned! {close_span=> {
+                    compile_error!(#error);
+                }});
+            }
+            Tag::Open { .. } | Tag::Text { .. } => unreachable!("Handled above"),
+        }
+    }
+
+    quote! { #(#tokens)* }
+}
+
+/// An expression that evaluates to the `VirtualNode` of an element or a text node.
+///
+/// The next tag determines whether or not a text node gets a space after it, the same as
+/// within an element.
+fn root_virtual_node(root: &[Tag], next_tag: Option<&Tag>) -> Option<TokenStream> {
+    match root[0] {
+        Tag::Open { .. } | Tag::Text { .. } => {}
+        _ => return None,
+    };
+
+    let mut html_parser = HtmlParser::new();
+
+    for (idx, tag) in root.iter().enumerate() {
+        html_parser.push_tag(tag, root.get(idx + 1).or(next_tag));
+    }
+
+    Some(html_parser.finish())
+}
+
+/// Split a body into its root nodes. An element along with all of its descendants, a text
+/// node, a braced block, a component or a nested `if`, `for` or `match`.
+fn split_roots(body: &[Tag]) -> Vec<&[Tag]> {
+    let mut roots = vec![];
+    let mut root_start = 0;
+    let mut depth = 0;
+
+    for (idx, tag) in body.iter().enumerate() {
+        match tag {
+            Tag::Open { name, .. } if !is_self_closing(&name.to_string()) => depth += 1,
+            Tag::Close { .. } if depth > 0 => depth -= 1,
+            _ => {}
+        };
+
+        if depth == 0 {
+            roots.push(&body[root_start..=idx]);
+            root_start = idx + 1;
+        }
+    }
+
+    // An element that is missing its close tag
+    if root_start < body.len() {
+        roots.push(&body[root_start..]);
+    }
+
+    roots
+}
@@ -1,4 +1,4 @@
-use crate::tag::TagKind;
+use crate::tag::{TagKind, TagName};
 use crate::Tag;
 use quote::{quote, quote_spanned};
 use std::collections::HashMap;
@@ -8,6 +8,8 @@ use syn::{Ident, Stmt};
 
 mod braced;
 mod close_tag;
+mod component;
+mod control_flow;
 mod open_tag;
 mod text;
 
@@ -29,7 +31,7 @@ pub struct HtmlParser {
     /// we push it's node index onto the stack.
     ///
     /// Text nodes cannot be parent nodes.
-    parent_stack: Vec<(usize, Ident)>,
+    parent_stack: Vec<(usize, TagName)>,
     /// Key -> index of the parent node within the HTML tree
     /// Value -> vector of child node indices
     parent_to_children: HashMap<usize, Vec<usize>>,
@@ -89,6 +91,24 @@ impl HtmlParser {
                 self.parse_braced(block, brace_span, next_tag);
                 self.last_tag_kind = Some(TagKind::Braced);
             }
+            Tag::ControlFlow {
+                control_flow,
+                brace_span,
+            } => {
+                self.parse_control_flow(control_flow, brace_span);
+                // Text next to an `if`, `for` or `match` is spaced the same as next to a block
+                self.last_tag_kind = Some(TagKind::Braced);
+            }
+            Tag::Component {
+                name,
+                attrs,
+                children,
+                ..
+            } => {
+                self.parse_component(name, attrs, children);
+                // Text after a component is spaced the same as after a close tag
+                self.last_tag_kind = Some(TagKind::Close);
+            }
         };
     }
 
@@ -170,11 +190,7 @@ impl HtmlParser {
     ///
     /// html! { <div> Hello</div> } <--- space between end of div and Hello
     fn separated_by_whitespace(&self, first_span: &Span, second_span: &Span) -> bool {
-        if first_span.end().line != second_span.end().line {
-            return true;
-        }
-
-        second_span.start().column - first_span.end().column > 0
+        separated_by_whitespace(first_span, second_span)
     }
 
     /// Create a new identifier for a VirtualNode and increment our node_idx so that next
@@ -207,12 +223,27 @@ impl HtmlParser {
     ///
     /// html! { <div> { some_var_in_braces } </div>
     /// html! { <div> { some_other_variable } </div>
+    ///
+    /// The statement gets bound to a variable first since passing a block such as `{ {node} }`
+    /// straight into `IterableNodes::braced` warns about unnecessary braces.
     fn push_iterable_nodes(&mut self, stmt: &Stmt) {
+        let nodes = quote! {
+            {
+                let __braced = #stmt;
+                IterableNodes::braced(__braced).iterable_nodes()
+            }
+        };
+        self.push_iterable_nodes_expr(nodes, stmt.span());
+    }
+
+    /// Generate virtual node tokens for an expression that evaluates to `IterableNodes`, such
+    /// as the nodes of an `if`, `for` or `match`.
+    fn push_iterable_nodes_expr(&mut self, nodes: proc_macro2::TokenStream, span: Span) {
         let node_idx = self.current_node_idx;
-        let node_ident = self.new_virtual_node_ident(stmt.span());
+        let node_ident = self.new_virtual_node_ident(span);
 
         let nodes = quote! {
-            let mut #node_ident: IterableNodes = #stmt.into();
+            let mut #node_ident: IterableNodes = #nodes;
         };
         self.push_tokens(nodes);
 
@@ -241,6 +272,15 @@ struct RecentSpanLocations {
     most_recent_block_start: Option<Span>,
 }
 
-fn is_self_closing(tag: &str) -> bool {
+/// See `HtmlParser::separated_by_whitespace`
+pub(crate) fn separated_by_whitespace(first_span: &Span, second_span: &Span) -> bool {
+    if first_span.end().line != second_span.end().line {
+        return true;
+    }
+
+    second_span.start().column - first_span.end().column > 0
+}
+
+pub(crate) fn is_self_closing(tag: &str) -> bool {
     html_validation::is_self_closing(tag)
 }
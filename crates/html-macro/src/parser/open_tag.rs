@@ -1,14 +1,24 @@
 use crate::parser::{is_self_closing, HtmlParser};
-use crate::tag::Attr;
+use crate::tag::{Attr, TagName};
+use crate::validation::open_tag_errors;
 use proc_macro2::{Ident, Span};
 use quote::quote;
 use syn::Expr;
 
 impl HtmlParser {
     /// Parse an incoming Tag::Open
-    pub(crate) fn parse_open_tag(&mut self, name: &Ident, closing_span: &Span, attrs: &Vec<Attr>) {
+    pub(crate) fn parse_open_tag(
+        &mut self,
+        name: &TagName,
+        closing_span: &Span,
+        attrs: &Vec<Attr>,
+    ) {
         self.set_most_recent_open_tag_end(closing_span.clone());
 
+        let open_tags = self.parent_stack.iter().map(|(_, open_tag)| open_tag);
+        let errors = open_tag_errors(name, attrs, open_tags);
+        self.tokens.extend(errors);
+
         let idx = &mut self.current_node_idx;
         let parent_to_children = &mut self.parent_to_children;
         let parent_stack = &mut self.parent_stack;
@@ -30,14 +40,67 @@ impl HtmlParser {
             let key = format!("{}", attr.key);
             let value = &attr.value;
 
+            // <input ref=input_ref />
+            if key == "ref" {
+                let set_node_ref = quote! {
+                    #var_name_node.as_velement_mut().expect("Not an element")
+                        .node_ref = Some(#value.clone());
+                };
+                tokens.push(set_node_ref);
+
+                continue;
+            }
+
+            // <div class="btn big">
+            // <div class=classes!["btn", "active" => is_active]>
+            if key == "class" {
+                let insert_classes = quote! {
+                    #var_name_node.as_velement_mut().expect("Not an element")
+                        .classes.insert(#value.to_string());
+                };
+                tokens.push(insert_classes);
+
+                continue;
+            }
+
+            // <div unsafe_inner_html="<span>hi</span>">
+            if key == "unsafe_inner_html" {
+                let set_inner_html = quote! {
+                    #var_name_node.as_velement_mut().expect("Not an element")
+                        .unsafe_inner_html = Some(#value.to_string());
+                };
+                tokens.push(set_inner_html);
+
+                continue;
+            }
+
+            // <div style={ ("width", width), ("color", "red") }>
+            if key == "style" {
+                if let Expr::Array(properties) = value {
+                    for property in properties.elems.iter() {
+                        let set_style_property = quote! {
+                            {
+                                let (name, value) = #property;
+                                #var_name_node.as_velement_mut().expect("Not an element")
+                                    .styles.insert(name.to_string(), value.to_string());
+                            }
+                        };
+                        tokens.push(set_style_property);
+                    }
+
+                    continue;
+                }
+            }
+
             match value {
                 Expr::Closure(closure) => {
                     // TODO: Use this to decide Box<FnMut(_, _, _, ...)
                     // After we merge the DomUpdater
                     let _arg_count = closure.inputs.len();
 
-                    // NOTE: Closures don't work on non wasm32 targets so we only add
-                    // events on wasm32 targets.
+                    // NOTE: wasm_bindgen Closures don't work on non wasm32 targets so there we
+                    // add native closures that can be called by DOMs that live outside of
+                    // the browser. Closures that can't be called natively are ignored.
                     let add_closure = quote! {
                         #[cfg(target_arch = "wasm32")]
                         {
@@ -48,6 +111,14 @@ impl HtmlParser {
                           #var_name_node.as_velement_mut().expect("Not an element")
                               .events.0.insert(#key.to_string(), closure_rc);
                         }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        {
+                          (&NativeEvent::new(#value)).insert_native_event(
+                              &mut #var_name_node.as_velement_mut().expect("Not an element")
+                                  .events,
+                              #key,
+                          );
+                        }
                     };
 
                     tokens.push(add_closure);
@@ -23,11 +23,14 @@ impl HtmlParser {
                 first_angle_bracket_span,
                 ..
             }) => self.separated_by_whitespace(&text_end, first_angle_bracket_span),
-            Some(Tag::Braced { brace_span, .. }) => {
+            Some(Tag::Braced { brace_span, .. }) | Some(Tag::ControlFlow { brace_span, .. }) => {
                 self.separated_by_whitespace(&text_end, brace_span)
             }
             Some(Tag::Open {
                 open_bracket_span, ..
+            })
+            | Some(Tag::Component {
+                open_bracket_span, ..
             }) => self.separated_by_whitespace(&text_end, open_bracket_span),
             _ => false,
         };
@@ -47,7 +50,7 @@ impl HtmlParser {
             // things work but in reality a text node isn't a parent ever.
             // Just need to make the code DRY / refactor so that we can make things make
             // sense vs. just bolting things together.
-            parent_stack.push((0, Ident::new("unused", Span::call_site())));
+            parent_stack.push((0, Ident::new("unused", Span::call_site()).into()));
         }
 
         let var_name = Ident::new(format!("node_{}", idx).as_str(), Span::call_site());
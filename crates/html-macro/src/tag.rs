@@ -1,8 +1,11 @@
-use proc_macro2::{Span, TokenStream, TokenTree};
-use syn::parse::{Parse, ParseStream, Result};
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use std::fmt;
+use syn::ext::IdentExt;
+use syn::parse::{Error, Parse, ParseStream, Parser, Result};
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::token::Brace;
-use syn::{braced, Block, Expr, Ident, Token};
+use syn::token::{Brace, Bracket};
+use syn::{braced, Block, Expr, ExprArray, Ident, Token};
 
 /// The different kinds of tokens that we parse.
 ///
@@ -12,14 +15,14 @@ pub enum Tag {
     /// <div id="app" class=*CSS>
     /// <br />
     Open {
-        name: Ident,
+        name: TagName,
         attrs: Vec<Attr>,
         open_bracket_span: Span,
         closing_bracket_span: Span,
     },
     /// </div>
     Close {
-        name: Ident,
+        name: TagName,
         first_angle_bracket_span: Span,
     },
     /// html! { <div> Hello World </div> }
@@ -50,6 +53,58 @@ pub enum Tag {
     ///   </div>
     /// }
     Braced { block: Box<Block>, brace_span: Span },
+    /// html! {
+    ///   <ul>
+    ///     { if show_header { <li>Header</li> } }
+    ///     { for item in items { <li>{ item }</li> } }
+    ///     { match user { Some(user) => { <li>{ user }</li> } None => {} } }
+    ///   </ul>
+    /// }
+    ///
+    /// A braced `if`, `for` or `match` whose first body starts with a tag. The bodies are
+    /// markup, anything else in braces is a regular block of Rust code.
+    ControlFlow {
+        control_flow: ControlFlow,
+        brace_span: Span,
+    },
+    /// <NavBarView active_page=ActivePage::Home />
+    /// <Card title="Welcome"> <p>Hello</p> </Card>
+    ///
+    /// A capitalized tag creates a `View`. The attributes are the fields of the view's struct
+    /// and, unless the tag is self closing, the nodes in between the open and close tag are its
+    /// `children` field.
+    Component {
+        name: Ident,
+        attrs: Vec<Attr>,
+        children: Option<Vec<Tag>>,
+        open_bracket_span: Span,
+    },
+}
+
+/// An `if`, `for` or `match` whose bodies are markup.
+///
+/// The conditions, patterns and expressions are kept as tokens and handed back to the compiler
+/// as is, so any errors within them point at the user's code.
+#[derive(Debug)]
+pub enum ControlFlow {
+    /// if cond { ... } else if other_cond { ... } else { ... }
+    If {
+        /// The condition of every `if` along with its body, including `if let` conditions.
+        branches: Vec<(TokenStream, Vec<Tag>)>,
+        else_body: Option<Vec<Tag>>,
+    },
+    /// for pat in iter { ... }
+    For {
+        pat: TokenStream,
+        iter: TokenStream,
+        body: Vec<Tag>,
+    },
+    /// match expr { pat => { ... } pat if guard => { ... } }
+    Match {
+        expr: TokenStream,
+        /// The pattern of every arm, including its guard, along with its body.
+        arms: Vec<(TokenStream, Vec<Tag>)>,
+    },
 }
 
 /// The different kinds of tokens that we parse.
@@ -68,10 +123,124 @@ pub enum TagKind {
 /// etc...
 #[derive(Debug)]
 pub struct Attr {
-    pub key: Ident,
+    pub key: AttrKey,
     pub value: Expr,
 }
 
+/// div
+/// use
+/// my-element
+///
+/// A tag name is one or more identifiers, keywords included, separated by `-` or `:`.
+#[derive(Debug, Clone)]
+pub struct TagName {
+    name: String,
+    span: Span,
+}
+
+impl TagName {
+    /// The span of the first identifier within the tag name.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl From<Ident> for TagName {
+    fn from(ident: Ident) -> Self {
+        TagName {
+            name: ident.to_string(),
+            span: ident.span(),
+        }
+    }
+}
+
+impl fmt::Display for TagName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> PartialEq<T> for TagName {
+    fn eq(&self, other: &T) -> bool {
+        self.name == other.as_ref()
+    }
+}
+
+impl Parse for TagName {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let (name, span) = parse_name(input, "tag name", "element")?;
+
+        Ok(TagName { name, span })
+    }
+}
+
+/// id
+/// type
+/// data-id
+/// aria-label
+/// xlink:href
+///
+/// An attribute name is one or more identifiers, keywords included, separated by `-` or `:`.
+#[derive(Debug)]
+pub struct AttrKey {
+    name: String,
+    span: Span,
+}
+
+impl AttrKey {
+    /// The span of the first identifier within the attribute name.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for AttrKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> PartialEq<T> for AttrKey {
+    fn eq(&self, other: &T) -> bool {
+        self.name == other.as_ref()
+    }
+}
+
+impl Parse for AttrKey {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let (name, span) = parse_name(input, "attribute name", "name")?;
+
+        Ok(AttrKey { name, span })
+    }
+}
+
+/// Parse identifiers separated by `-` or `:`, such as `data-id` or `xlink:href`, along with
+/// the span of the first identifier.
+///
+/// `example_rest` finishes the name in the error message for a trailing separator.
+fn parse_name(input: ParseStream, kind: &str, example_rest: &str) -> Result<(String, Span)> {
+    // Keywords such as `for`, `type` and `use` are names too
+    let first = Ident::parse_any(input)?;
+    let mut name = first.to_string();
+
+    while input.peek(Token![-]) || input.peek(Token![:]) {
+        let separator: TokenTree = input.parse()?;
+        name += &separator.to_string();
+
+        if !peek_ident(input) {
+            let error = format!(
+                "Expected the rest of the {} after `{}`. Try `{}{}`",
+                kind, name, name, example_rest
+            );
+            return Err(Error::new(separator.span(), error));
+        }
+
+        name += &Ident::parse_any(input)?.to_string();
+    }
+
+    Ok((name, first.span()))
+}
+
 impl Parse for Tag {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut input = input;
@@ -104,16 +273,30 @@ impl Parse for Tag {
 
 /// `<div id="app" class=*CSS>`
 fn parse_open_tag(input: &mut ParseStream, open_bracket_span: Span) -> Result<Tag> {
-    let name: Ident = input.parse()?;
+    let name: TagName = input.parse()?;
 
     let attrs = parse_attributes(input)?;
 
-    let _maybe_trailing_slash: Option<Token![/]> = input.parse()?;
-    //    let has_trailing_slash = has_trailing_slash.is_some();
+    let maybe_trailing_slash: Option<Token![/]> = input.parse()?;
 
     let closing_bracket = input.parse::<Token![>]>()?;
     let closing_bracket_span = closing_bracket.span();
 
+    if is_component(&name) {
+        let name = component_ident(&name)?;
+        let children = match maybe_trailing_slash {
+            Some(_) => None,
+            None => Some(parse_component_children(input, &name)?),
+        };
+
+        return Ok(Tag::Component {
+            name,
+            attrs,
+            children,
+            open_bracket_span,
+        });
+    }
+
     Ok(Tag::Open {
         name,
         attrs,
@@ -122,6 +305,60 @@ fn parse_open_tag(input: &mut ParseStream, open_bracket_span: Span) -> Result<Ta
     })
 }
 
+/// Components are capitalized, the same as the names of the types that they create.
+fn is_component(name: &TagName) -> bool {
+    name.name
+        .chars()
+        .next()
+        .map_or(false, |first| first.is_uppercase())
+}
+
+/// The name of the type that a component creates, such as `NavBarView`.
+fn component_ident(name: &TagName) -> Result<Ident> {
+    if name.name.contains('-') || name.name.contains(':') {
+        let error = format!(
+            r#""{}" can't be the name of a component. Try "{}""#,
+            name,
+            name.name.replace('-', "").replace(':', "")
+        );
+        return Err(Error::new(name.span(), error));
+    }
+
+    Ok(Ident::new(&name.name, name.span()))
+}
+
+/// Parse tags until we see the component's close tag, such as the `</Card>` of
+/// `<Card> <p>Hello</p> </Card>`.
+fn parse_component_children(input: &mut ParseStream, name: &Ident) -> Result<Vec<Tag>> {
+    let mut children = vec![];
+    // How many of the child elements are still open
+    let mut depth = 0;
+
+    loop {
+        if input.is_empty() {
+            let error = format!(r#"Missing "</{}>""#, name);
+            return Err(Error::new(name.span(), error));
+        }
+
+        let tag: Tag = input.parse()?;
+
+        match &tag {
+            Tag::Open { name, .. } if !html_validation::is_self_closing(&name.to_string()) => {
+                depth += 1;
+            }
+            Tag::Close {
+                name: close_name, ..
+            } if depth == 0 && *close_name == name.to_string() => {
+                return Ok(children);
+            }
+            Tag::Close { .. } if depth > 0 => depth -= 1,
+            _ => {}
+        };
+
+        children.push(tag);
+    }
+}
+
 /// Parse the attributes starting from something like:
 ///     id="app" class=*CSS>
 ///
@@ -131,27 +368,9 @@ fn parse_open_tag(input: &mut ParseStream, open_bracket_span: Span) -> Result<Ta
 fn parse_attributes(input: &mut ParseStream) -> Result<Vec<Attr>> {
     let mut attrs = Vec::new();
 
-    // Do we see an identifier such as `id`? If so proceed
-    while input.peek(Ident) || input.peek(Token![async])  || input.peek(Token![for])
-            || input.peek(Token![loop]) || input.peek(Token![type]) {
-        // <link rel="stylesheet" type="text/css"
-        //   .. async, for, loop, type need to be handled specially since they are keywords
-        let maybe_async_key: Option<Token![async]> = input.parse()?;
-        let maybe_for_key: Option<Token![for]> = input.parse()?;
-        let maybe_loop_key: Option<Token![loop]> = input.parse()?;
-        let maybe_type_key: Option<Token![type]> = input.parse()?;
-
-        let key = if maybe_async_key.is_some() {
-            Ident::new("async", maybe_async_key.unwrap().span())
-        } else if maybe_for_key.is_some() {
-            Ident::new("for", maybe_for_key.unwrap().span())
-        } else if maybe_loop_key.is_some() {
-            Ident::new("loop", maybe_loop_key.unwrap().span())
-        } else if maybe_type_key.is_some() {
-            Ident::new("type", maybe_type_key.unwrap().span())
-        } else {
-            input.parse()?
-        };
+    // Do we see an identifier such as `id` or `data-id`? If so proceed
+    while peek_ident(input) {
+        let key: AttrKey = input.parse()?;
 
         // =
         input.parse::<Token![=]>()?;
@@ -163,9 +382,7 @@ fn parse_attributes(input: &mut ParseStream) -> Result<Vec<Attr>> {
             let tt: TokenTree = input.parse()?;
             value_tokens.extend(Some(tt));
 
-            let has_attrib_key = input.peek(Ident) || input.peek(Token![async]) || input.peek(Token![for])
-                || input.peek(Token![loop]) || input.peek(Token![type]);
-            let peek_start_of_next_attr = has_attrib_key && input.peek2(Token![=]);
+            let peek_start_of_next_attr = peek_attr_key_and_equals(input);
 
             let peek_end_of_tag = input.peek(Token![>]);
 
@@ -176,7 +393,11 @@ fn parse_attributes(input: &mut ParseStream) -> Result<Vec<Attr>> {
             }
         }
 
-        let value: Expr = syn::parse2(value_tokens)?;
+        let value: Expr = if key == "style" {
+            parse_style_value(value_tokens)?
+        } else {
+            syn::parse2(value_tokens)?
+        };
 
         attrs.push(Attr { key, value });
     }
@@ -184,9 +405,53 @@ fn parse_attributes(input: &mut ParseStream) -> Result<Vec<Attr>> {
     Ok(attrs)
 }
 
+/// Whether the next token is an identifier or a keyword.
+fn peek_ident(input: ParseStream) -> bool {
+    Ident::parse_any(&input.fork()).is_ok()
+}
+
+/// Whether the next tokens are the start of an attribute, such as `data-id=`.
+fn peek_attr_key_and_equals(input: ParseStream) -> bool {
+    let fork = input.fork();
+
+    fork.parse::<AttrKey>().is_ok() && fork.peek(Token![=])
+}
+
+/// style={ ("width", width), ("color", "red") }
+///
+/// A braced list of (property, value) tuples gets turned into an array of tuples so that each
+/// style property can be set on its own. Anything else is a regular `style` attribute.
+fn parse_style_value(value_tokens: TokenStream) -> Result<Expr> {
+    let mut tokens = value_tokens.clone().into_iter();
+
+    if let (Some(TokenTree::Group(group)), None) = (tokens.next(), tokens.next()) {
+        if group.delimiter() == Delimiter::Brace {
+            let properties = Punctuated::<Expr, Token![,]>::parse_terminated.parse2(group.stream());
+
+            if let Ok(properties) = properties {
+                let is_style_map = properties.len() > 0
+                    && properties.iter().all(|property| match property {
+                        Expr::Tuple(tuple) => tuple.elems.len() == 2,
+                        _ => false,
+                    });
+
+                if is_style_map {
+                    return Ok(Expr::Array(ExprArray {
+                        attrs: vec![],
+                        bracket_token: Bracket(group.span()),
+                        elems: properties,
+                    }));
+                }
+            }
+        }
+    }
+
+    syn::parse2(value_tokens)
+}
+
 /// </div>
 fn parse_close_tag(input: &mut ParseStream, first_angle_bracket_span: Span) -> Result<Tag> {
-    let name: Ident = input.parse()?;
+    let name: TagName = input.parse()?;
 
     input.parse::<Token![>]>()?;
 
@@ -202,6 +467,15 @@ fn parse_block(input: &mut ParseStream) -> Result<Tag> {
 
     let brace_span = brace_token.span;
 
+    if is_control_flow(&content) {
+        let control_flow = parse_control_flow(&content)?;
+
+        return Ok(Tag::ControlFlow {
+            control_flow,
+            brace_span,
+        });
+    }
+
     let block_expr = content.call(Block::parse_within)?;
 
     let block = Box::new(Block {
@@ -212,6 +486,179 @@ fn parse_block(input: &mut ParseStream) -> Result<Tag> {
     Ok(Tag::Braced { block, brace_span })
 }
 
+/// Whether the block is an `if`, `for` or `match` with markup bodies, as opposed to Rust code
+/// such as `{ if show { some_node } else { other_node } }`.
+///
+/// It is if any of the bodies starts with a tag. The bodies are the braced groups after the
+/// keyword, or for a `match` the braced groups after the `=>` of every arm.
+fn is_control_flow(content: ParseStream) -> bool {
+    let is_match = content.peek(Token![match]);
+    if !(content.peek(Token![if]) || content.peek(Token![for]) || is_match) {
+        return false;
+    }
+
+    let tokens: TokenStream = match content.fork().parse() {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+
+    let mut bodies = brace_groups(tokens);
+    if is_match {
+        bodies = match bodies.into_iter().next() {
+            Some(arms) => brace_groups_after_fat_arrows(arms),
+            None => return false,
+        };
+    }
+
+    bodies
+        .into_iter()
+        .any(|body| match body.into_iter().next() {
+            Some(TokenTree::Punct(punct)) => punct.as_char() == '<',
+            _ => false,
+        })
+}
+
+fn brace_groups(tokens: TokenStream) -> Vec<TokenStream> {
+    tokens
+        .into_iter()
+        .filter_map(|tt| match tt {
+            TokenTree::Group(ref group) if group.delimiter() == Delimiter::Brace => {
+                Some(group.stream())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn brace_groups_after_fat_arrows(tokens: TokenStream) -> Vec<TokenStream> {
+    let mut groups = vec![];
+    let mut tokens = tokens.into_iter();
+    let mut previous_was_equals = false;
+
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Punct(ref punct) if punct.as_char() == '>' && previous_was_equals => {
+                groups.extend(brace_groups(tokens.next().into_iter().collect()));
+                previous_was_equals = false;
+            }
+            TokenTree::Punct(ref punct) => previous_was_equals = punct.as_char() == '=',
+            _ => previous_was_equals = false,
+        }
+    }
+
+    groups
+}
+
+/// `if`, `for` or `match`, see `is_control_flow`.
+fn parse_control_flow(input: ParseStream) -> Result<ControlFlow> {
+    let control_flow = if input.peek(Token![if]) {
+        parse_if(input)?
+    } else if input.peek(Token![for]) {
+        input.parse::<Token![for]>()?;
+        let pat = tokens_until(input, "`in`", |input| input.peek(Token![in]))?;
+        input.parse::<Token![in]>()?;
+        let iter = tokens_until(input, "the body", |input| input.peek(Brace))?;
+        let body = parse_body(input)?;
+
+        ControlFlow::For { pat, iter, body }
+    } else {
+        input.parse::<Token![match]>()?;
+        let expr = tokens_until(input, "the match arms", |input| input.peek(Brace))?;
+
+        let content;
+        braced!(content in input);
+
+        let mut arms = vec![];
+        while !content.is_empty() {
+            let pat = tokens_until(&content, "`=>`", |input| input.peek(Token![=>]))?;
+            content.parse::<Token![=>]>()?;
+
+            if !content.peek(Brace) {
+                return Err(content.error(
+                    "Wrap the markup of a match arm in braces, such as `pat => { <div></div> }`",
+                ));
+            }
+            let body = parse_body(&content)?;
+            let _maybe_comma: Option<Token![,]> = content.parse()?;
+
+            arms.push((pat, body));
+        }
+
+        ControlFlow::Match { expr, arms }
+    };
+
+    if !input.is_empty() {
+        return Err(input.error("Expected the end of the block"));
+    }
+
+    Ok(control_flow)
+}
+
+/// if cond { ... } else if other_cond { ... } else { ... }
+fn parse_if(input: ParseStream) -> Result<ControlFlow> {
+    let mut branches = vec![];
+    let mut else_body = None;
+
+    loop {
+        input.parse::<Token![if]>()?;
+        let cond = tokens_until(input, "the body", |input| input.peek(Brace))?;
+        let body = parse_body(input)?;
+        branches.push((cond, body));
+
+        let maybe_else: Option<Token![else]> = input.parse()?;
+        if maybe_else.is_none() {
+            break;
+        }
+
+        if !input.peek(Token![if]) {
+            else_body = Some(parse_body(input)?);
+            break;
+        }
+    }
+
+    Ok(ControlFlow::If {
+        branches,
+        else_body,
+    })
+}
+
+/// The tags within the braces of an `if`, `for` or `match` body.
+fn parse_body(input: ParseStream) -> Result<Vec<Tag>> {
+    let content;
+    braced!(content in input);
+
+    let mut tags = vec![];
+    while !content.is_empty() {
+        tags.push(content.parse()?);
+    }
+
+    Ok(tags)
+}
+
+/// Collect tokens until we see the token that comes after them, such as the `in` of a `for`.
+fn tokens_until(
+    input: ParseStream,
+    expected: &str,
+    peek_end: fn(ParseStream) -> bool,
+) -> Result<TokenStream> {
+    let mut tokens = TokenStream::new();
+
+    while !peek_end(input) {
+        if input.is_empty() {
+            return Err(input.error(format!("Expected {}", expected)));
+        }
+
+        let tt: TokenTree = input.parse()?;
+        tokens.extend(Some(tt));
+    }
+
+    if tokens.is_empty() {
+        return Err(input.error(format!("Expected some code before {}", expected)));
+    }
+
+    Ok(tokens)
+}
+
 /// Parse a sequence of tokens until we run into a closing tag
 ///   html! { <div> Hello World </div> }
 /// or a brace
@@ -0,0 +1,345 @@
+use crate::parser::{is_self_closing, separated_by_whitespace};
+use crate::tag::{Attr, ControlFlow, Tag, TagKind, TagName};
+use crate::validation::open_tag_errors;
+use proc_macro2::Span;
+use quote::{quote, quote_spanned};
+use syn::{Block, Expr};
+
+/// Used to turn [`Tag`]s into the statements that build a `VTree`.
+///
+/// Unlike the `HtmlParser` there is no need to keep track of parents and children since a
+/// `VTree` is built by pushing nodes depth first, the same order that we parse our tags in.
+///
+/// [`Tag`]: enum.Tag.html
+pub struct TreeParser {
+    /// The tokens that we'll return back into the compiler when we're done.
+    tokens: Vec<proc_macro2::TokenStream>,
+    /// The names of the elements that were opened but not yet closed.
+    open_tags: Vec<TagName>,
+    /// Whether or not we've pushed a node yet. The first tag is the root node.
+    pushed_root: bool,
+    /// The end of the most recent open tag, used to determine whether or not to put space
+    /// around text nodes.
+    most_recent_open_tag_end: Option<Span>,
+    /// The start of the most recent braced block, used to determine whether or not to put
+    /// space around text nodes.
+    most_recent_block_start: Option<Span>,
+    /// The last kind of tag that we parsed.
+    last_tag_kind: Option<TagKind>,
+}
+
+impl TreeParser {
+    /// Create a new TreeParser
+    pub fn new() -> TreeParser {
+        TreeParser {
+            tokens: vec![],
+            open_tags: vec![],
+            pushed_root: false,
+            most_recent_open_tag_end: None,
+            most_recent_block_start: None,
+            last_tag_kind: None,
+        }
+    }
+
+    /// Generate the tokens that push the incoming Tag into the tree.
+    pub fn push_tag(&mut self, tag: &Tag, next_tag: Option<&Tag>) {
+        match tag {
+            Tag::Open {
+                name,
+                attrs,
+                closing_bracket_span,
+                ..
+            } => {
+                self.parse_open_tag(name, attrs);
+                self.most_recent_open_tag_end = Some(*closing_bracket_span);
+                self.last_tag_kind = Some(TagKind::Open);
+            }
+            Tag::Close { name, .. } => {
+                self.parse_close_tag(name);
+                self.last_tag_kind = Some(TagKind::Close);
+            }
+            Tag::Text {
+                text,
+                start_span,
+                end_span,
+            } => {
+                self.parse_text(text, start_span.unwrap(), end_span.unwrap(), next_tag);
+                self.last_tag_kind = Some(TagKind::Text);
+            }
+            Tag::Braced { block, brace_span } => {
+                self.parse_braced(block, brace_span, next_tag);
+                self.most_recent_block_start = Some(*brace_span);
+                self.last_tag_kind = Some(TagKind::Braced);
+            }
+            Tag::Component { name, .. } => {
+                let error = "html_tree! does not support components, use html! instead";
+                let name_span = name.span();
+
+                self.tokens.push(quote_spanned! {name_span=> {
+                    compile_error!(#error);
+                }});
+                self.last_tag_kind = Some(TagKind::Close);
+            }
+            Tag::ControlFlow {
+                control_flow,
+                brace_span,
+            } => {
+                self.parse_control_flow(control_flow, brace_span);
+                self.most_recent_block_start = Some(*brace_span);
+                // Text next to an `if`, `for` or `match` is spaced the same as next to a block
+                self.last_tag_kind = Some(TagKind::Braced);
+            }
+        };
+
+        self.pushed_root = true;
+    }
+
+    /// Wrap the statements that we generated into a block that evaluates to the tree.
+    pub fn finish(&mut self) -> proc_macro2::TokenStream {
+        let tokens = &self.tokens;
+
+        quote! {
+            {
+                let mut __tree = VTree::new();
+                #(#tokens)*
+                __tree
+            }
+        }
+    }
+
+    fn parse_open_tag(&mut self, name: &TagName, attrs: &Vec<Attr>) {
+        let html_tag = name.to_string();
+
+        let errors = open_tag_errors(name, attrs, self.open_tags.iter());
+        self.tokens.extend(errors);
+
+        self.tokens.push(quote! {
+            __tree.open_element(#html_tag);
+        });
+
+        for attr in attrs.iter() {
+            let key = attr.key.to_string();
+            let value = &attr.value;
+
+            let unsupported = match value {
+                Expr::Closure(_) => true,
+                Expr::Array(_) => key == "style",
+                _ => key == "ref" || key == "unsafe_inner_html",
+            };
+            if unsupported {
+                let error = format!("html_tree! does not support {}, use html! instead", key);
+                let key_span = attr.key.span();
+
+                self.tokens.push(quote_spanned! {key_span=> {
+                    compile_error!(#error);
+                }});
+                continue;
+            }
+
+            self.tokens.push(quote! {
+                __tree.attr(#key, #value);
+            });
+        }
+
+        if is_self_closing(&html_tag) {
+            self.tokens.push(quote! {
+                __tree.close_element();
+            });
+        } else {
+            self.open_tags.push(name.clone());
+        }
+    }
+
+    fn parse_close_tag(&mut self, name: &TagName) {
+        let close_span = name.span();
+        let close_tag = name.to_string();
+
+        if is_self_closing(&close_tag) {
+            let error = format!(
+                r#"{} is a self closing tag. Try "<{}>" or "<{} />""#,
+                close_tag, close_tag, close_tag
+            );
+            self.tokens.push(quote_spanned! {close_span=> {
+                compile_error!(#error);
+            }});
+            return;
+        }
+
+        let last_open_tag = self.open_tags.pop().expect("Last open tag").to_string();
+        if last_open_tag != close_tag {
+            let error = format!(
+                r#"Wrong closing tag. Try changing "{}" into "{}""#,
+                close_tag, last_open_tag
+            );
+            self.tokens.push(quote_spanned! {close_span=> {
+                compile_error!(#error);
+            }});
+        }
+
+        self.tokens.push(quote! {
+            __tree.close_element();
+        });
+    }
+
+    /// Text gets the same spacing as within `html!`, see `HtmlParser::parse_text`.
+    fn parse_text(&mut self, text: &str, text_start: Span, text_end: Span, next_tag: Option<&Tag>) {
+        let mut text = text.to_string();
+
+        let most_recent_span = match self.last_tag_kind {
+            Some(TagKind::Braced) => self.most_recent_block_start,
+            Some(TagKind::Open) => self.most_recent_open_tag_end,
+            _ => None,
+        };
+        if let Some(most_recent_span) = most_recent_span {
+            if separated_by_whitespace(&most_recent_span, &text_start) {
+                text = " ".to_string() + &text;
+            }
+        }
+
+        let next_tag_start = match next_tag {
+            Some(Tag::Close {
+                first_angle_bracket_span,
+                ..
+            }) => Some(first_angle_bracket_span),
+            Some(Tag::Braced { brace_span, .. }) | Some(Tag::ControlFlow { brace_span, .. }) => {
+                Some(brace_span)
+            }
+            Some(Tag::Open {
+                open_bracket_span, ..
+            })
+            | Some(Tag::Component {
+                open_bracket_span, ..
+            }) => Some(open_bracket_span),
+            _ => None,
+        };
+        if let Some(next_tag_start) = next_tag_start {
+            if separated_by_whitespace(&text_end, next_tag_start) {
+                text += " ";
+            }
+        }
+
+        self.tokens.push(quote! {
+            __tree.text(#text);
+        });
+    }
+
+    /// The bodies of an `if`, `for` or `match` push their nodes right into the tree, so unlike
+    /// within `html!` there is nothing to collect.
+    fn parse_control_flow(&mut self, control_flow: &ControlFlow, brace_span: &Span) {
+        if !self.pushed_root {
+            let error = "An if, for or match can't be the root node. Try wrapping it in an element";
+            self.tokens.push(quote_spanned! {*brace_span=> {
+                compile_error!(#error);
+            }});
+            return;
+        }
+
+        let tokens = match control_flow {
+            ControlFlow::If {
+                branches,
+                else_body,
+            } => {
+                let branches = branches.iter().map(|(cond, body)| {
+                    let body = body_tokens(body);
+                    quote! { if #cond { #body } }
+                });
+                let else_body = else_body.as_ref().map(|body| body_tokens(body));
+
+                quote! {
+                    #(#branches)else* else { #else_body }
+                }
+            }
+            ControlFlow::For { pat, iter, body } => {
+                let body = body_tokens(body);
+
+                quote! {
+                    for #pat in #iter { #body }
+                }
+            }
+            ControlFlow::Match { expr, arms } => {
+                let arms = arms.iter().map(|(pat, body)| {
+                    let body = body_tokens(body);
+                    quote! { #pat => { #body } }
+                });
+
+                quote! {
+                    match #expr { #(#arms)* }
+                }
+            }
+        };
+
+        self.tokens.push(tokens);
+    }
+
+    /// Braced nodes get the same spacing as within `html!`, see `HtmlParser::parse_braced`.
+    fn parse_braced(&mut self, block: &Box<Block>, brace_span: &Span, next_tag: Option<&Tag>) {
+        let mut insert_whitespace_before_text = false;
+        if let Some(open_tag_end) = self.most_recent_open_tag_end.as_ref() {
+            if self.last_tag_kind == Some(TagKind::Open)
+                && separated_by_whitespace(open_tag_end, brace_span)
+            {
+                insert_whitespace_before_text = true;
+            }
+        }
+
+        let insert_whitespace_after_text = match next_tag {
+            Some(Tag::Close {
+                first_angle_bracket_span,
+                ..
+            }) => separated_by_whitespace(brace_span, &first_angle_bracket_span),
+            Some(Tag::Braced {
+                brace_span: next_brace_span,
+                ..
+            })
+            | Some(Tag::ControlFlow {
+                brace_span: next_brace_span,
+                ..
+            }) => separated_by_whitespace(brace_span, &next_brace_span),
+            _ => false,
+        };
+
+        for stmt in block.stmts.iter() {
+            // The root node of an `html_tree!` call never gets any spacing
+            if !self.pushed_root {
+                self.tokens.push(quote! {
+                    IntoTreeNodes::push_into(#stmt, &mut __tree);
+                });
+                continue;
+            }
+
+            let insert_space_before = if insert_whitespace_before_text {
+                quote! { __tree.insert_space_before_text(); }
+            } else {
+                quote! {}
+            };
+            let insert_space_after = if insert_whitespace_after_text {
+                quote! { __tree.insert_space_after_text(__start); }
+            } else {
+                quote! {}
+            };
+
+            self.tokens.push(quote! {
+                {
+                    #insert_space_before
+                    let __start = __tree.len();
+                    IntoTreeNodes::push_into(#stmt, &mut __tree);
+                    #insert_space_after
+                }
+            });
+        }
+    }
+}
+
+/// The statements that push the nodes of an `if`, `for` or `match` body into the tree.
+fn body_tokens(body: &[Tag]) -> proc_macro2::TokenStream {
+    let mut tree_parser = TreeParser::new();
+    // The body's nodes are children of an element that was already pushed
+    tree_parser.pushed_root = true;
+
+    for (idx, tag) in body.iter().enumerate() {
+        tree_parser.push_tag(tag, body.get(idx + 1));
+    }
+
+    let tokens = &tree_parser.tokens;
+    quote! { #(#tokens)* }
+}
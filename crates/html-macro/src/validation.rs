@@ -0,0 +1,96 @@
+use crate::tag::{Attr, TagName};
+use html_validation::{
+    children_namespace, element_namespace, is_valid_attribute, is_valid_tag, similar_attribute,
+    similar_tag, HTML_NAMESPACE,
+};
+use proc_macro2::TokenStream;
+use quote::quote_spanned;
+
+/// Attributes that aren't HTML but mean something to us, such as the `key` that `diff` uses
+/// to tell nodes apart, the `label` that `virtual_node_test_utils` finds nodes by or the hooks
+/// that get called with the element as it gets created, updated and removed.
+const VIRTUAL_NODE_ATTRIBUTES: &[&str] = &[
+    "key",
+    "label",
+    "on_create_elem",
+    "on_remove_elem",
+    "on_update_elem",
+    "ref",
+    "unsafe_inner_html",
+];
+
+/// A `compile_error!` for an element that doesn't exist and for every attribute that the
+/// element doesn't accept.
+///
+/// `open_tags` are the elements that the element is within, starting from the root. We only
+/// know which attributes HTML elements accept, so those within an `<svg>` or `<math>` can
+/// have any attribute.
+pub(crate) fn open_tag_errors<'a, I>(
+    name: &TagName,
+    attrs: &[Attr],
+    open_tags: I,
+) -> Vec<TokenStream>
+where
+    I: Iterator<Item = &'a TagName>,
+{
+    let tag = name.to_string();
+
+    if !is_valid_tag(&tag) {
+        let error = match similar_tag(&tag) {
+            Some(similar) => format!(r#""{}" is not an element. Try "{}""#, tag, similar),
+            None => format!(
+                r#""{}" is not an element. Custom elements need a dash in their name, such as "x-{}""#,
+                tag, tag
+            ),
+        };
+        let name_span = name.span();
+
+        return vec![quote_spanned! {name_span=> {
+            compile_error!(#error);
+        }}];
+    }
+
+    if namespace_within(&tag, open_tags) != HTML_NAMESPACE {
+        return vec![];
+    }
+
+    attrs
+        .iter()
+        .filter_map(|attr| {
+            let key = attr.key.to_string();
+
+            if VIRTUAL_NODE_ATTRIBUTES.contains(&key.as_str()) || is_valid_attribute(&tag, &key) {
+                return None;
+            }
+
+            let similar = similar_attribute(&tag, &key).map(|similar| similar.to_string());
+            let error = format!(
+                r#""{}" is not an attribute of <{}>. Try "{}""#,
+                key,
+                tag,
+                similar.unwrap_or_else(|| format!("data-{}", key))
+            );
+            let key_span = attr.key.span();
+
+            Some(quote_spanned! {key_span=> {
+                compile_error!(#error);
+            }})
+        })
+        .collect()
+}
+
+/// The namespace of an element that is within the given open elements.
+fn namespace_within<'a, I>(tag: &str, open_tags: I) -> &'static str
+where
+    I: Iterator<Item = &'a TagName>,
+{
+    let mut parent_namespace = None;
+
+    for open_tag in open_tags {
+        let open_tag = open_tag.to_string();
+        let namespace = element_namespace(&open_tag, parent_namespace);
+        parent_namespace = Some(children_namespace(&open_tag, namespace));
+    }
+
+    element_namespace(tag, parent_namespace)
+}
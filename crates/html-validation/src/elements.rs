@@ -0,0 +1,367 @@
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+
+use super::svg_namespace::is_svg_namespace;
+
+lazy_static! {
+    // The attributes that every HTML element accepts
+    //  https://developer.mozilla.org/en-US/docs/Web/HTML/Global_attributes
+    static ref GLOBAL_ATTRIBUTES: HashSet<&'static str> = [
+        "accesskey", "autocapitalize", "autofocus", "class", "contenteditable", "dir", "draggable",
+        "enterkeyhint", "hidden", "id", "inert", "inputmode", "is", "itemid", "itemprop", "itemref",
+        "itemscope", "itemtype", "lang", "nonce", "part", "popover", "role", "slot", "spellcheck",
+        "style", "tabindex", "title", "translate", "xmlns",
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    // The event handler attributes that every HTML element accepts, including those of the
+    // window that can be set on <body>
+    //  https://developer.mozilla.org/en-US/docs/Web/API/Element#events
+    //  https://html.spec.whatwg.org/multipage/webappapis.html#globaleventhandlers
+    static ref EVENT_HANDLER_ATTRIBUTES: HashSet<&'static str> = [
+        "onabort", "onafterprint", "onanimationcancel", "onanimationend", "onanimationiteration",
+        "onanimationstart", "onauxclick", "onbeforeinput", "onbeforeprint", "onbeforetoggle",
+        "onbeforeunload", "onblur", "oncancel", "oncanplay", "oncanplaythrough", "onchange",
+        "onclick", "onclose", "oncontextmenu", "oncopy", "oncuechange", "oncut", "ondblclick",
+        "ondrag", "ondragend", "ondragenter", "ondragleave", "ondragover", "ondragstart", "ondrop",
+        "ondurationchange", "onemptied", "onended", "onerror", "onfocus", "onfocusin",
+        "onfocusout", "onformdata", "onfullscreenchange", "onfullscreenerror",
+        "ongotpointercapture", "onhashchange", "oninput", "oninvalid", "onkeydown", "onkeypress",
+        "onkeyup", "onlanguagechange", "onload", "onloadeddata", "onloadedmetadata",
+        "onloadstart", "onlostpointercapture", "onmessage", "onmessageerror", "onmousedown",
+        "onmouseenter", "onmouseleave", "onmousemove", "onmouseout", "onmouseover", "onmouseup",
+        "onoffline", "ononline", "onpagehide", "onpageshow", "onpaste", "onpause", "onplay",
+        "onplaying", "onpointercancel", "onpointerdown", "onpointerenter", "onpointerleave",
+        "onpointermove", "onpointerout", "onpointerover", "onpointerup", "onpopstate",
+        "onprogress", "onratechange", "onrejectionhandled", "onreset", "onresize", "onscroll",
+        "onscrollend", "onsecuritypolicyviolation", "onseeked", "onseeking", "onselect",
+        "onselectionchange", "onselectstart", "onslotchange", "onstalled", "onstorage",
+        "onsubmit", "onsuspend", "ontimeupdate", "ontoggle", "ontouchcancel", "ontouchend",
+        "ontouchmove", "ontouchstart", "ontransitioncancel", "ontransitionend",
+        "ontransitionrun", "ontransitionstart", "onunhandledrejection", "onunload",
+        "onvolumechange", "onwaiting", "onwheel",
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    // HTML elements along with the attributes that only they accept
+    //  https://developer.mozilla.org/en-US/docs/Web/HTML/Element
+    //  https://developer.mozilla.org/en-US/docs/Web/HTML/Attributes
+    static ref HTML_ELEMENTS: HashMap<&'static str, &'static [&'static str]> = {
+        let elements: &[(&'static str, &'static [&'static str])] = &[
+            ("a", &["download", "href", "hreflang", "ping", "referrerpolicy", "rel", "target", "type"]),
+            ("abbr", &[]),
+            ("address", &[]),
+            ("area", &["alt", "coords", "download", "href", "ping", "referrerpolicy", "rel", "shape", "target"]),
+            ("article", &[]),
+            ("aside", &[]),
+            ("audio", &["autoplay", "controls", "crossorigin", "loop", "muted", "preload", "src"]),
+            ("b", &[]),
+            ("base", &["href", "target"]),
+            ("bdi", &[]),
+            ("bdo", &[]),
+            ("blockquote", &["cite"]),
+            ("body", &[]),
+            ("br", &[]),
+            ("button", &[
+                "disabled", "form", "formaction", "formenctype", "formmethod", "formnovalidate",
+                "formtarget", "name", "popovertarget", "popovertargetaction", "type", "value",
+            ]),
+            ("canvas", &["height", "width"]),
+            ("caption", &[]),
+            ("cite", &[]),
+            ("code", &[]),
+            ("col", &["span"]),
+            ("colgroup", &["span"]),
+            ("command", &["checked", "disabled", "icon", "label", "radiogroup", "type"]),
+            ("data", &["value"]),
+            ("datalist", &[]),
+            ("dd", &[]),
+            ("del", &["cite", "datetime"]),
+            ("details", &["name", "open"]),
+            ("dfn", &[]),
+            ("dialog", &["open"]),
+            ("div", &[]),
+            ("dl", &[]),
+            ("dt", &[]),
+            ("em", &[]),
+            ("embed", &["height", "src", "type", "width"]),
+            ("fieldset", &["disabled", "form", "name"]),
+            ("figcaption", &[]),
+            ("figure", &[]),
+            ("footer", &[]),
+            ("form", &[
+                "accept-charset", "action", "autocomplete", "enctype", "method", "name", "novalidate",
+                "rel", "target",
+            ]),
+            ("h1", &[]),
+            ("h2", &[]),
+            ("h3", &[]),
+            ("h4", &[]),
+            ("h5", &[]),
+            ("h6", &[]),
+            ("head", &[]),
+            ("header", &[]),
+            ("hgroup", &[]),
+            ("hr", &[]),
+            ("html", &["manifest", "version"]),
+            ("i", &[]),
+            ("iframe", &[
+                "allow", "allowfullscreen", "frameborder", "height", "loading", "name",
+                "referrerpolicy", "sandbox", "src", "srcdoc", "width",
+            ]),
+            ("img", &[
+                "alt", "crossorigin", "decoding", "fetchpriority", "height", "ismap", "loading",
+                "referrerpolicy", "sizes", "src", "srcset", "usemap", "width",
+            ]),
+            ("input", &[
+                "accept", "alt", "autocomplete", "capture", "checked", "dirname", "disabled", "form",
+                "formaction", "formenctype", "formmethod", "formnovalidate", "formtarget", "height",
+                "list", "max", "maxlength", "min", "minlength", "multiple", "name", "pattern",
+                "placeholder", "popovertarget", "popovertargetaction", "readonly", "required", "size",
+                "src", "step", "type", "value", "width",
+            ]),
+            ("ins", &["cite", "datetime"]),
+            ("kbd", &[]),
+            ("keygen", &["challenge", "disabled", "form", "keytype", "name"]),
+            ("label", &["for"]),
+            ("legend", &[]),
+            ("li", &["value"]),
+            ("link", &[
+                "as", "blocking", "crossorigin", "disabled", "fetchpriority", "href", "hreflang",
+                "imagesizes", "imagesrcset", "integrity", "media", "referrerpolicy", "rel", "sizes",
+                "type",
+            ]),
+            ("main", &[]),
+            ("map", &["name"]),
+            ("mark", &[]),
+            ("menu", &[]),
+            ("meta", &["charset", "content", "http-equiv", "media", "name"]),
+            ("meter", &["high", "low", "max", "min", "optimum", "value"]),
+            ("nav", &[]),
+            ("noscript", &[]),
+            ("object", &["data", "form", "height", "name", "type", "width"]),
+            ("ol", &["reversed", "start", "type"]),
+            ("optgroup", &["disabled", "label"]),
+            ("option", &["disabled", "label", "selected", "value"]),
+            ("output", &["for", "form", "name"]),
+            ("p", &[]),
+            ("param", &["name", "value"]),
+            ("picture", &[]),
+            ("pre", &[]),
+            ("progress", &["max", "value"]),
+            ("q", &["cite"]),
+            ("rp", &[]),
+            ("rt", &[]),
+            ("ruby", &[]),
+            ("s", &[]),
+            ("samp", &[]),
+            ("script", &[
+                "async", "blocking", "charset", "crossorigin", "defer", "fetchpriority", "integrity",
+                "nomodule", "referrerpolicy", "src", "type",
+            ]),
+            ("search", &[]),
+            ("section", &[]),
+            ("select", &["autocomplete", "disabled", "form", "multiple", "name", "required", "size"]),
+            ("slot", &["name"]),
+            ("small", &[]),
+            ("source", &["height", "media", "sizes", "src", "srcset", "type", "width"]),
+            ("span", &[]),
+            ("strong", &[]),
+            ("style", &["blocking", "media", "type"]),
+            ("sub", &[]),
+            ("summary", &[]),
+            ("sup", &[]),
+            ("table", &["border"]),
+            ("tbody", &[]),
+            ("td", &["colspan", "headers", "rowspan"]),
+            ("template", &[
+                "shadowrootclonable", "shadowrootdelegatesfocus", "shadowrootmode",
+            ]),
+            ("textarea", &[
+                "autocomplete", "cols", "dirname", "disabled", "form", "maxlength", "minlength", "name",
+                "placeholder", "readonly", "required", "rows", "wrap",
+            ]),
+            ("tfoot", &[]),
+            ("th", &["abbr", "colspan", "headers", "rowspan", "scope"]),
+            ("thead", &[]),
+            ("time", &["datetime"]),
+            ("title", &[]),
+            ("tr", &[]),
+            ("track", &["default", "kind", "label", "src", "srclang"]),
+            ("u", &[]),
+            ("ul", &[]),
+            ("var", &[]),
+            ("video", &[
+                "autoplay", "controls", "crossorigin", "height", "loop", "muted", "playsinline",
+                "poster", "preload", "src", "width",
+            ]),
+            ("wbr", &[]),
+        ];
+
+        elements.iter().cloned().collect()
+    };
+
+    // MathML elements
+    //  https://developer.mozilla.org/en-US/docs/Web/MathML/Element
+    static ref MATHML_ELEMENTS: HashSet<&'static str> = [
+        "annotation", "annotation-xml", "maction", "math", "menclose", "merror", "mfenced", "mfrac",
+        "mi", "mmultiscripts", "mn", "mo", "mover", "mpadded", "mphantom", "mprescripts", "mroot",
+        "mrow", "ms", "mspace", "msqrt", "mstyle", "msub", "msubsup", "msup", "mtable", "mtd",
+        "mtext", "mtr", "munder", "munderover", "none", "semantics",
+    ]
+    .iter()
+    .cloned()
+    .collect();
+}
+
+// Tags that are SVG elements but also HTML elements, so `is_svg_namespace` leaves them out
+static SHARED_SVG_ELEMENTS: &[&str] = &["a", "script", "style", "title"];
+
+/// Whether or not this is the tag of an HTML, SVG or MathML element, or of a custom element.
+///
+/// Custom elements have a dash in their name.
+///
+/// ```
+/// use html_validation::is_valid_tag;
+///
+/// assert_eq!(is_valid_tag("div"), true);
+/// assert_eq!(is_valid_tag("circle"), true);
+/// assert_eq!(is_valid_tag("my-element"), true);
+///
+/// assert_eq!(is_valid_tag("dvi"), false);
+/// ```
+pub fn is_valid_tag(tag: &str) -> bool {
+    HTML_ELEMENTS.contains_key(tag)
+        || is_svg_namespace(tag)
+        || SHARED_SVG_ELEMENTS.contains(&tag)
+        || MATHML_ELEMENTS.contains(tag)
+        || is_custom_element(tag)
+}
+
+/// Whether or not an HTML element accepts this attribute.
+///
+/// Global attributes, event handlers such as `onclick`, `data-*`, `aria-*` and namespaced
+/// attributes such as `xml:lang` are accepted on every element. SVG, MathML and custom elements
+/// accept any attribute.
+///
+/// ```
+/// use html_validation::is_valid_attribute;
+///
+/// assert_eq!(is_valid_attribute("img", "src"), true);
+/// assert_eq!(is_valid_attribute("img", "data-src"), true);
+/// assert_eq!(is_valid_attribute("circle", "cx"), true);
+///
+/// assert_eq!(is_valid_attribute("div", "onclick"), true);
+///
+/// assert_eq!(is_valid_attribute("img", "scr"), false);
+/// assert_eq!(is_valid_attribute("div", "href"), false);
+/// assert_eq!(is_valid_attribute("div", "onclik"), false);
+/// ```
+pub fn is_valid_attribute(tag: &str, attribute: &str) -> bool {
+    let element_attributes = match HTML_ELEMENTS.get(tag) {
+        Some(element_attributes) => element_attributes,
+        None => return true,
+    };
+
+    element_attributes.contains(&attribute)
+        || GLOBAL_ATTRIBUTES.contains(attribute)
+        || EVENT_HANDLER_ATTRIBUTES.contains(attribute)
+        || attribute.starts_with("data-")
+        || attribute.starts_with("aria-")
+        || attribute.contains(':')
+}
+
+/// The tag of the HTML element that is closest to a misspelled tag, if any is close enough.
+///
+/// ```
+/// use html_validation::similar_tag;
+///
+/// assert_eq!(similar_tag("dvi"), Some("div"));
+/// assert_eq!(similar_tag("paragraph"), None);
+/// ```
+pub fn similar_tag(tag: &str) -> Option<&'static str> {
+    most_similar(tag, HTML_ELEMENTS.keys().cloned())
+}
+
+/// The attribute of an HTML element that is closest to a misspelled attribute, if any is close
+/// enough.
+///
+/// ```
+/// use html_validation::similar_attribute;
+///
+/// assert_eq!(similar_attribute("img", "scr"), Some("src"));
+/// assert_eq!(similar_attribute("a", "herf"), Some("href"));
+/// assert_eq!(similar_attribute("button", "onclik"), Some("onclick"));
+/// assert_eq!(similar_attribute("div", "size"), None);
+/// ```
+pub fn similar_attribute(tag: &str, attribute: &str) -> Option<&'static str> {
+    let element_attributes = HTML_ELEMENTS.get(tag)?;
+
+    most_similar(
+        attribute,
+        element_attributes
+            .iter()
+            .chain(GLOBAL_ATTRIBUTES.iter())
+            .chain(EVENT_HANDLER_ATTRIBUTES.iter())
+            .cloned(),
+    )
+}
+
+fn is_custom_element(tag: &str) -> bool {
+    tag.contains('-')
+}
+
+/// The candidate that takes the fewest edits to turn into the name, as long as that is at
+/// most two edits. Names of up to three characters only get one.
+fn most_similar<I>(name: &str, candidates: I) -> Option<&'static str>
+where
+    I: Iterator<Item = &'static str>,
+{
+    let max_distance = if name.len() > 3 { 2 } else { 1 };
+
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        // Break ties by name since hash sets and maps have no order
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of insertions, deletions, substitutions and swaps of neighboring characters
+/// that it takes to turn one string into another.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // distances[i][j] is the distance between the first i chars of a and the first j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution_cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
@@ -33,8 +33,15 @@
 
 #![deny(missing_docs)]
 
+pub use elements::{is_valid_attribute, is_valid_tag, similar_attribute, similar_tag};
+pub use namespace::{
+    attribute_namespace, children_namespace, element_namespace, HTML_NAMESPACE,
+    MATHML_NAMESPACE, SVG_NAMESPACE, XLINK_NAMESPACE, XMLNS_NAMESPACE, XML_NAMESPACE,
+};
 pub use self_closing::is_self_closing;
 pub use svg_namespace::is_svg_namespace;
 
+mod elements;
+mod namespace;
 mod self_closing;
 mod svg_namespace;
@@ -0,0 +1,94 @@
+use super::svg_namespace::is_svg_namespace;
+
+/// The namespace of HTML elements.
+pub const HTML_NAMESPACE: &'static str = "http://www.w3.org/1999/xhtml";
+/// The namespace of SVG elements such as `<svg>` and `<circle>`.
+pub const SVG_NAMESPACE: &'static str = "http://www.w3.org/2000/svg";
+/// The namespace of MathML elements such as `<math>` and `<mi>`.
+pub const MATHML_NAMESPACE: &'static str = "http://www.w3.org/1998/Math/MathML";
+/// The namespace of `xlink:` attributes such as `xlink:href`.
+pub const XLINK_NAMESPACE: &'static str = "http://www.w3.org/1999/xlink";
+/// The namespace of `xml:` attributes such as `xml:lang`.
+pub const XML_NAMESPACE: &'static str = "http://www.w3.org/XML/1998/namespace";
+/// The namespace of `xmlns` attributes such as `xmlns:xlink`.
+pub const XMLNS_NAMESPACE: &'static str = "http://www.w3.org/2000/xmlns/";
+
+/// The namespace of an element, given the namespace that its parent's children are in.
+///
+/// `<svg>` and `<math>` elements start a new namespace, every other element inherits the
+/// namespace of its parent.
+///
+/// When we don't know the parent, such as for the root element of a virtual dom, we guess
+/// based on the tag. Tags that are both HTML and SVG tags, such as `a` or `title`, are
+/// guessed to be HTML.
+///
+/// ```
+/// use html_validation::{element_namespace, HTML_NAMESPACE, SVG_NAMESPACE};
+///
+/// assert_eq!(element_namespace("svg", Some(HTML_NAMESPACE)), SVG_NAMESPACE);
+/// assert_eq!(element_namespace("a", Some(SVG_NAMESPACE)), SVG_NAMESPACE);
+/// assert_eq!(element_namespace("a", Some(HTML_NAMESPACE)), HTML_NAMESPACE);
+///
+/// assert_eq!(element_namespace("circle", None), SVG_NAMESPACE);
+/// assert_eq!(element_namespace("a", None), HTML_NAMESPACE);
+/// ```
+pub fn element_namespace(tag: &str, parent_namespace: Option<&str>) -> &'static str {
+    match tag {
+        "svg" => return SVG_NAMESPACE,
+        "math" => return MATHML_NAMESPACE,
+        _ => {}
+    };
+
+    match parent_namespace {
+        Some(SVG_NAMESPACE) => SVG_NAMESPACE,
+        Some(MATHML_NAMESPACE) => MATHML_NAMESPACE,
+        Some(_) => HTML_NAMESPACE,
+        None if is_svg_namespace(tag) => SVG_NAMESPACE,
+        None => HTML_NAMESPACE,
+    }
+}
+
+/// The namespace that the children of an element are in.
+///
+/// This is the element's own namespace, except for elements that can contain HTML such as
+/// an SVG `<foreignObject>` or a MathML `<mtext>`.
+///
+/// ```
+/// use html_validation::{children_namespace, HTML_NAMESPACE, SVG_NAMESPACE};
+///
+/// assert_eq!(children_namespace("g", SVG_NAMESPACE), SVG_NAMESPACE);
+/// assert_eq!(children_namespace("foreignObject", SVG_NAMESPACE), HTML_NAMESPACE);
+/// ```
+pub fn children_namespace(tag: &str, namespace: &str) -> &'static str {
+    match namespace {
+        SVG_NAMESPACE if tag == "foreignObject" => HTML_NAMESPACE,
+        SVG_NAMESPACE => SVG_NAMESPACE,
+        // https://html.spec.whatwg.org/multipage/parsing.html#mathml-text-integration-point
+        MATHML_NAMESPACE if ["mi", "mo", "mn", "ms", "mtext"].contains(&tag) => HTML_NAMESPACE,
+        MATHML_NAMESPACE => MATHML_NAMESPACE,
+        _ => HTML_NAMESPACE,
+    }
+}
+
+/// The namespace of an attribute such as `xlink:href`, if it has one.
+///
+/// ```
+/// use html_validation::{attribute_namespace, XLINK_NAMESPACE};
+///
+/// assert_eq!(attribute_namespace("xlink:href"), Some(XLINK_NAMESPACE));
+/// assert_eq!(attribute_namespace("href"), None);
+/// ```
+pub fn attribute_namespace(name: &str) -> Option<&'static str> {
+    if name == "xmlns" {
+        return Some(XMLNS_NAMESPACE);
+    }
+
+    let prefix = &name[..name.find(':')?];
+
+    match prefix {
+        "xlink" => Some(XLINK_NAMESPACE),
+        "xml" => Some(XML_NAMESPACE),
+        "xmlns" => Some(XMLNS_NAMESPACE),
+        _ => None,
+    }
+}
@@ -6,7 +6,7 @@ lazy_static! {
     //  https://developer.mozilla.org/en-US/docs/Web/SVG/Element
     //  a hashmap of `(tag, is_self_closing)`
     static ref SVG_NAMESPACED_TAGS: HashMap<&'static str, bool> = [
-        // TODO: can cause conflict with html `a`
+        // Conflicts with html `a`, so it inherits the svg namespace from its parent instead
         //("a", true),
         ("animate", true),
         ("animateMotion", false),
@@ -70,19 +70,19 @@ lazy_static! {
         ("polyline", true),
         ("radialGradient", false),
         ("rect", true),
-        // TODO: can cause conflict with html `script` tag
+        // Conflicts with html `script`, so it inherits the svg namespace from its parent instead
         //("script", false),
         ("set", true),
         ("solidcolor", true),
         ("stop", true),
-        // TODO: can cause conflict with html `style` tag
+        // Conflicts with html `style`, so it inherits the svg namespace from its parent instead
         //("style", false),
         ("svg", false),
         ("switch", false),
         ("symbol", false),
         ("text", false),
         ("textPath", false),
-        // TODO: can cause conflict with html `title` tag
+        // Conflicts with html `title`, so it inherits the svg namespace from its parent instead
         //("title", false),
         ("tspan", false),
         // TODO: undocumented
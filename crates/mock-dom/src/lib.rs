@@ -0,0 +1,223 @@
+//! An in-memory DOM that virtual-dom-rs can create and patch nodes in.
+//!
+//! This lets you test your views, diffing and patching with a plain `cargo test`, no
+//! headless browser required.
+//!
+//! ```ignore
+//! let mut dom_updater = DomUpdater::with_backend(MockDom, html! { <button>0</button> });
+//!
+//! dom_updater.update(html! { <button>1</button> }).unwrap();
+//!
+//! assert_eq!(dom_updater.root_node().outer_html(), "<button>1</button>");
+//! ```
+//!
+//! Event handlers that were created by the `html!` macro can be triggered with
+//! `MockNode::dispatch_event`. Since there are no real browser events outside of the browser,
+//! handlers get called with a placeholder event. The same goes for the element that hooks such
+//! as `on_create_elem` get called with. Use `MockNode::has_node_ref` to see which element a
+//! `NodeRef` points at.
+
+#![deny(missing_docs)]
+
+use std::fmt;
+use virtual_node::{DomBackend, DynClosure, NodeKind, NodeRef};
+use wasm_bindgen::JsValue;
+
+mod node;
+pub use self::node::MockNode;
+
+/// A `DomBackend` that creates `MockNode`s.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MockDom;
+
+thread_local! {
+    static DOCUMENT: MockNode = MockNode::element("body", None);
+}
+
+impl MockDom {
+    /// The `<body>` of the current thread's document, which portals can render into.
+    ///
+    /// Nodes only end up in the document if you append them to it.
+    pub fn document() -> MockNode {
+        DOCUMENT.with(|document| document.clone())
+    }
+}
+
+/// The errors that operations on a `MockDom` can fail with.
+#[derive(Debug, PartialEq)]
+pub enum MockDomError {
+    /// The operation only works on elements.
+    NotAnElement,
+    /// Tried to remove a node from a parent that it is not a child of.
+    NotAChild,
+}
+
+impl fmt::Display for MockDomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MockDomError::NotAnElement => write!(f, "The node is not an element"),
+            MockDomError::NotAChild => write!(f, "The node is not a child of this node"),
+        }
+    }
+}
+
+impl std::error::Error for MockDomError {}
+
+impl DomBackend for MockDom {
+    type Node = MockNode;
+    type Error = MockDomError;
+
+    fn create_element(&self, tag: &str) -> Result<MockNode, MockDomError> {
+        Ok(MockNode::element(tag, None))
+    }
+
+    fn create_element_ns(&self, namespace: &str, tag: &str) -> Result<MockNode, MockDomError> {
+        Ok(MockNode::element(tag, Some(namespace)))
+    }
+
+    fn create_text_node(&self, text: &str) -> MockNode {
+        MockNode::text(text)
+    }
+
+    fn create_comment(&self, text: &str) -> MockNode {
+        MockNode::comment(text)
+    }
+
+    fn node_kind(&self, node: &MockNode) -> NodeKind {
+        node.node_kind()
+    }
+
+    fn child_nodes(&self, node: &MockNode) -> Vec<MockNode> {
+        node.child_nodes()
+    }
+
+    fn parent_node(&self, node: &MockNode) -> Option<MockNode> {
+        node.parent_node()
+    }
+
+    fn local_name(&self, elem: &MockNode) -> String {
+        elem.tag().unwrap_or_default()
+    }
+
+    fn namespace_uri(&self, elem: &MockNode) -> Option<String> {
+        elem.namespace()
+    }
+
+    /// `body` is the document and `#id` is the element within the document with that id.
+    fn portal_target(&self, target: &str) -> Option<MockNode> {
+        let document = MockDom::document();
+
+        if target == "body" {
+            return Some(document);
+        }
+
+        if target.starts_with('#') {
+            return document.get_element_by_id(&target[1..]);
+        }
+
+        None
+    }
+
+    fn set_attribute(&self, elem: &MockNode, name: &str, value: &str) -> Result<(), MockDomError> {
+        elem.set_attribute(name, value)
+    }
+
+    fn set_attribute_ns(
+        &self,
+        elem: &MockNode,
+        namespace: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<(), MockDomError> {
+        elem.set_attribute_ns(Some(namespace), name, value)
+    }
+
+    fn remove_attribute(&self, elem: &MockNode, name: &str) -> Result<(), MockDomError> {
+        elem.remove_attribute(name)
+    }
+
+    fn add_class(&self, elem: &MockNode, token: &str) -> Result<(), MockDomError> {
+        elem.add_class(token)
+    }
+
+    fn remove_class(&self, elem: &MockNode, token: &str) -> Result<(), MockDomError> {
+        elem.remove_class(token)
+    }
+
+    fn set_style_property(
+        &self,
+        elem: &MockNode,
+        name: &str,
+        value: &str,
+    ) -> Result<(), MockDomError> {
+        elem.set_style_property(name, value)
+    }
+
+    fn remove_style_property(&self, elem: &MockNode, name: &str) -> Result<(), MockDomError> {
+        elem.remove_style_property(name)
+    }
+
+    fn set_inner_html(&self, elem: &MockNode, html: &str) {
+        elem.set_inner_html(html);
+    }
+
+    fn has_inner_html(&self, elem: &MockNode) -> bool {
+        elem.has_inner_html()
+    }
+
+    fn set_text(&self, node: &MockNode, text: &str) {
+        node.set_text(text);
+    }
+
+    fn append_child(&self, parent: &MockNode, child: &MockNode) -> Result<(), MockDomError> {
+        parent.append_child(child);
+        Ok(())
+    }
+
+    fn insert_before(
+        &self,
+        parent: &MockNode,
+        child: &MockNode,
+        reference: &MockNode,
+    ) -> Result<(), MockDomError> {
+        parent.insert_before(child, reference)
+    }
+
+    fn replace_with(&self, old: &MockNode, new: &MockNode) -> Result<(), MockDomError> {
+        old.replace_with(new);
+        Ok(())
+    }
+
+    fn remove_child(&self, parent: &MockNode, child: &MockNode) -> Result<(), MockDomError> {
+        parent.remove_child(child)
+    }
+
+    fn add_event_listener(
+        &self,
+        elem: &MockNode,
+        event: &str,
+        callback: &DynClosure,
+    ) -> Result<(), MockDomError> {
+        elem.add_event_listener(event, callback)
+    }
+
+    // Just like event handlers, hooks get called with a placeholder instead of the element.
+    fn call_elem_hook(&self, hook: &DynClosure, elem: &MockNode) -> Result<(), MockDomError> {
+        if elem.node_kind() != NodeKind::Element {
+            return Err(MockDomError::NotAnElement);
+        }
+
+        hook.call_native(JsValue::NULL);
+        Ok(())
+    }
+
+    // Node refs point to `web_sys::Element`s, so we keep track of them on the `MockNode`
+    // instead. See `MockNode::has_node_ref`.
+    fn set_node_ref(&self, node_ref: &NodeRef, elem: &MockNode) {
+        elem.set_node_ref(node_ref);
+    }
+
+    fn clear_node_ref(&self, node_ref: &NodeRef, elem: &MockNode) {
+        elem.clear_node_ref(node_ref);
+    }
+}
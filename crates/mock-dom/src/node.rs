@@ -0,0 +1,555 @@
+//! The nodes of a `MockDom`.
+
+use crate::MockDomError;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+use virtual_node::{Classes, DynClosure, NodeKind, NodeRef, Styles};
+use wasm_bindgen::JsValue;
+
+/// A handle to an element, text or comment node in a `MockDom`.
+///
+/// Cloning a `MockNode` is cheap. All clones point to the same node.
+#[derive(Clone)]
+pub struct MockNode(Rc<RefCell<NodeData>>);
+
+struct NodeData {
+    kind: Kind,
+    parent: Weak<RefCell<NodeData>>,
+    children: Vec<MockNode>,
+    listeners: Vec<(String, DynClosure)>,
+    /// The `NodeRef`s that point at this element.
+    node_refs: Vec<NodeRef>,
+}
+
+enum Kind {
+    Element {
+        tag: String,
+        namespace: Option<String>,
+        attrs: Vec<Attribute>,
+        classes: Classes,
+        styles: Styles,
+    },
+    Text(String),
+    Comment(String),
+    /// HTML that was set via `unsafe_inner_html`. We don't parse it, so it is kept as is.
+    RawHtml(String),
+}
+
+struct Attribute {
+    name: String,
+    namespace: Option<String>,
+    value: String,
+}
+
+impl MockNode {
+    pub(crate) fn element(tag: &str, namespace: Option<&str>) -> Self {
+        MockNode::new(Kind::Element {
+            tag: tag.to_string(),
+            namespace: namespace.map(|namespace| namespace.to_string()),
+            attrs: vec![],
+            classes: Classes::new(),
+            styles: Styles::new(),
+        })
+    }
+
+    pub(crate) fn text(text: &str) -> Self {
+        MockNode::new(Kind::Text(text.to_string()))
+    }
+
+    pub(crate) fn comment(text: &str) -> Self {
+        MockNode::new(Kind::Comment(text.to_string()))
+    }
+
+    fn new(kind: Kind) -> Self {
+        MockNode(Rc::new(RefCell::new(NodeData {
+            kind,
+            parent: Weak::new(),
+            children: vec![],
+            listeners: vec![],
+            node_refs: vec![],
+        })))
+    }
+
+    /// What kind of node this is.
+    pub fn node_kind(&self) -> NodeKind {
+        match self.0.borrow().kind {
+            Kind::Element { .. } => NodeKind::Element,
+            Kind::Text(_) => NodeKind::Text,
+            Kind::Comment(_) => NodeKind::Comment,
+            Kind::RawHtml(_) => NodeKind::Other,
+        }
+    }
+
+    /// The tag of an element, such as "div".
+    pub fn tag(&self) -> Option<String> {
+        match &self.0.borrow().kind {
+            Kind::Element { tag, .. } => Some(tag.clone()),
+            _ => None,
+        }
+    }
+
+    /// The namespace of an element, such as "http://www.w3.org/2000/svg".
+    pub fn namespace(&self) -> Option<String> {
+        match &self.0.borrow().kind {
+            Kind::Element { namespace, .. } => namespace.clone(),
+            _ => None,
+        }
+    }
+
+    /// The value of an element's attribute.
+    pub fn get_attribute(&self, name: &str) -> Option<String> {
+        match &self.0.borrow().kind {
+            Kind::Element { attrs, .. } => attrs
+                .iter()
+                .find(|attr| attr.name == name)
+                .map(|attr| attr.value.clone()),
+            _ => None,
+        }
+    }
+
+    /// The namespace of an element's attribute, such as "http://www.w3.org/1999/xlink" for
+    /// an `xlink:href` attribute.
+    pub fn get_attribute_namespace(&self, name: &str) -> Option<String> {
+        match &self.0.borrow().kind {
+            Kind::Element { attrs, .. } => attrs
+                .iter()
+                .find(|attr| attr.name == name)
+                .and_then(|attr| attr.namespace.clone()),
+            _ => None,
+        }
+    }
+
+    /// Whether or not a token is in an element's class list.
+    pub fn has_class(&self, token: &str) -> bool {
+        match &self.0.borrow().kind {
+            Kind::Element { classes, .. } => classes.contains(token),
+            _ => false,
+        }
+    }
+
+    /// The value of a property of an element's inline style, such as "width".
+    pub fn style_property(&self, name: &str) -> Option<String> {
+        match &self.0.borrow().kind {
+            Kind::Element { styles, .. } => styles.get(name).map(|value| value.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The text of this node and all of its descendants.
+    pub fn text_content(&self) -> String {
+        let node = self.0.borrow();
+
+        match &node.kind {
+            Kind::Element { .. } => node.children.iter().map(|c| c.text_content()).collect(),
+            Kind::Text(text) => text.clone(),
+            Kind::Comment(_) | Kind::RawHtml(_) => "".to_string(),
+        }
+    }
+
+    /// All of this node's children, including text and comment nodes.
+    pub fn child_nodes(&self) -> Vec<MockNode> {
+        self.0.borrow().children.clone()
+    }
+
+    /// The node that this node is a child of.
+    pub fn parent_node(&self) -> Option<MockNode> {
+        self.0.borrow().parent.upgrade().map(MockNode)
+    }
+
+    /// Find this node or a descendant of it by its `id` attribute.
+    pub fn get_element_by_id(&self, id: &str) -> Option<MockNode> {
+        if self.get_attribute("id").as_ref().map(|i| i.as_str()) == Some(id) {
+            return Some(self.clone());
+        }
+
+        self.child_nodes()
+            .iter()
+            .filter_map(|child| child.get_element_by_id(id))
+            .next()
+    }
+
+    /// Serialize this node and its descendants into HTML, the same way that a browser's
+    /// `outerHTML` does.
+    pub fn outer_html(&self) -> String {
+        let node = self.0.borrow();
+
+        match &node.kind {
+            Kind::Element {
+                tag,
+                attrs,
+                classes,
+                styles,
+                ..
+            } => {
+                let mut html = format!("<{}", tag);
+
+                let mut class = None;
+                let mut style = None;
+
+                for attr in attrs.iter() {
+                    // Just like a browser, the class list and the inline style properties show
+                    // up in the class and style attributes.
+                    if attr.name == "class" && !classes.is_empty() {
+                        class = Some(attr.value.as_str());
+                        continue;
+                    }
+                    if attr.name == "style" && !styles.is_empty() {
+                        style = Some(attr.value.trim_end().trim_end_matches(';'));
+                        continue;
+                    }
+
+                    html += &format!(r#" {}="{}""#, attr.name, escape_attribute(&attr.value));
+                }
+
+                if !classes.is_empty() {
+                    let mut class = Classes::from(class.unwrap_or(""));
+                    class.insert(classes.clone());
+
+                    html += &format!(r#" class="{}""#, escape_attribute(&class.to_string()));
+                }
+
+                if !styles.is_empty() {
+                    let styles = match style {
+                        Some(style) => format!("{}; {}", style, styles),
+                        None => styles.to_string(),
+                    };
+
+                    html += &format!(r#" style="{}""#, escape_attribute(&styles));
+                }
+                html += ">";
+
+                html += &self.inner_html();
+
+                if !html_validation::is_self_closing(tag) {
+                    html += &format!("</{}>", tag);
+                }
+
+                html
+            }
+            Kind::Text(text) => escape_text(text),
+            Kind::Comment(text) => format!("<!--{}-->", text),
+            Kind::RawHtml(html) => html.clone(),
+        }
+    }
+
+    /// Serialize this node's descendants into HTML.
+    pub fn inner_html(&self) -> String {
+        self.0
+            .borrow()
+            .children
+            .iter()
+            .map(|child| child.outer_html())
+            .collect()
+    }
+
+    /// Dispatch an event such as "click" on this node.
+    ///
+    /// The event bubbles up, so the listeners of this node get called first, then the
+    /// listeners of its parent, and so on up to the root node.
+    pub fn dispatch_event(&self, event: &str) {
+        let mut current = Some(self.clone());
+
+        while let Some(node) = current {
+            // Listeners might patch the DOM, so don't hold on to a borrow while calling them.
+            let listeners: Vec<DynClosure> = node
+                .0
+                .borrow()
+                .listeners
+                .iter()
+                .filter(|(name, _)| name == event)
+                .map(|(_, callback)| callback.clone())
+                .collect();
+
+            for listener in listeners {
+                listener.call_native(JsValue::NULL);
+            }
+
+            current = node.parent_node();
+        }
+    }
+
+    /// Whether or not the `NodeRef` points at this element.
+    ///
+    /// `NodeRef::get` only returns browser elements, so this is how tests can tell which
+    /// element a `NodeRef` was set to.
+    pub fn has_node_ref(&self, node_ref: &NodeRef) -> bool {
+        self.0.borrow().node_refs.contains(node_ref)
+    }
+
+    pub(crate) fn set_node_ref(&self, node_ref: &NodeRef) {
+        if !self.has_node_ref(node_ref) {
+            self.0.borrow_mut().node_refs.push(node_ref.clone());
+        }
+    }
+
+    pub(crate) fn clear_node_ref(&self, node_ref: &NodeRef) {
+        self.0
+            .borrow_mut()
+            .node_refs
+            .retain(|existing| existing != node_ref);
+    }
+
+    pub(crate) fn set_attribute(&self, name: &str, value: &str) -> Result<(), MockDomError> {
+        self.set_attribute_ns(None, name, value)
+    }
+
+    pub(crate) fn set_attribute_ns(
+        &self,
+        namespace: Option<&str>,
+        name: &str,
+        value: &str,
+    ) -> Result<(), MockDomError> {
+        match &mut self.0.borrow_mut().kind {
+            Kind::Element { attrs, .. } => {
+                let namespace = namespace.map(|namespace| namespace.to_string());
+
+                match attrs.iter_mut().find(|attr| attr.name == name) {
+                    Some(attr) => {
+                        attr.namespace = namespace;
+                        attr.value = value.to_string();
+                    }
+                    None => attrs.push(Attribute {
+                        name: name.to_string(),
+                        namespace,
+                        value: value.to_string(),
+                    }),
+                };
+
+                Ok(())
+            }
+            _ => Err(MockDomError::NotAnElement),
+        }
+    }
+
+    pub(crate) fn remove_attribute(&self, name: &str) -> Result<(), MockDomError> {
+        match &mut self.0.borrow_mut().kind {
+            Kind::Element { attrs, .. } => {
+                attrs.retain(|attr| attr.name != name);
+                Ok(())
+            }
+            _ => Err(MockDomError::NotAnElement),
+        }
+    }
+
+    pub(crate) fn add_class(&self, token: &str) -> Result<(), MockDomError> {
+        match &mut self.0.borrow_mut().kind {
+            Kind::Element { classes, .. } => {
+                classes.insert(token);
+                Ok(())
+            }
+            _ => Err(MockDomError::NotAnElement),
+        }
+    }
+
+    pub(crate) fn remove_class(&self, token: &str) -> Result<(), MockDomError> {
+        match &mut self.0.borrow_mut().kind {
+            Kind::Element { classes, .. } => {
+                classes.remove(token);
+                Ok(())
+            }
+            _ => Err(MockDomError::NotAnElement),
+        }
+    }
+
+    pub(crate) fn set_style_property(&self, name: &str, value: &str) -> Result<(), MockDomError> {
+        match &mut self.0.borrow_mut().kind {
+            Kind::Element { styles, .. } => {
+                styles.insert(name, value);
+                Ok(())
+            }
+            _ => Err(MockDomError::NotAnElement),
+        }
+    }
+
+    pub(crate) fn remove_style_property(&self, name: &str) -> Result<(), MockDomError> {
+        match &mut self.0.borrow_mut().kind {
+            Kind::Element { styles, .. } => {
+                styles.remove(name);
+                Ok(())
+            }
+            _ => Err(MockDomError::NotAnElement),
+        }
+    }
+
+    pub(crate) fn set_inner_html(&self, html: &str) {
+        for child in self.child_nodes() {
+            child.0.borrow_mut().parent = Weak::new();
+        }
+
+        self.0.borrow_mut().children = vec![];
+        self.append_child(&MockNode::new(Kind::RawHtml(html.to_string())));
+    }
+
+    pub(crate) fn has_inner_html(&self) -> bool {
+        self.0
+            .borrow()
+            .children
+            .iter()
+            .any(|child| match child.0.borrow().kind {
+                Kind::RawHtml(_) => true,
+                _ => false,
+            })
+    }
+
+    pub(crate) fn set_text(&self, new_text: &str) {
+        match &mut self.0.borrow_mut().kind {
+            Kind::Text(text) | Kind::Comment(text) => *text = new_text.to_string(),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn append_child(&self, child: &MockNode) {
+        child.detach();
+
+        child.0.borrow_mut().parent = Rc::downgrade(&self.0);
+        self.0.borrow_mut().children.push(child.clone());
+    }
+
+    pub(crate) fn insert_before(
+        &self,
+        child: &MockNode,
+        reference: &MockNode,
+    ) -> Result<(), MockDomError> {
+        if reference.parent_node().as_ref() != Some(self) {
+            return Err(MockDomError::NotAChild);
+        }
+
+        child.detach();
+
+        let mut data = self.0.borrow_mut();
+        let idx = data
+            .children
+            .iter()
+            .position(|c| c == reference)
+            .ok_or(MockDomError::NotAChild)?;
+        data.children.insert(idx, child.clone());
+        child.0.borrow_mut().parent = Rc::downgrade(&self.0);
+
+        Ok(())
+    }
+
+    /// Just like in the browser, replacing a node that has no parent does nothing.
+    pub(crate) fn replace_with(&self, new: &MockNode) {
+        let parent = match self.parent_node() {
+            Some(parent) => parent,
+            None => return,
+        };
+
+        new.detach();
+
+        let mut parent_data = parent.0.borrow_mut();
+        if let Some(idx) = parent_data.children.iter().position(|c| c == self) {
+            parent_data.children[idx] = new.clone();
+            new.0.borrow_mut().parent = Rc::downgrade(&parent.0);
+            self.0.borrow_mut().parent = Weak::new();
+        }
+    }
+
+    pub(crate) fn remove_child(&self, child: &MockNode) -> Result<(), MockDomError> {
+        if child.parent_node().as_ref() != Some(self) {
+            return Err(MockDomError::NotAChild);
+        }
+
+        child.detach();
+        Ok(())
+    }
+
+    pub(crate) fn add_event_listener(
+        &self,
+        event: &str,
+        callback: &DynClosure,
+    ) -> Result<(), MockDomError> {
+        if self.node_kind() != NodeKind::Element {
+            return Err(MockDomError::NotAnElement);
+        }
+
+        self.0
+            .borrow_mut()
+            .listeners
+            .push((event.to_string(), callback.clone()));
+        Ok(())
+    }
+
+    /// Remove this node from its parent, if it has one.
+    fn detach(&self) {
+        if let Some(parent) = self.parent_node() {
+            parent.0.borrow_mut().children.retain(|c| c != self);
+        }
+
+        self.0.borrow_mut().parent = Weak::new();
+    }
+}
+
+impl PartialEq for MockNode {
+    // Two handles are equal if they point to the same node.
+    fn eq(&self, rhs: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &rhs.0)
+    }
+}
+
+impl fmt::Debug for MockNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MockNode({})", self.outer_html())
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outer_html() {
+        let div = MockNode::element("div", None);
+        div.set_attribute("title", r#"Say "hi""#).unwrap();
+
+        let br = MockNode::element("br", None);
+        div.append_child(&br);
+        div.append_child(&MockNode::text("a < b"));
+        div.append_child(&MockNode::comment("ptns"));
+
+        assert_eq!(
+            div.outer_html(),
+            r#"<div title="Say &quot;hi&quot;"><br>a &lt; b<!--ptns--></div>"#
+        );
+    }
+
+    #[test]
+    fn append_moves_node() {
+        let first = MockNode::element("div", None);
+        let second = MockNode::element("div", None);
+        let child = MockNode::element("span", None);
+
+        first.append_child(&child);
+        second.append_child(&child);
+
+        assert_eq!(first.child_nodes().len(), 0);
+        assert_eq!(child.parent_node(), Some(second));
+    }
+
+    #[test]
+    fn replace_and_remove() {
+        let parent = MockNode::element("div", None);
+        let old = MockNode::element("b", None);
+        let new = MockNode::element("i", None);
+
+        parent.append_child(&old);
+        old.replace_with(&new);
+        assert_eq!(parent.outer_html(), "<div><i></i></div>");
+        assert_eq!(old.parent_node(), None);
+
+        assert_eq!(parent.remove_child(&old), Err(MockDomError::NotAChild));
+        parent.remove_child(&new).unwrap();
+        assert_eq!(parent.outer_html(), "<div></div>");
+    }
+}
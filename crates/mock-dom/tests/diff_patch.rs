@@ -0,0 +1,295 @@
+//! Tests that ensure that diffing and patching work properly in a MockDom.
+//!
+//! These are ported from virtual-dom-rs's browser tests so that they can run natively.
+//!
+//! To run all tests in this file:
+//!
+//! cargo test -p mock-dom --test diff_patch
+
+#![feature(proc_macro_hygiene)]
+
+use mock_dom::MockDom;
+use virtual_dom_rs::prelude::*;
+use virtual_dom_rs::{DomBackend, Patch, PatchOwned};
+
+mod diff_patch_test_case;
+use self::diff_patch_test_case::DiffPatchTest;
+
+#[test]
+fn replace_child() {
+    DiffPatchTest {
+        desc: "Replace a root node attribute attribute and a child text node",
+        old: html! {
+         <div>
+           Original element
+         </div>
+        },
+        new: html! { <div> Patched element</div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn truncate_children() {
+    DiffPatchTest {
+        desc: "Truncates extra children",
+        old: html! {
+         <div>
+           <div> <div> <b></b> <em></em> </div> </div>
+         </div>
+        },
+        new: html! {
+         <div>
+           <div> <div> <b></b> </div> </div>
+         </div>
+        },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "https://github.com/chinedufn/percy/issues/48",
+        old: html! {
+         <div>
+          ab <p></p> c
+         </div>
+        },
+        new: html! {
+         <div>
+           ab <p></p>
+         </div>
+        },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn remove_attributes() {
+    DiffPatchTest {
+        desc: "Removes attributes",
+        old: html! { <div style=""> </div>
+        },
+        new: html! { <div></div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn classes() {
+    DiffPatchTest {
+        desc: "Adds and removes individual class tokens",
+        old: html! { <div class="btn big"></div> },
+        new: html! { <div class="btn active"></div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn keeps_classes_added_by_others() {
+    let old = html! { <div class="btn"></div> };
+    let new = html! { <div class=classes!["btn", "active" => true]></div> };
+
+    let root_node = old.create_dom_node_with(&MockDom).unwrap().node;
+
+    // Something else adds a class through `classList`
+    MockDom.add_class(&root_node, "focus-ring").unwrap();
+
+    let patches = virtual_dom_rs::diff(&old, &new);
+    virtual_dom_rs::patch_with(&MockDom, root_node.clone(), &patches).unwrap();
+
+    assert!(root_node.has_class("active"));
+    assert!(root_node.has_class("focus-ring"));
+}
+
+#[test]
+fn style_properties() {
+    DiffPatchTest {
+        desc: "Sets and removes individual style properties",
+        old: html! { <div style={ ("width", "10px"), ("color", "red") }></div> },
+        new: html! { <div style={ ("width", "20px"), ("top", "0") }></div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn inner_html() {
+    DiffPatchTest {
+        desc: "Replaces changed raw HTML and patches the nodes after it",
+        old: html! { <div><div unsafe_inner_html="<b>Old</b><em></em>"></div><span id="a"></span></div> },
+        new: html! { <div><div unsafe_inner_html="<i>New</i>"></div><span id="b"></span></div> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Switches between raw HTML and children",
+        old: html! { <div><p unsafe_inner_html="<b>Raw</b>"></p><p><em></em></p></div> },
+        new: html! { <div><p><b>Children</b></p><p unsafe_inner_html="<em></em>"></p></div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn keeps_style_properties_set_by_others() {
+    let old = html! { <div style={ ("width", "10px") }></div> };
+    let new = html! { <div style={ ("width", "20px") }></div> };
+
+    let root_node = old.create_dom_node_with(&MockDom).unwrap().node;
+
+    // Something like an animation library sets an inline style of its own
+    MockDom
+        .set_style_property(&root_node, "transform", "scale(2)")
+        .unwrap();
+
+    let patches = virtual_dom_rs::diff(&old, &new);
+    virtual_dom_rs::patch_with(&MockDom, root_node.clone(), &patches).unwrap();
+
+    assert_eq!(root_node.style_property("width"), Some("20px".to_string()));
+    assert_eq!(
+        root_node.style_property("transform"),
+        Some("scale(2)".to_string())
+    );
+}
+
+#[test]
+fn append_children() {
+    DiffPatchTest {
+        desc: "Append a child node",
+        old: html! { <div> </div>
+        },
+        new: html! { <div> <span></span> </div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn text_node_siblings() {
+    // NOTE: Since there are two text nodes next to eachother we expect a `<!--ptns-->` separator in
+    // between them.
+    // @see virtual_node/mod.rs -> create_dom_node() for more information
+    // TODO: A little more spacing than there should be in between the text nodes ... but doesn't
+    // impact the user experience so we can look into that later..
+    let override_expected = Some(
+        r#"<div id="after"><span> The button has been clicked:  <!--ptns--> world </span></div>"#,
+    );
+
+    let old1 = VirtualNode::text("The button has been clicked: ");
+    let old2 = VirtualNode::text("hello");
+
+    let new1 = VirtualNode::text("The button has been clicked: ");
+    let new2 = VirtualNode::text("world");
+
+    DiffPatchTest {
+        desc: "Diff patch on text node siblings",
+        old: html! {
+        <div id="before">
+            <span> { {old1} {old2} } </span>
+        </div>
+        },
+        new: html! {
+        <div id="after">
+            <span> { {new1} {new2} } </span>
+        </div>
+        },
+        override_expected,
+    }
+    .test();
+}
+
+#[test]
+fn append_text_node() {
+    DiffPatchTest {
+        desc: "Append text node",
+        old: html! { <div> </div> },
+        new: html! { <div> Hello </div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn append_sibling_text_nodes() {
+    let text1 = VirtualNode::text("Hello");
+    let text2 = VirtualNode::text("World");
+
+    DiffPatchTest {
+        desc: "Append sibling text nodes",
+        old: html! { <div> </div> },
+        new: html! { <div> {text1} {text2} </div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn replace_with_children() {
+    DiffPatchTest {
+        desc: "Replace node that has children",
+        old: html! { <table><tr><th>0</th></tr><tr><td>1</td></tr></table> },
+        new: html! { <table><tr><td>2</td></tr><tr><th>3</th></tr></table> },
+        override_expected: None,
+    }
+    .test();
+}
+
+// https://github.com/chinedufn/percy/issues/62
+#[test]
+fn replace_element_with_text_node() {
+    DiffPatchTest {
+        desc: "#62: Replace element with text node",
+        old: html! { <span> <br> </span> },
+        new: html! { <span> a </span> },
+        override_expected: None,
+    }
+    .test();
+}
+
+// https://github.com/chinedufn/percy/issues/68
+#[test]
+fn text_root_node() {
+    DiffPatchTest {
+        desc: "Patching of text root node works",
+        old: html! { Old text },
+        new: html! { New text },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn replace_text_with_element() {
+    DiffPatchTest {
+        desc: "Replacing a text node with an element works",
+        old: html! { <div>a</div> },
+        new: html! { <div><br></div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+#[test]
+fn apply_owned_patches() {
+    let old = html! { <div id="old"> <b>Hello</b> <em></em> </div> };
+    let root_node = old.create_dom_node_with(&MockDom).unwrap().node;
+
+    // The owned patches can be applied after the virtual doms were dropped
+    let patches: Vec<PatchOwned> = {
+        let new = html! { <div class="new"> <i></i> </div> };
+        virtual_dom_rs::diff(&old, &new)
+            .into_iter()
+            .map(Patch::into_owned)
+            .collect()
+    };
+    drop(old);
+
+    virtual_dom_rs::patch_with(&MockDom, root_node.clone(), &patches).unwrap();
+
+    assert_eq!(root_node.outer_html(), r#"<div class="new"><i></i></div>"#);
+}
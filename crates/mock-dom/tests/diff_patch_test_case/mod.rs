@@ -0,0 +1,43 @@
+//! Kept in its own file to mirror virtual-dom-rs's browser tests
+
+use mock_dom::MockDom;
+use virtual_dom_rs::prelude::*;
+
+/// A test case that both diffing and patching are working in a MockDom
+pub struct DiffPatchTest<'a> {
+    /// Description of the test case.
+    pub desc: &'static str,
+    /// The old virtual node.
+    pub old: VirtualNode,
+    /// The new virtual node.
+    pub new: VirtualNode,
+    /// By default we generate the expected based on `new.to_string()`. You can
+    /// use this field to override the expected HTML after patching.
+    pub override_expected: Option<&'a str>,
+}
+
+impl<'a> DiffPatchTest<'a> {
+    pub fn test(&mut self) {
+        // Create a DOM node of the virtual root node
+        let root_node = self.old.create_dom_node_with(&MockDom).unwrap().node;
+
+        // Generate patches
+        let patches = virtual_dom_rs::diff(&self.old, &self.new);
+
+        // Patch our root node. It should now look like `self.new`
+        virtual_dom_rs::patch_with(&MockDom, root_node.clone(), &patches).unwrap();
+
+        // Determine the expected outer HTML
+        let expected_outer_html = match self.override_expected {
+            Some(ref expected) => expected.to_string(),
+            None => self.new.to_string(),
+        };
+
+        assert_eq!(
+            &root_node.outer_html(),
+            &expected_outer_html,
+            "{}",
+            self.desc
+        );
+    }
+}
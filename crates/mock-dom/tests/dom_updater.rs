@@ -0,0 +1,400 @@
+//! Ensure that our DomUpdater maintains Rc's to closures so that they work even
+//! after dropping virtual dom nodes.
+//!
+//! To run all tests in this file:
+//!
+//! cargo test -p mock-dom --test dom_updater
+
+#![feature(proc_macro_hygiene)]
+
+use mock_dom::MockDom;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use virtual_dom_rs::prelude::*;
+use virtual_dom_rs::{
+    DiffBudget, DomBackend, DomUpdater, PatchError, RecoveryPolicy, UpdateProgress,
+};
+use web_sys::{Element, Event};
+
+// Verify that our DomUpdater's patch method works.
+// We test a simple case here, since diff_patch.rs is responsible for testing more complex
+// diffing and patching.
+#[test]
+fn patches_dom() {
+    let vdom = html! { <div></div> };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, vdom);
+
+    let new_vdom = html! { <div id="patched"></div> };
+    dom_updater.update(new_vdom).unwrap();
+
+    assert!(dom_updater
+        .root_node()
+        .get_element_by_id("patched")
+        .is_some());
+}
+
+// When you replace a DOM node with another DOM node we need to make sure that the closures
+// from the new DOM node are stored by the DomUpdater otherwise they'll get dropped and
+// won't work.
+#[test]
+fn updates_active_closure_on_replace() {
+    let wrapper = html! { <div> <div></div> </div> };
+    let mut dom_updater = DomUpdater::with_backend(MockDom, wrapper);
+
+    let inputs = Rc::new(Cell::new(0));
+    let inputs_clone = Rc::clone(&inputs);
+
+    let id = "update-active-closures-on-replace";
+
+    {
+        let replace_node = html! {
+        <div>
+           <input
+              id=id
+              oninput=move |_event: Event| {
+                 inputs_clone.set(inputs_clone.get() + 1);
+              }
+           >
+        </div>
+        };
+
+        // New node replaces old node.
+        // We are testing that we've stored this new node's closures even though `new` will be dropped
+        // at the end of this block.
+        dom_updater.update(replace_node).unwrap();
+    }
+
+    let input = dom_updater.root_node().get_element_by_id(id).unwrap();
+    input.dispatch_event("input");
+
+    assert_eq!(inputs.get(), 1);
+}
+
+// When you append a DOM node we need to make sure that the closures from the new DOM node
+// are stored by the DomUpdater otherwise they'll get dropped and won't work.
+#[test]
+fn updates_active_closures_on_append() {
+    let old = html! { <div> </div> };
+    let mut dom_updater = DomUpdater::with_backend(MockDom, old);
+
+    let inputs = Rc::new(Cell::new(0));
+    let inputs_clone = Rc::clone(&inputs);
+
+    let id = "update-active-closures-on-append";
+
+    {
+        let append_node = html! {
+        <div>
+           <input
+              id=id
+              oninput=move |_event: Event| {
+                 inputs_clone.set(inputs_clone.get() + 1);
+              }
+           >
+         </div>
+        };
+
+        // New node gets appended into the DOM.
+        // We are testing that we've stored this new node's closures even though `new` will be dropped
+        // at the end of this block.
+        dom_updater.update(append_node).unwrap();
+    }
+
+    let input = dom_updater.root_node().get_element_by_id(id).unwrap();
+    input.dispatch_event("input");
+
+    assert_eq!(inputs.get(), 1);
+}
+
+// A third party script removed one of our nodes, so there is nothing to patch at its index.
+#[test]
+fn returns_patch_errors() {
+    let mut dom_updater = DomUpdater::with_backend(MockDom, html! { <div> <b></b> </div> });
+
+    let root_node = dom_updater.root_node();
+    let bold = root_node.child_nodes()[0].clone();
+    MockDom.remove_child(&root_node, &bold).unwrap();
+
+    let err = dom_updater
+        .update(html! { <div> <b id="patched"></b> </div> })
+        .unwrap_err();
+
+    assert_eq!(err, PatchError::MissingNode(1));
+}
+
+// Instead of returning an error the DomUpdater can re-create the root node from the new
+// virtual dom.
+#[test]
+fn recreates_root_after_patch_error() {
+    let mount = MockDom.create_element("body").unwrap();
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, html! { <div> <b></b> </div> });
+    dom_updater.set_recovery_policy(RecoveryPolicy::RecreateRoot);
+    MockDom
+        .append_child(&mount, &dom_updater.root_node())
+        .unwrap();
+
+    // A browser extension replaced our element with a text node
+    let root_node = dom_updater.root_node();
+    let bold = root_node.child_nodes()[0].clone();
+    MockDom
+        .replace_with(&bold, &MockDom.create_text_node("Ad"))
+        .unwrap();
+
+    dom_updater
+        .update(html! { <div> <b id="patched"></b> </div> })
+        .unwrap();
+
+    assert_eq!(mount.inner_html(), r#"<div><b id="patched"></b></div>"#);
+    assert_eq!(mount.child_nodes(), vec![dom_updater.root_node()]);
+}
+
+// A root node that isn't within a parent can't be replaced by a new one.
+#[test]
+fn recreate_detached_root() {
+    let mut dom_updater = DomUpdater::with_backend(MockDom, html! { <div> <b></b> </div> });
+    let root_node = dom_updater.root_node();
+
+    assert_eq!(dom_updater.recreate_root(), Err(PatchError::DetachedRoot));
+    assert!(dom_updater.root_node() == root_node);
+
+    dom_updater
+        .update(html! { <div> <b id="patched"></b> </div> })
+        .unwrap();
+    assert_eq!(root_node.outer_html(), r#"<div><b id="patched"></b></div>"#);
+}
+
+// When the root element's tag changes the root node gets replaced, and every later update
+// needs to patch the new root node.
+#[test]
+fn replaces_root_node() {
+    let mount = MockDom.create_element("body").unwrap();
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, html! { <div></div> });
+    MockDom
+        .append_child(&mount, &dom_updater.root_node())
+        .unwrap();
+
+    dom_updater.update(html! { <span></span> }).unwrap();
+    dom_updater
+        .update(html! { <span id="patched"></span> })
+        .unwrap();
+
+    assert_eq!(mount.inner_html(), r#"<span id="patched"></span>"#);
+    assert_eq!(mount.child_nodes(), vec![dom_updater.root_node()]);
+}
+
+#[test]
+fn text_root_node() {
+    let mount = MockDom.create_element("body").unwrap();
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, VirtualNode::text("Hello"));
+    MockDom
+        .append_child(&mount, &dom_updater.root_node())
+        .unwrap();
+
+    dom_updater.update(VirtualNode::text("World")).unwrap();
+    assert_eq!(mount.inner_html(), "World");
+
+    dom_updater.update(html! { <b>Bold</b> }).unwrap();
+    assert_eq!(mount.inner_html(), "<b>Bold</b>");
+
+    dom_updater.update(VirtualNode::text("Text again")).unwrap();
+    assert_eq!(mount.inner_html(), "Text again");
+    assert_eq!(mount.child_nodes(), vec![dom_updater.root_node()]);
+}
+
+// A fragment root renders several nodes right into the mount element, next to whatever
+// else is in there.
+#[test]
+fn fragment_root() {
+    let mount = MockDom.create_element("main").unwrap();
+    let header = MockDom.create_element("header").unwrap();
+    MockDom.append_child(&mount, &header).unwrap();
+
+    let fragment = |count: u32| -> Vec<VirtualNode> {
+        (0..count)
+            .map(|n| html! { <p>{ n.to_string() }</p> })
+            .collect()
+    };
+
+    let mut dom_updater = DomUpdater::with_backend_fragment(MockDom, fragment(2), &mount);
+
+    let footer = MockDom.create_element("footer").unwrap();
+    MockDom.append_child(&mount, &footer).unwrap();
+
+    assert_eq!(
+        mount.inner_html(),
+        "<header></header><p>0</p><p>1</p><!--ptfe--><footer></footer>"
+    );
+
+    dom_updater.update_fragment(fragment(3)).unwrap();
+    assert_eq!(
+        mount.inner_html(),
+        "<header></header><p>0</p><p>1</p><p>2</p><!--ptfe--><footer></footer>"
+    );
+
+    dom_updater.update_fragment(fragment(1)).unwrap();
+    assert_eq!(
+        mount.inner_html(),
+        "<header></header><p>0</p><!--ptfe--><footer></footer>"
+    );
+    assert_eq!(dom_updater.root_nodes(), mount.child_nodes()[1..2].to_vec());
+
+    dom_updater.update_fragment(vec![]).unwrap();
+    assert_eq!(
+        mount.inner_html(),
+        "<header></header><!--ptfe--><footer></footer>"
+    );
+    assert_eq!(dom_updater.root_nodes(), vec![]);
+
+    dom_updater
+        .update_fragment(vec![VirtualNode::text("Hello"), html! { <b>World</b> }])
+        .unwrap();
+    assert_eq!(
+        mount.inner_html(),
+        "<header></header>Hello<b>World</b><!--ptfe--><footer></footer>"
+    );
+}
+
+// Root nodes within a fragment get patched and replaced just like a single root node.
+#[test]
+fn patches_and_replaces_fragment_root_nodes() {
+    let mount = MockDom.create_element("body").unwrap();
+
+    let mut dom_updater = DomUpdater::with_backend_fragment(
+        MockDom,
+        vec![html! { <div><em>A</em></div> }, html! { <span></span> }],
+        &mount,
+    );
+
+    dom_updater
+        .update_fragment(vec![
+            html! { <div id="patched"><em>B</em></div> },
+            html! { <p></p> },
+        ])
+        .unwrap();
+    assert_eq!(
+        mount.inner_html(),
+        r#"<div id="patched"><em>B</em></div><p></p><!--ptfe-->"#
+    );
+
+    dom_updater
+        .update_fragment(vec![html! { <div></div> }, html! { <p id="patched"></p> }])
+        .unwrap();
+    assert_eq!(
+        mount.inner_html(),
+        r#"<div></div><p id="patched"></p><!--ptfe-->"#
+    );
+    assert_eq!(dom_updater.root_nodes(), mount.child_nodes()[0..2].to_vec());
+
+    dom_updater.recreate_root().unwrap();
+    assert_eq!(
+        mount.inner_html(),
+        r#"<div></div><p id="patched"></p><!--ptfe-->"#
+    );
+    assert_eq!(dom_updater.root_nodes(), mount.child_nodes()[0..2].to_vec());
+}
+
+fn list(items: &[&str]) -> VirtualNode {
+    let items: Vec<VirtualNode> = items
+        .iter()
+        .map(|item| html! { <li>{ item.to_string() }</li> })
+        .collect();
+    html! { <ul>{ items }</ul> }
+}
+
+// The DOM doesn't get touched until the whole diff is done, then every patch gets applied at
+// once.
+#[test]
+fn update_in_slices() {
+    let mut dom_updater = DomUpdater::with_backend(MockDom, list(&["a", "b", "c"]));
+    let root_node = dom_updater.root_node();
+
+    let progress = dom_updater
+        .update_in_slices(list(&["a", "B", "C"]), DiffBudget::Nodes(2))
+        .unwrap();
+    assert_eq!(progress, UpdateProgress::Pending);
+
+    let mut slices = 1;
+    loop {
+        assert_eq!(root_node.outer_html(), "<ul><li>a</li><li>b</li><li>c</li></ul>");
+
+        slices += 1;
+        if dom_updater.continue_update().unwrap() == UpdateProgress::Done {
+            break;
+        }
+    }
+
+    // 7 nodes, 2 per slice
+    assert_eq!(slices, 4);
+    assert!(!dom_updater.has_pending_update());
+    assert_eq!(root_node.outer_html(), "<ul><li>a</li><li>B</li><li>C</li></ul>");
+}
+
+// A newer update wins over one that is still being diffed.
+#[test]
+fn newer_update_discards_pending_update() {
+    let mut dom_updater = DomUpdater::with_backend(MockDom, list(&["a", "b"]));
+    let root_node = dom_updater.root_node();
+
+    dom_updater
+        .update_in_slices(list(&["stale"]), DiffBudget::Nodes(1))
+        .unwrap();
+    let progress = dom_updater
+        .update_in_slices(list(&["x", "y", "z"]), DiffBudget::Nodes(100))
+        .unwrap();
+    assert_eq!(progress, UpdateProgress::Done);
+    assert_eq!(root_node.outer_html(), "<ul><li>x</li><li>y</li><li>z</li></ul>");
+
+    dom_updater
+        .update_in_slices(list(&["stale"]), DiffBudget::Nodes(1))
+        .unwrap();
+    dom_updater.update(list(&["x"])).unwrap();
+    assert!(!dom_updater.has_pending_update());
+
+    assert_eq!(dom_updater.continue_update().unwrap(), UpdateProgress::Done);
+    assert_eq!(root_node.outer_html(), "<ul><li>x</li></ul>");
+}
+
+// Element hooks get called and node refs get set and cleared, just like in the browser.
+// on_update_elem only gets called when the attributes change.
+#[test]
+fn elem_hooks_and_node_refs() {
+    let calls: Rc<RefCell<Vec<&str>>> = Rc::new(RefCell::new(vec![]));
+    let input_ref = NodeRef::new();
+
+    let view = |value: Option<&str>| {
+        let value = match value {
+            Some(value) => value.to_string(),
+            None => return html! { <div></div> },
+        };
+
+        let update_calls = Rc::clone(&calls);
+        let remove_calls = Rc::clone(&calls);
+
+        html! {
+          <div>
+            <input
+              id="input"
+              value=value
+              ref=input_ref
+              on_update_elem=move |_elem: Element| { update_calls.borrow_mut().push("update"); }
+              on_remove_elem=move |_elem: Element| { remove_calls.borrow_mut().push("remove"); }
+            />
+          </div>
+        }
+    };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, view(Some("a")));
+    let input = dom_updater.root_node().get_element_by_id("input").unwrap();
+    assert!(input.has_node_ref(&input_ref));
+
+    dom_updater.update(view(Some("b"))).unwrap();
+    assert_eq!(&*calls.borrow(), &["update"]);
+
+    dom_updater.update(view(None)).unwrap();
+    assert_eq!(&*calls.borrow(), &["update", "remove"]);
+    assert!(!input.has_node_ref(&input_ref));
+}
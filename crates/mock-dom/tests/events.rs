@@ -0,0 +1,92 @@
+//! Ensure that event handlers can be triggered in a MockDom.
+//!
+//! To run all tests in this file:
+//!
+//! cargo test -p mock-dom --test events
+
+#![feature(proc_macro_hygiene)]
+
+use mock_dom::MockDom;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use virtual_dom_rs::prelude::*;
+use virtual_dom_rs::DomUpdater;
+use web_sys::MouseEvent;
+
+// Make sure that we successfully attach an event listener and see it work.
+#[test]
+fn on_click() {
+    let clicks = Rc::new(Cell::new(0));
+    let clicks_clone = Rc::clone(&clicks);
+
+    let button = html! {
+      <button onclick=move |_event: MouseEvent| { clicks_clone.set(clicks_clone.get() + 1); }>
+        Click me
+      </button>
+    };
+    let button = button.create_dom_node_with(&MockDom).unwrap().node;
+
+    button.dispatch_event("click");
+    button.dispatch_event("click");
+
+    assert_eq!(clicks.get(), 2);
+}
+
+// Events bubble up from the node that they were dispatched on to its ancestors.
+#[test]
+fn events_bubble() {
+    let order = Rc::new(RefCell::new(vec![]));
+    let outer_order = Rc::clone(&order);
+    let inner_order = Rc::clone(&order);
+
+    let vdom = html! {
+      <div onclick=move |_event: MouseEvent| { outer_order.borrow_mut().push("outer"); }>
+        <span id="inner" onclick=move |_event: MouseEvent| { inner_order.borrow_mut().push("inner"); }>
+        </span>
+      </div>
+    };
+    let div = vdom.create_dom_node_with(&MockDom).unwrap().node;
+
+    div.get_element_by_id("inner")
+        .unwrap()
+        .dispatch_event("click");
+
+    assert_eq!(&*order.borrow(), &["inner", "outer"]);
+}
+
+// Mount a component's view, click it and then assert on the re-rendered DOM.
+#[test]
+fn click_and_rerender_component() {
+    let count = Rc::new(Cell::new(0));
+
+    let view = |count: &Rc<Cell<u32>>| {
+        let count_clone = Rc::clone(count);
+
+        html! {
+          <div>
+            <button id="increment" onclick=move |_event: MouseEvent| { count_clone.set(count_clone.get() + 1); }>
+              Increment
+            </button>
+            <span id="count">{ count.get().to_string() }</span>
+          </div>
+        }
+    };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, view(&count));
+    let root_node = dom_updater.root_node();
+    assert_eq!(
+        root_node.get_element_by_id("count").unwrap().outer_html(),
+        r#"<span id="count">0</span>"#
+    );
+
+    root_node
+        .get_element_by_id("increment")
+        .unwrap()
+        .dispatch_event("click");
+    dom_updater.update(view(&count)).unwrap();
+
+    assert_eq!(
+        root_node.get_element_by_id("count").unwrap().outer_html(),
+        r#"<span id="count">1</span>"#
+    );
+}
@@ -0,0 +1,185 @@
+//! Ensure that elements get created in the namespace of their parent, so that everything
+//! within an `<svg>` is an SVG element.
+//!
+//! To run all tests in this file:
+//!
+//! cargo test -p mock-dom --test namespaces
+
+#![feature(proc_macro_hygiene)]
+
+use html_validation::{MATHML_NAMESPACE, SVG_NAMESPACE, XLINK_NAMESPACE};
+use mock_dom::{MockDom, MockNode};
+use virtual_dom_rs::prelude::*;
+use virtual_dom_rs::{DomUpdater, VElement};
+
+#[test]
+fn svg_children_inherit_svg_namespace() {
+    let root = create(html! { <div> <svg> <a> <title>Link</title> </a> </svg> </div> });
+
+    let svg = root.child_nodes()[0].clone();
+    let a = svg.child_nodes()[0].clone();
+    let title = a.child_nodes()[0].clone();
+
+    assert_eq!(root.namespace(), None);
+    assert_eq!(
+        svg.namespace().as_ref().map(|n| n.as_str()),
+        Some(SVG_NAMESPACE)
+    );
+    assert_eq!(
+        a.namespace().as_ref().map(|n| n.as_str()),
+        Some(SVG_NAMESPACE)
+    );
+    assert_eq!(
+        title.namespace().as_ref().map(|n| n.as_str()),
+        Some(SVG_NAMESPACE)
+    );
+}
+
+#[test]
+fn foreign_object_children_are_html() {
+    let root = create(html! {
+      <svg> <foreignObject> <div> <span></span> </div> </foreignObject> </svg>
+    });
+
+    let foreign_object = root.child_nodes()[0].clone();
+    let div = foreign_object.child_nodes()[0].clone();
+
+    assert_eq!(
+        foreign_object.namespace().as_ref().map(|n| n.as_str()),
+        Some(SVG_NAMESPACE)
+    );
+    assert_eq!(div.namespace(), None);
+    assert_eq!(div.child_nodes()[0].namespace(), None);
+}
+
+#[test]
+fn mathml_children_inherit_mathml_namespace() {
+    let root = create(html! { <math> <mrow> <mtext> <b></b> </mtext> </mrow> </math> });
+
+    let mrow = root.child_nodes()[0].clone();
+    let mtext = mrow.child_nodes()[0].clone();
+
+    assert_eq!(
+        root.namespace().as_ref().map(|n| n.as_str()),
+        Some(MATHML_NAMESPACE)
+    );
+    assert_eq!(
+        mrow.namespace().as_ref().map(|n| n.as_str()),
+        Some(MATHML_NAMESPACE)
+    );
+    assert_eq!(
+        mtext.namespace().as_ref().map(|n| n.as_str()),
+        Some(MATHML_NAMESPACE)
+    );
+    assert_eq!(mtext.child_nodes()[0].namespace(), None);
+}
+
+#[test]
+fn xlink_attributes_are_namespaced() {
+    let mut image = VElement::new("image");
+    image
+        .attrs
+        .insert("xlink:href".to_string(), "#icon".to_string());
+    image.attrs.insert("x".to_string(), "5".to_string());
+
+    let mut svg = VElement::new("svg");
+    svg.children.push(image.into());
+
+    let root = create(svg.into());
+    let image = root.child_nodes()[0].clone();
+
+    assert_eq!(
+        image
+            .get_attribute_namespace("xlink:href")
+            .as_ref()
+            .map(|n| n.as_str()),
+        Some(XLINK_NAMESPACE)
+    );
+    assert_eq!(image.get_attribute_namespace("x"), None);
+}
+
+#[test]
+fn patches_set_xlink_attributes_within_namespace() {
+    let old = html! { <svg> <image /> </svg> };
+
+    let mut image = VElement::new("image");
+    image
+        .attrs
+        .insert("xlink:href".to_string(), "#icon".to_string());
+    let mut new = VElement::new("svg");
+    new.children.push(image.into());
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, old);
+    dom_updater.update(new.into()).unwrap();
+
+    let image = dom_updater.root_node().child_nodes()[0].clone();
+    assert_eq!(
+        image
+            .get_attribute_namespace("xlink:href")
+            .as_ref()
+            .map(|n| n.as_str()),
+        Some(XLINK_NAMESPACE)
+    );
+}
+
+#[test]
+fn appended_children_inherit_parent_namespace() {
+    let old = html! { <svg> <g></g> </svg> };
+    let new = html! { <svg> <g> <a></a> </g> <a></a> </svg> };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, old);
+    dom_updater.update(new).unwrap();
+
+    let svg = dom_updater.root_node();
+    let g = svg.child_nodes()[0].clone();
+
+    assert_eq!(
+        g.child_nodes()[0].namespace().as_ref().map(|n| n.as_str()),
+        Some(SVG_NAMESPACE)
+    );
+    assert_eq!(
+        svg.child_nodes()[1]
+            .namespace()
+            .as_ref()
+            .map(|n| n.as_str()),
+        Some(SVG_NAMESPACE)
+    );
+}
+
+#[test]
+fn replaced_nodes_inherit_parent_namespace() {
+    let old = html! { <div> <svg> <g></g> </svg> <span></span> </div> };
+    let new = html! { <div> <svg> <a></a> </svg> <a></a> </div> };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, old);
+    dom_updater.update(new).unwrap();
+
+    let root = dom_updater.root_node();
+    let svg = root.child_nodes()[0].clone();
+
+    assert_eq!(
+        svg.child_nodes()[0]
+            .namespace()
+            .as_ref()
+            .map(|n| n.as_str()),
+        Some(SVG_NAMESPACE)
+    );
+    assert_eq!(root.child_nodes()[1].namespace(), None);
+}
+
+#[test]
+fn foreign_object_appended_children_are_html() {
+    let old = html! { <svg> <foreignObject></foreignObject> </svg> };
+    let new = html! { <svg> <foreignObject> <a></a> </foreignObject> </svg> };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, old);
+    dom_updater.update(new).unwrap();
+
+    let foreign_object = dom_updater.root_node().child_nodes()[0].clone();
+
+    assert_eq!(foreign_object.child_nodes()[0].namespace(), None);
+}
+
+fn create(vdom: VirtualNode) -> MockNode {
+    vdom.create_dom_node_with(&MockDom).unwrap().node
+}
@@ -0,0 +1,166 @@
+//! Ensure that the DomUpdater creates, patches and removes the children of portals within
+//! their targets.
+//!
+//! To run all tests in this file:
+//!
+//! cargo test -p mock-dom --test portals
+
+#![feature(proc_macro_hygiene)]
+
+use mock_dom::MockDom;
+use std::cell::Cell;
+use std::rc::Rc;
+use virtual_dom_rs::prelude::*;
+use virtual_dom_rs::{DomBackend, DomUpdater, PatchError};
+use web_sys::MouseEvent;
+
+fn modal(text: &str) -> VirtualNode {
+    html! {
+    <div>
+      <button>Open</button>
+      { VirtualNode::portal("body", html! { <div class="modal">{ text }</div> }) }
+    </div>
+    }
+}
+
+#[test]
+fn creates_children_within_target() {
+    let dom_updater = DomUpdater::with_backend(MockDom, modal("Hello"));
+
+    assert_eq!(
+        dom_updater.root_node().outer_html(),
+        "<div><button>Open</button></div>"
+    );
+    assert_eq!(
+        MockDom::document().inner_html(),
+        r#"<div class="modal">Hello</div>"#
+    );
+}
+
+#[test]
+fn patches_children_within_target() {
+    let mut dom_updater = DomUpdater::with_backend(MockDom, modal("Hello"));
+
+    dom_updater.update(modal("World")).unwrap();
+
+    assert_eq!(
+        MockDom::document().inner_html(),
+        r#"<div class="modal">World</div>"#
+    );
+
+    // Replacing the portal's root node keeps it patchable
+    dom_updater
+        .update(html! { <div> { VirtualNode::portal("body", html! { <span>1</span> }) } </div> })
+        .unwrap();
+    dom_updater
+        .update(html! { <div> { VirtualNode::portal("body", html! { <span>2</span> }) } </div> })
+        .unwrap();
+
+    assert_eq!(MockDom::document().inner_html(), "<span>2</span>");
+}
+
+#[test]
+fn keeps_placeholder_when_children_become_empty() {
+    let portal = |text: Option<&str>| html! { <div> { VirtualNode::portal("body", text) } </div> };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, portal(Some("Toast")));
+    let placeholder = dom_updater.root_node().child_nodes()[0].clone();
+
+    dom_updater.update(portal(None)).unwrap();
+    assert_eq!(MockDom::document().inner_html(), "");
+
+    dom_updater.update(portal(Some("Toast"))).unwrap();
+    assert_eq!(MockDom::document().inner_html(), "Toast");
+
+    assert!(dom_updater.root_node().child_nodes()[0] == placeholder);
+}
+
+#[test]
+fn removes_children_when_portal_is_removed() {
+    let mut dom_updater = DomUpdater::with_backend(MockDom, modal("Hello"));
+
+    dom_updater
+        .update(html! { <div> <button>Open</button> </div> })
+        .unwrap();
+
+    assert_eq!(MockDom::document().inner_html(), "");
+}
+
+#[test]
+fn moves_children_to_new_target() {
+    let overlay = MockDom.create_element("div").unwrap();
+    MockDom.set_attribute(&overlay, "id", "overlay").unwrap();
+    MockDom.append_child(&MockDom::document(), &overlay).unwrap();
+
+    let portal = |target: &str| html! { <div> { VirtualNode::portal(target, "Toast") } </div> };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, portal("#overlay"));
+    assert_eq!(overlay.inner_html(), "Toast");
+
+    dom_updater.update(portal("body")).unwrap();
+
+    assert_eq!(overlay.inner_html(), "");
+    assert_eq!(
+        MockDom::document().inner_html(),
+        r#"<div id="overlay"></div>Toast"#
+    );
+}
+
+#[test]
+fn tracks_closures_of_children() {
+    let clicks = Rc::new(Cell::new(0));
+    let clicks_clone = Rc::clone(&clicks);
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, modal("Hello"));
+
+    {
+        let close = html! {
+          <button id="close" onclick=move |_event: MouseEvent| { clicks_clone.set(clicks_clone.get() + 1); }>
+            Close
+          </button>
+        };
+
+        dom_updater
+            .update(html! { <div> { VirtualNode::portal("body", close) } </div> })
+            .unwrap();
+    }
+
+    let close = MockDom::document().get_element_by_id("close").unwrap();
+    close.dispatch_event("click");
+
+    assert_eq!(clicks.get(), 1);
+}
+
+#[test]
+fn nested_portals() {
+    let nested = |text: &str| {
+        html! {
+        <div>
+          {
+            VirtualNode::portal("body", html! {
+              <div id="outer">{ VirtualNode::portal("#outer", text) }</div>
+            })
+          }
+        </div>
+        }
+    };
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, nested("Hello"));
+    dom_updater.update(nested("World")).unwrap();
+
+    assert_eq!(
+        MockDom::document().inner_html(),
+        r#"<div id="outer">World</div>"#
+    );
+}
+
+#[test]
+fn missing_target() {
+    let mut dom_updater = DomUpdater::with_backend(MockDom, html! { <div></div> });
+
+    let err = dom_updater
+        .update(html! { <div> { VirtualNode::portal("#missing", "Hi") } </div> })
+        .unwrap_err();
+
+    assert_eq!(err, PatchError::MissingPortalTarget("#missing".to_string()));
+}
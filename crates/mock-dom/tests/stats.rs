@@ -0,0 +1,88 @@
+//! Ensure that DomUpdater reports stats about its updates.
+//!
+//! To run all tests in this file:
+//!
+//! cargo test -p mock-dom --features mock-dom/stats --test stats
+
+#![cfg(feature = "stats")]
+#![feature(proc_macro_hygiene)]
+
+use mock_dom::MockDom;
+use std::cell::RefCell;
+use std::rc::Rc;
+use virtual_dom_rs::prelude::*;
+use virtual_dom_rs::stats::{PatchCounts, UpdateStats};
+use virtual_dom_rs::{DiffBudget, DomUpdater, UpdateProgress};
+
+#[test]
+fn update_with_stats() {
+    let mut dom_updater = DomUpdater::with_backend(
+        MockDom,
+        html! { <ul> <li>1</li> <li class="old">2</li> <li>3</li> </ul> },
+    );
+
+    let stats = dom_updater
+        .update_with_stats(html! { <ul> <li>1</li> <li>Two</li> </ul> })
+        .unwrap();
+
+    assert_eq!(
+        stats.patches,
+        PatchCounts {
+            truncate_children: 1,
+            remove_classes: 1,
+            change_text: 1,
+            ..PatchCounts::default()
+        }
+    );
+    assert_eq!(stats.patches.total(), 3);
+    // The ul, both kept li's and their text nodes
+    assert_eq!(stats.diff_nodes_visited, 5);
+    // We stop looking for nodes to patch after the second li's text node
+    assert_eq!(stats.find_nodes_visited, 5);
+}
+
+#[test]
+fn on_update_stats() {
+    let reported: Rc<RefCell<Vec<UpdateStats>>> = Rc::new(RefCell::new(vec![]));
+    let reported_clone = Rc::clone(&reported);
+
+    let mut dom_updater = DomUpdater::with_backend(MockDom, html! { <div></div> });
+    dom_updater.on_update_stats(move |stats| reported_clone.borrow_mut().push(stats.clone()));
+
+    dom_updater.update(html! { <div id="a"></div> }).unwrap();
+    dom_updater.update(html! { <div id="a"></div> }).unwrap();
+
+    let reported = reported.borrow();
+    assert_eq!(reported.len(), 2);
+    assert_eq!(reported[0].patches.add_attributes, 1);
+    assert_eq!(reported[1].patches.total(), 0, "Nothing changed");
+}
+
+// An update that is diffed across several slices gets reported once, after it was patched.
+#[test]
+fn update_in_slices_stats() {
+    let reported: Rc<RefCell<Vec<UpdateStats>>> = Rc::new(RefCell::new(vec![]));
+    let reported_clone = Rc::clone(&reported);
+
+    let mut dom_updater = DomUpdater::with_backend(
+        MockDom,
+        html! { <ul> <li>1</li> <li>2</li> <li>3</li> </ul> },
+    );
+    dom_updater.on_update_stats(move |stats| reported_clone.borrow_mut().push(stats.clone()));
+
+    dom_updater
+        .update_in_slices(
+            html! { <ul> <li>1</li> <li>Two</li> <li>Three</li> </ul> },
+            DiffBudget::Nodes(2),
+        )
+        .unwrap();
+    while dom_updater.continue_update().unwrap() == UpdateProgress::Pending {
+        assert_eq!(reported.borrow().len(), 0);
+    }
+
+    let reported = reported.borrow();
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported[0].patches.change_text, 2);
+    // The ul, the three li's and their text nodes, across every slice
+    assert_eq!(reported[0].diff_nodes_visited, 7);
+}
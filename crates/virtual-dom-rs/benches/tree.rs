@@ -0,0 +1,121 @@
+//! Compare building, diffing and rendering a `VirtualNode` with a `VTree`.
+//!
+//! To run all benchmarks in this file:
+//!
+//! cargo bench -p virtual-dom-rs --bench tree
+
+#![feature(proc_macro_hygiene)]
+#![feature(test)]
+
+extern crate test;
+
+use test::Bencher;
+use virtual_dom_rs::prelude::*;
+use virtual_dom_rs::{diff, diff_tree};
+
+/// The number of rows in a list, each row has 5 nodes.
+const ROW_COUNT: usize = 1000;
+
+fn row_class(idx: usize, selected: usize) -> &'static str {
+    if idx == selected {
+        "row selected"
+    } else {
+        "row"
+    }
+}
+
+fn list_virtual_node(selected: usize) -> VirtualNode {
+    html! {
+      <ul>
+        { for idx in 0..ROW_COUNT {
+          <li id=idx class=row_class(idx, selected)>
+            <span>{ format!("Row {}", idx) }</span>
+            <b>!</b>
+          </li>
+        } }
+      </ul>
+    }
+}
+
+fn list_tree(selected: usize) -> VTree {
+    html_tree! {
+      <ul>
+        { for idx in 0..ROW_COUNT {
+          <li id=idx class=row_class(idx, selected)>
+            <span>{ format!("Row {}", idx) }</span>
+            <b>!</b>
+          </li>
+        } }
+      </ul>
+    }
+}
+
+/// Build the same list as `list_tree`, reusing the buffers of the previous frame's tree.
+fn rebuild_list_tree(tree: &mut VTree, selected: usize) {
+    tree.clear();
+
+    tree.open_element("ul");
+    for idx in 0..ROW_COUNT {
+        tree.open_element("li");
+        tree.attr("id", idx);
+        tree.attr("class", row_class(idx, selected));
+        tree.open_element("span");
+        tree.text(format_args!("Row {}", idx));
+        tree.close_element();
+        tree.open_element("b");
+        tree.text("!");
+        tree.close_element();
+        tree.close_element();
+    }
+    tree.close_element();
+}
+
+#[bench]
+fn build_virtual_node(b: &mut Bencher) {
+    b.iter(|| list_virtual_node(0));
+}
+
+#[bench]
+fn build_tree(b: &mut Bencher) {
+    b.iter(|| list_tree(0));
+}
+
+#[bench]
+fn build_tree_reusing_buffers(b: &mut Bencher) {
+    let mut tree = VTree::new();
+
+    b.iter(|| {
+        rebuild_list_tree(&mut tree, 0);
+        tree.len()
+    });
+}
+
+#[bench]
+fn diff_virtual_node(b: &mut Bencher) {
+    let old = list_virtual_node(0);
+    let new = list_virtual_node(1);
+
+    b.iter(|| diff(&old, &new));
+}
+
+#[bench]
+fn diff_tree_nodes(b: &mut Bencher) {
+    let old = list_tree(0);
+    let new = list_tree(1);
+
+    b.iter(|| diff_tree(&old, &new));
+}
+
+#[bench]
+fn to_string_virtual_node(b: &mut Bencher) {
+    let node = list_virtual_node(0);
+
+    b.iter(|| node.to_string());
+}
+
+#[bench]
+fn to_string_tree(b: &mut Bencher) {
+    let tree = list_tree(0);
+
+    b.iter(|| tree.to_string());
+}
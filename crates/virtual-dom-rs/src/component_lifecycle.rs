@@ -0,0 +1,249 @@
+//! Figure out which components were mounted, updated or unmounted by a DOM update.
+
+use crate::patch::{AsPatch, Patch};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use virtual_node::{ComponentLifecycle, RenderRequest, VirtualNode};
+
+/// The components that an update will add to, keep in or remove from the DOM.
+pub(crate) struct ComponentChanges {
+    mounted: Vec<Rc<dyn ComponentLifecycle>>,
+    updated: Vec<Rc<dyn ComponentLifecycle>>,
+    unmounted: Vec<Rc<dyn ComponentLifecycle>>,
+}
+
+impl ComponentChanges {
+    /// Every component in the virtual dom is about to be mounted.
+    pub fn mount_all(vdom: &VirtualNode) -> ComponentChanges {
+        let mut mounted = vec![];
+        collect_components(vdom, &mut mounted);
+
+        ComponentChanges {
+            mounted,
+            updated: vec![],
+            unmounted: vec![],
+        }
+    }
+
+    /// Compare the components in the old and the new virtual dom, given the patches that turn
+    /// the old one into the new one.
+    ///
+    /// Components are matched up by their ids. A component within an old node that gets
+    /// replaced has its root element re-created, so it gets unmounted and mounted again.
+    pub fn between<P: AsPatch>(
+        old: &VirtualNode,
+        new: &VirtualNode,
+        patches: &[P],
+    ) -> ComponentChanges {
+        let replaced_nodes: HashSet<usize> = patches
+            .iter()
+            .filter_map(|patch| match *patch.as_patch() {
+                Patch::Replace(node_idx, _) => Some(node_idx),
+                _ => None,
+            })
+            .collect();
+
+        let mut replaced_components = vec![];
+        collect_replaced_components(old, &mut 0, &replaced_nodes, &mut replaced_components);
+
+        let replaced_ids: HashSet<u32> = replaced_components
+            .iter()
+            .map(|c| c.component_id())
+            .collect();
+
+        let mut old_components = vec![];
+        collect_components(old, &mut old_components);
+
+        let mut new_components = vec![];
+        collect_components(new, &mut new_components);
+
+        let mut old_components: HashMap<u32, Rc<dyn ComponentLifecycle>> = old_components
+            .into_iter()
+            .filter(|c| !replaced_ids.contains(&c.component_id()))
+            .map(|c| (c.component_id(), c))
+            .collect();
+
+        let mut mounted = vec![];
+        let mut updated = vec![];
+
+        for component in new_components {
+            match old_components.remove(&component.component_id()) {
+                Some(_) => updated.push(component),
+                None => mounted.push(component),
+            }
+        }
+
+        ComponentChanges {
+            mounted,
+            updated,
+            unmounted: replaced_components
+                .into_iter()
+                .chain(old_components.into_iter().map(|(_, c)| c))
+                .collect(),
+        }
+    }
+
+    /// Let removed components know that they're about to be removed. Call this before
+    /// patching the DOM.
+    pub fn will_unmount(&self) {
+        for component in self.unmounted.iter() {
+            component.will_unmount();
+        }
+    }
+
+    /// Let new and kept components know that the DOM was patched. Call this after patching
+    /// the DOM.
+    pub fn mounted_and_updated(&self, render_request: &RenderRequest) {
+        for component in self.mounted.iter() {
+            component.mounted(Rc::clone(render_request));
+        }
+
+        for component in self.updated.iter() {
+            component.updated();
+        }
+    }
+}
+
+fn collect_components(node: &VirtualNode, components: &mut Vec<Rc<dyn ComponentLifecycle>>) {
+    match node {
+        VirtualNode::Element(element_node) => {
+            components.extend(element_node.components.0.iter().cloned());
+
+            for child in element_node.rendered_children().iter() {
+                collect_components(child, components);
+            }
+        }
+        VirtualNode::Portal(portal) => {
+            for child in portal.children.iter() {
+                collect_components(child, components);
+            }
+        }
+        VirtualNode::Text(_) => {}
+    }
+}
+
+/// Collect the components within the old nodes that get replaced, using the same depth first
+/// node indices as the patches.
+fn collect_replaced_components(
+    node: &VirtualNode,
+    cur_node_idx: &mut usize,
+    replaced_nodes: &HashSet<usize>,
+    components: &mut Vec<Rc<dyn ComponentLifecycle>>,
+) {
+    if replaced_nodes.contains(cur_node_idx) {
+        collect_components(node, components);
+    }
+
+    *cur_node_idx += 1;
+
+    if let VirtualNode::Element(element_node) = node {
+        for child in element_node.rendered_children().iter() {
+            collect_replaced_components(child, cur_node_idx, replaced_nodes, components);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, html};
+    use std::cell::RefCell;
+    use virtual_node::{Component, ComponentHandle, IterableNodes, View};
+
+    struct Lifecycle {
+        name: &'static str,
+        calls: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Component for Lifecycle {
+        fn render(&self, _handle: &ComponentHandle<Self>) -> VirtualNode {
+            html! { <div></div> }
+        }
+
+        fn mounted(&mut self) {
+            self.calls
+                .borrow_mut()
+                .push(format!("mounted {}", self.name));
+        }
+
+        fn updated(&mut self) {
+            self.calls
+                .borrow_mut()
+                .push(format!("updated {}", self.name));
+        }
+
+        fn will_unmount(&mut self) {
+            self.calls
+                .borrow_mut()
+                .push(format!("will_unmount {}", self.name));
+        }
+    }
+
+    fn lifecycle(
+        name: &'static str,
+        calls: &Rc<RefCell<Vec<String>>>,
+    ) -> ComponentHandle<Lifecycle> {
+        ComponentHandle::new(Lifecycle {
+            name,
+            calls: Rc::clone(calls),
+        })
+    }
+
+    #[test]
+    fn mount_all_components() {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let a = lifecycle("a", &calls);
+        let b = lifecycle("b", &calls);
+
+        let vdom = html! { <div> { a.render() } <span> { b.render() } </span> </div> };
+
+        ComponentChanges::mount_all(&vdom).mounted_and_updated(&(Rc::new(|| {}) as RenderRequest));
+
+        assert_eq!(*calls.borrow(), vec!["mounted a", "mounted b"]);
+        assert!(a.is_mounted() && b.is_mounted());
+    }
+
+    #[test]
+    fn mount_update_and_unmount() {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let kept = lifecycle("kept", &calls);
+        let removed = lifecycle("removed", &calls);
+        let added = lifecycle("added", &calls);
+
+        let old = html! { <div> { kept.render() } { removed.render() } </div> };
+        let new = html! { <div> { kept.render() } { added.render() } </div> };
+
+        let changes = ComponentChanges::between(&old, &new, &diff(&old, &new));
+
+        changes.will_unmount();
+        assert_eq!(*calls.borrow(), vec!["will_unmount removed"]);
+
+        changes.mounted_and_updated(&(Rc::new(|| {}) as RenderRequest));
+        assert_eq!(
+            *calls.borrow(),
+            vec!["will_unmount removed", "mounted added", "updated kept"]
+        );
+    }
+
+    #[test]
+    fn replaced_root_element_remounts() {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let component = lifecycle("component", &calls);
+
+        // The component's root element changed its tag, so it gets replaced
+        let mut replaced_root = component.render();
+        replaced_root.as_velement_mut().unwrap().tag = "span".to_string();
+
+        let old = html! { <div> { component.render() } </div> };
+        let new = html! { <div> { replaced_root } </div> };
+
+        let changes = ComponentChanges::between(&old, &new, &diff(&old, &new));
+
+        changes.will_unmount();
+        changes.mounted_and_updated(&(Rc::new(|| {}) as RenderRequest));
+        assert_eq!(
+            *calls.borrow(),
+            vec!["will_unmount component", "mounted component"]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 //! Kept in it's own file to more easily import into the Percy book.
 
 use crate::diff::diff;
-use crate::patch::Patch;
+use crate::patch::{apply_patches_virtual, Patch};
 use virtual_node::VirtualNode;
 
 /// Test that we generate the right Vec<Patch> for some start and end virtual dom.
@@ -22,5 +22,10 @@ impl<'a> DiffTestCase<'a> {
         let patches = diff(&self.old, &self.new);
 
         assert_eq!(patches, self.expected, "{}", self.description);
+
+        // Applying the patches to the old virtual dom should give us the new virtual dom
+        let mut patched = self.old.clone();
+        apply_patches_virtual(&mut patched, &patches);
+        assert_eq!(patched, self.new, "{}", self.description);
     }
 }
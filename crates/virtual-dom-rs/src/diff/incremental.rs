@@ -0,0 +1,315 @@
+use super::{diff_node, ChildrenToDiff};
+use crate::stats::{self, Phase};
+use crate::{Patch, PatchOwned, VirtualNode};
+
+/// How much diffing `diff_incremental` does before it pauses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffBudget {
+    /// Diff at most this many nodes before pausing.
+    Nodes(usize),
+    /// Diff for roughly this many milliseconds before pausing.
+    ///
+    /// We only check the clock every so many nodes, so a slice can run a little over.
+    Millis(f64),
+}
+
+/// The result of diffing for one slice of a `DiffBudget`.
+#[derive(Debug, PartialEq)]
+pub enum DiffProgress {
+    /// Every node was diffed. These are the same patches that `diff` returns.
+    Done(Vec<PatchOwned>),
+    /// We ran out of budget. Pass the continuation back into `diff_incremental`, along with the
+    /// same old and new virtual doms, to pick up where we left off.
+    Paused(DiffContinuation),
+}
+
+/// Where a paused `diff_incremental` left off, along with the patches so far.
+///
+/// A continuation doesn't borrow the virtual doms, so they can be held on to across frames. It
+/// only makes sense for the pair of virtual doms that it came from, resuming with any others
+/// panics or produces bogus patches.
+#[derive(Debug, Default, PartialEq)]
+pub struct DiffContinuation {
+    /// One entry per element whose children we're in the middle of, from the root down.
+    frames: Vec<DiffFrame>,
+    cur_node_idx: usize,
+    patches: Vec<PatchOwned>,
+    nodes_diffed: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct DiffFrame {
+    /// The index of the next child to diff within the element's rendered children.
+    next_child: usize,
+    /// Where the removal patches of truncated children go within all of the patches.
+    truncate_patch_idx: usize,
+}
+
+impl DiffContinuation {
+    /// How many nodes have been diffed so far.
+    pub fn nodes_diffed(&self) -> usize {
+        self.nodes_diffed
+    }
+}
+
+/// How many nodes we diff in between looking at the clock for a `DiffBudget::Millis`.
+const NODES_PER_CLOCK_CHECK: usize = 64;
+
+/// Diff two virtual doms a slice at a time, such as to spread the diffing of a very large
+/// tree across several animation frames.
+///
+/// Start with no continuation. Every call diffs nodes until the budget runs out, always making
+/// some progress, and returns either all of the patches or a continuation to resume with.
+///
+/// Once done the patches are exactly the ones that `diff` returns, so they can be applied all
+/// at once and the DOM never shows a partial update.
+pub fn diff_incremental(
+    old: &VirtualNode,
+    new: &VirtualNode,
+    budget: DiffBudget,
+    continuation: Option<DiffContinuation>,
+) -> DiffProgress {
+    stats::timed(Phase::Diff, || {
+        let started_at = now_ms();
+        let mut nodes_this_slice = 0;
+
+        let (mut state, mut stack) = match continuation {
+            Some(state) => {
+                let stack = resume_stack(old, new, &state.frames);
+                (state, stack)
+            }
+            None => {
+                let mut state = DiffContinuation::default();
+                let mut stack = vec![];
+
+                diff_one(old, new, &mut state, &mut stack);
+                nodes_this_slice += 1;
+
+                (state, stack)
+            }
+        };
+
+        while let Some(children) = stack.last() {
+            let frame = state.frames.last_mut().expect("One frame per element");
+
+            match children.kept_child(frame.next_child) {
+                Some((old_child, new_child)) => {
+                    frame.next_child += 1;
+                    state.cur_node_idx += 1;
+
+                    diff_one(old_child, new_child, &mut state, &mut stack);
+                    nodes_this_slice += 1;
+                }
+                None => {
+                    let truncate_patch_idx = frame.truncate_patch_idx;
+                    let on_remove_patches = children
+                        .truncated_removal_patches(&mut state.cur_node_idx)
+                        .into_iter()
+                        .map(Patch::into_owned);
+                    state
+                        .patches
+                        .splice(truncate_patch_idx..truncate_patch_idx, on_remove_patches);
+
+                    state.frames.pop();
+                    stack.pop();
+                    continue;
+                }
+            };
+
+            if !stack.is_empty() && budget_spent(budget, nodes_this_slice, started_at) {
+                return DiffProgress::Paused(state);
+            }
+        }
+
+        DiffProgress::Done(state.patches)
+    })
+}
+
+/// Diff a single node and keep track of its children if we need to descend into them.
+fn diff_one<'a>(
+    old: &'a VirtualNode,
+    new: &'a VirtualNode,
+    state: &mut DiffContinuation,
+    stack: &mut Vec<ChildrenToDiff<'a>>,
+) {
+    let mut patches = vec![];
+    let children = diff_node(old, new, &mut state.cur_node_idx, &mut patches);
+
+    let patch_offset = state.patches.len();
+    state
+        .patches
+        .extend(patches.into_iter().map(Patch::into_owned));
+    state.nodes_diffed += 1;
+
+    if let Some(children) = children {
+        state.frames.push(DiffFrame {
+            next_child: 0,
+            truncate_patch_idx: patch_offset + children.truncate_patch_idx,
+        });
+        stack.push(children);
+    }
+}
+
+/// Walk back down to the elements whose children we were in the middle of diffing.
+///
+/// Every element on the way down was the most recently diffed child of its parent.
+fn resume_stack<'a>(
+    old: &'a VirtualNode,
+    new: &'a VirtualNode,
+    frames: &[DiffFrame],
+) -> Vec<ChildrenToDiff<'a>> {
+    let mut stack: Vec<ChildrenToDiff<'a>> = vec![];
+    let (mut old, mut new) = (old, new);
+
+    for depth in 0..frames.len() {
+        if depth > 0 {
+            let (old_child, new_child) = stack[depth - 1]
+                .kept_child(frames[depth - 1].next_child - 1)
+                .expect("Resumed a diff with different virtual doms");
+            old = old_child;
+            new = new_child;
+        }
+
+        match (old, new) {
+            (VirtualNode::Element(old_element), VirtualNode::Element(new_element)) => {
+                stack.push(ChildrenToDiff::between(old_element, new_element));
+            }
+            _ => panic!("Resumed a diff with different virtual doms"),
+        }
+    }
+
+    stack
+}
+
+fn budget_spent(budget: DiffBudget, nodes_this_slice: usize, started_at: f64) -> bool {
+    match budget {
+        DiffBudget::Nodes(max_nodes) => nodes_this_slice >= max_nodes,
+        DiffBudget::Millis(max_ms) => {
+            nodes_this_slice % NODES_PER_CLOCK_CHECK == 0 && now_ms() - started_at >= max_ms
+        }
+    }
+}
+
+// `std::time::Instant` isn't available in the browser and `performance` is only enabled for the
+// `stats` feature, so we use `Date.now()`.
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::time::Instant;
+
+    thread_local! {
+        static START: Instant = Instant::now();
+    }
+
+    START.with(|start| start.elapsed().as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::diff;
+    use crate::html;
+    use std::rc::Rc;
+    use wasm_bindgen::JsValue;
+
+    fn diff_in_slices(
+        old: &VirtualNode,
+        new: &VirtualNode,
+        budget: DiffBudget,
+    ) -> (Vec<PatchOwned>, usize) {
+        let mut continuation = None;
+        let mut slices = 0;
+
+        loop {
+            slices += 1;
+            match diff_incremental(old, new, budget, continuation.take()) {
+                DiffProgress::Done(patches) => return (patches, slices),
+                DiffProgress::Paused(paused) => continuation = Some(paused),
+            }
+        }
+    }
+
+    /// Whatever the budget, the patches should match the ones that `diff` comes up with.
+    fn assert_same_patches(old: VirtualNode, new: VirtualNode, description: &str) {
+        let expected: Vec<PatchOwned> = diff(&old, &new)
+            .into_iter()
+            .map(Patch::into_owned)
+            .collect();
+
+        for budget in vec![
+            DiffBudget::Nodes(0),
+            DiffBudget::Nodes(1),
+            DiffBudget::Nodes(2),
+            DiffBudget::Nodes(3),
+            DiffBudget::Millis(1000.0),
+        ] {
+            let (patches, _slices) = diff_in_slices(&old, &new, budget);
+            assert_eq!(patches, expected, "{} with {:?}", description, budget);
+        }
+    }
+
+    #[test]
+    fn same_patches_as_diff() {
+        assert_same_patches(
+            html! { <div> <b></b> </div> },
+            html! { <span> <b></b> </span> },
+            "Replace the root",
+        );
+        assert_same_patches(
+            html! { <div> <b> <i>1</i> </b> <b></b> </div> },
+            html! { <div> <i>1</i> <i></i> </div> },
+            "Replace children that have descendants",
+        );
+        assert_same_patches(
+            html! { <div> <b> <i></i> <i></i> </b> <b></b> </div> },
+            html! { <div> <b> <i></i> </b> <i></i> </div> },
+            "Truncate a child and replace the next node after its parent",
+        );
+        assert_same_patches(
+            html! { <div> <p> <b>1</b> <em id="a">2</em> </p> <p>3</p> </div> },
+            html! { <div> <p> <b>One</b> <em id="b">2</em> </p> <p>3</p> <p>4</p> </div> },
+            "Text, attributes and appended children deep in the tree",
+        );
+        assert_same_patches(
+            html! { <ul> <li>1</li> <li>2</li> <li>3</li> <li>4</li> </ul> },
+            html! { <ul> <li>1</li> </ul> },
+            "Truncate several children",
+        );
+    }
+
+    #[test]
+    fn on_remove_elem_in_truncated_children() {
+        let mut old = html! { <div> <span></span> <em><b></b></em> </div> };
+        let new = html! { <div> <span></span> </div> };
+
+        for child in old.as_velement_mut().unwrap().children.iter_mut() {
+            child
+                .as_velement_mut()
+                .unwrap()
+                .events
+                .0
+                .insert("on_remove_elem".to_string(), Rc::new(JsValue::NULL));
+        }
+
+        assert_same_patches(old, new, "Removal patches go before the truncate patch");
+    }
+
+    #[test]
+    fn pauses_once_the_budget_is_spent() {
+        let old = html! { <div> <p>1</p> <p>2</p> <p>3</p> </div> };
+        let new = html! { <div> <p>1</p> <p>2</p> <p>Three</p> </div> };
+
+        // 7 nodes, 2 per slice
+        let (_patches, slices) = diff_in_slices(&old, &new, DiffBudget::Nodes(2));
+        assert_eq!(slices, 4);
+
+        match diff_incremental(&old, &new, DiffBudget::Nodes(3), None) {
+            DiffProgress::Paused(continuation) => assert_eq!(continuation.nodes_diffed(), 3),
+            DiffProgress::Done(_) => panic!("Should have paused"),
+        };
+    }
+}
@@ -1,13 +1,20 @@
-use crate::Patch;
-use crate::VirtualNode;
-use std::cmp::min;
+use crate::stats::{self, Phase};
+use crate::{ElemHook, Patch};
+use crate::{VElement, VirtualNode};
 use std::collections::HashMap;
 use std::mem;
+use std::rc::Rc;
+
+mod incremental;
+pub use self::incremental::{diff_incremental, DiffBudget, DiffContinuation, DiffProgress};
+
+mod tree;
+pub use self::tree::diff_tree;
 
 /// Given two VirtualNode's generate Patch's that would turn the old virtual node's
 /// real DOM node equivalent into the new VirtualNode's real DOM node equivalent.
 pub fn diff<'a>(old: &'a VirtualNode, new: &'a VirtualNode) -> Vec<Patch<'a>> {
-    diff_recursive(&old, &new, &mut 0)
+    stats::timed(Phase::Diff, || diff_recursive(&old, &new, &mut 0))
 }
 
 fn diff_recursive<'a, 'b>(
@@ -16,6 +23,83 @@ fn diff_recursive<'a, 'b>(
     cur_node_idx: &'b mut usize,
 ) -> Vec<Patch<'a>> {
     let mut patches = vec![];
+
+    if let Some(children) = diff_node(old, new, cur_node_idx, &mut patches) {
+        for (old_child, new_child) in children.kept() {
+            *cur_node_idx += 1;
+            patches.append(&mut diff_recursive(old_child, new_child, cur_node_idx))
+        }
+
+        let on_remove_patches = children.truncated_removal_patches(cur_node_idx);
+        splice_before(&mut patches, children.truncate_patch_idx, on_remove_patches);
+    }
+
+    patches
+}
+
+/// The children of two elements that still need to be diffed after the elements themselves
+/// were, see `diff_node`.
+pub(crate) struct ChildrenToDiff<'a> {
+    old: &'a [VirtualNode],
+    new: &'a [VirtualNode],
+    /// Where the removal patches of truncated children need to go within the patches that
+    /// `diff_node` pushed to, right before the `TruncateChildren` patch.
+    pub(crate) truncate_patch_idx: usize,
+}
+
+impl<'a> ChildrenToDiff<'a> {
+    /// The children of two elements that weren't replaced, ignoring any `truncate_patch_idx`.
+    pub(crate) fn between(old: &'a VElement, new: &'a VElement) -> ChildrenToDiff<'a> {
+        ChildrenToDiff {
+            old: old.rendered_children(),
+            new: new.rendered_children(),
+            truncate_patch_idx: 0,
+        }
+    }
+
+    /// The pairs of old and new children that need to be diffed, in order.
+    pub(crate) fn kept(&self) -> impl Iterator<Item = (&'a VirtualNode, &'a VirtualNode)> {
+        self.old.iter().zip(self.new.iter())
+    }
+
+    /// The old child at the given index along with the new child that it gets diffed with.
+    pub(crate) fn kept_child(&self, idx: usize) -> Option<(&'a VirtualNode, &'a VirtualNode)> {
+        match (self.old.get(idx), self.new.get(idx)) {
+            (Some(old_child), Some(new_child)) => Some((old_child, new_child)),
+            _ => None,
+        }
+    }
+
+    /// Any `on_remove_elem` hooks within the truncated children need to be called and any
+    /// node refs need to be cleared before the children get truncated.
+    ///
+    /// Called once all of the kept children were diffed, so that the node index gets
+    /// incremented past the truncated children.
+    pub(crate) fn truncated_removal_patches(&self, cur_node_idx: &mut usize) -> Vec<Patch<'a>> {
+        let mut on_remove_patches = vec![];
+
+        if self.new.len() < self.old.len() {
+            for child in self.old[self.new.len()..].iter() {
+                increment_node_idx_for_children(child, cur_node_idx, &mut on_remove_patches);
+            }
+        }
+
+        on_remove_patches
+    }
+}
+
+/// Push the patches for a single node, without descending into its children.
+///
+/// If both nodes are elements that weren't replaced we return their children, which then need
+/// to be diffed in order.
+pub(crate) fn diff_node<'a, 'b>(
+    old: &'a VirtualNode,
+    new: &'a VirtualNode,
+    cur_node_idx: &'b mut usize,
+    patches: &mut Vec<Patch<'a>>,
+) -> Option<ChildrenToDiff<'a>> {
+    stats::diff_node_visited();
+
     let mut replace = false;
 
     // Different enum variants, replace!
@@ -38,17 +122,30 @@ fn diff_recursive<'a, 'b>(
         {
             replace = true;
         }
+
+        // Replace if only one of the elements has raw HTML instead of children, so that we
+        // never have to patch between the two.
+        if old_element.unsafe_inner_html.is_some() != new_element.unsafe_inner_html.is_some() {
+            replace = true;
+        }
     }
 
     // Handle replacing of a node
     if replace {
-        patches.push(Patch::Replace(*cur_node_idx, &new));
+        let replaced_node_idx = *cur_node_idx;
+
+        // Any `on_remove_elem` hooks within the replaced node need to be called and any
+        // node refs need to be cleared before the node gets replaced.
         if let VirtualNode::Element(old_element_node) = old {
-            for child in old_element_node.children.iter() {
-                increment_node_idx_for_children(child, cur_node_idx);
+            push_removal_patches(old_element_node, replaced_node_idx, patches);
+
+            for child in old_element_node.rendered_children().iter() {
+                increment_node_idx_for_children(child, cur_node_idx, patches);
             }
         }
-        return patches;
+
+        patches.push(Patch::Replace(replaced_node_idx, &new));
+        return None;
     }
 
     // The following comparison can only contain identical variants, other
@@ -99,58 +196,183 @@ fn diff_recursive<'a, 'b>(
                 };
             }
 
+            let add_classes: Vec<&str> = new_element
+                .classes
+                .iter()
+                .filter(|token| !old_element.classes.contains(token))
+                .collect();
+            let remove_classes: Vec<&str> = old_element
+                .classes
+                .iter()
+                .filter(|token| !new_element.classes.contains(token))
+                .collect();
+
+            let mut set_style_properties: Vec<(&str, &str)> = vec![];
+            let mut remove_style_properties: Vec<&str> = vec![];
+
+            for (name, new_value) in new_element.styles.iter() {
+                if old_element.styles.get(name) != Some(new_value) {
+                    set_style_properties.push((name, new_value));
+                }
+            }
+
+            for (name, _old_value) in old_element.styles.iter() {
+                if new_element.styles.get(name).is_none() {
+                    remove_style_properties.push(name);
+                }
+            }
+
+            let attributes_changed = add_attributes.len() > 0
+                || remove_attributes.len() > 0
+                || add_classes.len() > 0
+                || remove_classes.len() > 0
+                || set_style_properties.len() > 0
+                || remove_style_properties.len() > 0
+                || old_element.unsafe_inner_html != new_element.unsafe_inner_html;
+
             if add_attributes.len() > 0 {
                 patches.push(Patch::AddAttributes(*cur_node_idx, add_attributes));
             }
             if remove_attributes.len() > 0 {
                 patches.push(Patch::RemoveAttributes(*cur_node_idx, remove_attributes));
             }
+            if add_classes.len() > 0 {
+                patches.push(Patch::AddClasses(*cur_node_idx, add_classes));
+            }
+            if remove_classes.len() > 0 {
+                patches.push(Patch::RemoveClasses(*cur_node_idx, remove_classes));
+            }
+            if set_style_properties.len() > 0 {
+                patches.push(Patch::SetStyleProperties(
+                    *cur_node_idx,
+                    set_style_properties,
+                ));
+            }
+            if remove_style_properties.len() > 0 {
+                patches.push(Patch::RemoveStyleProperties(
+                    *cur_node_idx,
+                    remove_style_properties,
+                ));
+            }
+            if let (Some(old_html), Some(new_html)) =
+                (&old_element.unsafe_inner_html, &new_element.unsafe_inner_html)
+            {
+                if old_html != new_html {
+                    patches.push(Patch::SetInnerHtml(*cur_node_idx, new_html));
+                }
+            }
+
+            if let Some(hook) = new_element.events.0.get("on_update_elem") {
+                if attributes_changed {
+                    let hook = ElemHook(Rc::clone(hook));
+                    patches.push(Patch::CallOnUpdateElem(*cur_node_idx, hook));
+                }
+            }
+
+            if old_element.node_ref != new_element.node_ref {
+                if let Some(old_node_ref) = &old_element.node_ref {
+                    patches.push(Patch::ClearNodeRef(*cur_node_idx, old_node_ref));
+                }
+                if let Some(new_node_ref) = &new_element.node_ref {
+                    patches.push(Patch::SetNodeRef(*cur_node_idx, new_node_ref));
+                }
+            }
+
+            // We don't descend into raw HTML
+            let old_children = old_element.rendered_children();
+            let new_children = new_element.rendered_children();
 
-            let old_child_count = old_element.children.len();
-            let new_child_count = new_element.children.len();
+            let old_child_count = old_children.len();
+            let new_child_count = new_children.len();
 
             if new_child_count > old_child_count {
                 let append_patch: Vec<&'a VirtualNode> =
-                    new_element.children[old_child_count..].iter().collect();
+                    new_children[old_child_count..].iter().collect();
                 patches.push(Patch::AppendChildren(*cur_node_idx, append_patch))
             }
 
+            let truncate_patch_idx = patches.len();
             if new_child_count < old_child_count {
                 patches.push(Patch::TruncateChildren(*cur_node_idx, new_child_count))
             }
 
-            let min_count = min(old_child_count, new_child_count);
-            for index in 0..min_count {
-                *cur_node_idx = *cur_node_idx + 1;
-                let old_child = &old_element.children[index];
-                let new_child = &new_element.children[index];
-                patches.append(&mut diff_recursive(&old_child, &new_child, cur_node_idx))
-            }
-            if new_child_count < old_child_count {
-                for child in old_element.children[min_count..].iter() {
-                    increment_node_idx_for_children(child, cur_node_idx);
-                }
-            }
+            return Some(ChildrenToDiff {
+                old: old_children,
+                new: new_children,
+                truncate_patch_idx,
+            });
         }
-        (VirtualNode::Text(_), VirtualNode::Element(_))
-        | (VirtualNode::Element(_), VirtualNode::Text(_)) => {
+        // Within this tree a portal is only an empty text node, which never changes. Its target
+        // and children get patched by the `DomUpdater`.
+        (VirtualNode::Portal(_), VirtualNode::Portal(_)) => {}
+        _ => {
             unreachable!("Unequal variant discriminants should already have been handled");
         }
     };
 
-    //    new_root.create_element()
-    patches
+    None
 }
 
-fn increment_node_idx_for_children<'a, 'b>(old: &'a VirtualNode, cur_node_idx: &'b mut usize) {
+/// Increment the node index past a node that is being removed and all of its descendants.
+///
+/// Every removed element gets its removal patches, see `push_removal_patches`.
+fn increment_node_idx_for_children<'a, 'b>(
+    old: &'a VirtualNode,
+    cur_node_idx: &'b mut usize,
+    on_remove_patches: &mut Vec<Patch<'a>>,
+) {
     *cur_node_idx += 1;
     if let VirtualNode::Element(element_node) = old {
-        for child in element_node.children.iter() {
-            increment_node_idx_for_children(&child, cur_node_idx);
+        push_removal_patches(element_node, *cur_node_idx, on_remove_patches);
+
+        for child in element_node.rendered_children().iter() {
+            increment_node_idx_for_children(&child, cur_node_idx, on_remove_patches);
         }
     }
 }
 
+/// The patches that need to be applied to a node, as a root node, before it gets removed.
+///
+/// See `push_removal_patches`.
+pub(crate) fn removal_patches(old: &VirtualNode) -> Vec<Patch<'_>> {
+    let mut patches = vec![];
+
+    if let VirtualNode::Element(old_element) = old {
+        push_removal_patches(old_element, 0, &mut patches);
+
+        let mut cur_node_idx = 0;
+        for child in old_element.rendered_children().iter() {
+            increment_node_idx_for_children(child, &mut cur_node_idx, &mut patches);
+        }
+    }
+
+    patches
+}
+
+/// An element that is about to be removed gets a `CallOnRemoveElem` patch if it has an
+/// `on_remove_elem` hook and a `ClearNodeRef` patch if it has a node ref.
+fn push_removal_patches<'a>(
+    old_element: &'a VElement,
+    node_idx: usize,
+    on_remove_patches: &mut Vec<Patch<'a>>,
+) {
+    if let Some(hook) = old_element.events.0.get("on_remove_elem") {
+        let hook = ElemHook(Rc::clone(hook));
+        on_remove_patches.push(Patch::CallOnRemoveElem(node_idx, hook));
+    }
+
+    if let Some(node_ref) = &old_element.node_ref {
+        on_remove_patches.push(Patch::ClearNodeRef(node_idx, node_ref));
+    }
+}
+
+/// Insert patches before the patch at the given index.
+fn splice_before<'a>(patches: &mut Vec<Patch<'a>>, idx: usize, before: Vec<Patch<'a>>) {
+    if before.len() > 0 {
+        patches.splice(idx..idx, before);
+    }
+}
+
 #[cfg(test)]
 mod diff_test_case;
 #[cfg(test)]
@@ -159,8 +381,10 @@ use self::diff_test_case::*;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{html, VText, VirtualNode};
+    use crate::{classes, html, DynClosure, IterableNodes, NodeRef, VText, VirtualNode};
     use std::collections::HashMap;
+    use std::rc::Rc;
+    use wasm_bindgen::JsValue;
 
     #[test]
     fn replace_node() {
@@ -195,8 +419,8 @@ mod tests {
         DiffTestCase {
             description: "Added a new node to the root node",
             old: html! { <div> <b></b> </div> },
-            new: html! { <div> <b></b> <new></new> </div> },
-            expected: vec![Patch::AppendChildren(0, vec![&html! { <new></new> }])],
+            new: html! { <div> <b></b> <span></span> </div> },
+            expected: vec![Patch::AppendChildren(0, vec![&html! { <span></span> }])],
         }
         .test();
     }
@@ -290,6 +514,102 @@ mod tests {
         .test();
     }
 
+    #[test]
+    fn classes() {
+        DiffTestCase {
+            description: "Add and remove individual class tokens",
+            old: html! { <div class="btn big"></div> },
+            new: html! { <div class=classes!["btn", "active" => true, "big" => false]></div> },
+            expected: vec![
+                Patch::AddClasses(0, vec!["active"]),
+                Patch::RemoveClasses(0, vec!["big"]),
+            ],
+        }
+        .test();
+        DiffTestCase {
+            description: "The order of class tokens doesn't matter",
+            old: html! { <div class="a b"></div> },
+            new: html! { <div class="b a a"></div> },
+            expected: vec![],
+        }
+        .test();
+    }
+
+    #[test]
+    fn style_properties() {
+        DiffTestCase {
+            description: "Set a changed style property without touching the others",
+            old: html! { <div style={ ("width", "10px"), ("color", "red") }></div> },
+            new: html! { <div style={ ("width", "20px"), ("color", "red") }></div> },
+            expected: vec![Patch::SetStyleProperties(0, vec![("width", "20px")])],
+        }
+        .test();
+        DiffTestCase {
+            description: "Add and remove style properties",
+            old: html! { <div style={ ("width", "10px") }></div> },
+            new: html! { <div style={ ("color", "red") }></div> },
+            expected: vec![
+                Patch::SetStyleProperties(0, vec![("color", "red")]),
+                Patch::RemoveStyleProperties(0, vec!["width"]),
+            ],
+        }
+        .test();
+        DiffTestCase {
+            description: "The order of style properties doesn't matter",
+            old: html! { <div style={ ("width", "10px"), ("color", "red") }></div> },
+            new: html! { <div style={ ("color", "red"), ("width", "10px") }></div> },
+            expected: vec![],
+        }
+        .test();
+    }
+
+    #[test]
+    fn inner_html() {
+        DiffTestCase {
+            description: "Replace changed raw HTML without descending into it",
+            old: html! { <div><div unsafe_inner_html="<b>Old</b>"></div><em></em></div> },
+            new: html! { <div><div unsafe_inner_html="<b>New</b>"></div><em></em></div> },
+            expected: vec![Patch::SetInnerHtml(1, "<b>New</b>")],
+        }
+        .test();
+        DiffTestCase {
+            description: "Replace an element that switches from raw HTML to children",
+            old: html! { <div unsafe_inner_html="<b>Raw</b>"></div> },
+            new: html! { <div><b>Raw</b></div> },
+            expected: vec![Patch::Replace(0, &html! { <div><b>Raw</b></div> })],
+        }
+        .test();
+    }
+
+    #[test]
+    fn portals() {
+        DiffTestCase {
+            description: "Unchanged portals are left alone",
+            old: html! { <div> { VirtualNode::portal("body", "Modal") } <b></b> </div> },
+            new: html! { <div> { VirtualNode::portal("body", "Modal") } <b></b> </div> },
+            expected: vec![],
+        }
+        .test();
+
+        // The diff doesn't touch the portals themselves, so we can't use a `DiffTestCase` which
+        // checks that the patches turn the old portals into the new ones.
+        let old = html! { <div> { VirtualNode::portal("body", html! { <em></em> }) } <b></b> </div> };
+        let new = html! { <div> { VirtualNode::portal("body", "Modal") } <i></i> </div> };
+        assert_eq!(
+            diff(&old, &new),
+            vec![Patch::Replace(2, &html! { <i></i> })],
+            "A changed portal keeps its placeholder without descending into it"
+        );
+
+        let old = html! { <div> { VirtualNode::portal("body", "Modal") } </div> };
+        let new = html! { <div> { VirtualNode::portal("body", vec![]) } </div> };
+        assert_eq!(
+            diff(&old, &new),
+            vec![],
+            "A portal keeps its placeholder when its children become empty"
+        );
+    }
+
     #[test]
     fn replace_text_node() {
         DiffTestCase {
@@ -315,6 +635,155 @@ mod tests {
         .test()
     }
 
+    #[test]
+    fn call_on_remove_elem() {
+        let mut old = html! { <div> <b></b> <span> <em></em> </span> </div> };
+        let span_hook = with_hook(
+            &mut old.as_velement_mut().unwrap().children[1],
+            "on_remove_elem",
+        );
+        let em_hook = with_hook(
+            &mut old.as_velement_mut().unwrap().children[1]
+                .as_velement_mut()
+                .unwrap()
+                .children[0],
+            "on_remove_elem",
+        );
+
+        DiffTestCase {
+            description: "Call on_remove_elem for truncated children and their descendants",
+            old,
+            new: html! { <div> <b></b> </div> },
+            expected: vec![
+                Patch::CallOnRemoveElem(2, span_hook),
+                Patch::CallOnRemoveElem(3, em_hook),
+                Patch::TruncateChildren(0, 1),
+            ],
+        }
+        .test();
+
+        let mut old = html! { <div> <em></em> </div> };
+        let div_hook = with_hook(&mut old, "on_remove_elem");
+        let em_hook = with_hook(
+            &mut old.as_velement_mut().unwrap().children[0],
+            "on_remove_elem",
+        );
+
+        DiffTestCase {
+            description: "Call on_remove_elem for a replaced node and its descendants",
+            old,
+            new: html! { <span></span> },
+            expected: vec![
+                Patch::CallOnRemoveElem(0, div_hook),
+                Patch::CallOnRemoveElem(1, em_hook),
+                Patch::Replace(0, &html! { <span></span> }),
+            ],
+        }
+        .test();
+    }
+
+    #[test]
+    fn call_on_update_elem() {
+        let mut attributes = HashMap::new();
+        attributes.insert("id", "new");
+
+        let mut new = html! { <div id="new"></div> };
+        let hook = with_hook(&mut new, "on_update_elem");
+
+        DiffTestCase {
+            description: "Call on_update_elem after patching attributes",
+            old: html! { <div id="old"></div> },
+            new,
+            expected: vec![
+                Patch::AddAttributes(0, attributes),
+                Patch::CallOnUpdateElem(0, hook),
+            ],
+        }
+        .test();
+
+        let mut new = html! { <div id="same"></div> };
+        with_hook(&mut new, "on_update_elem");
+
+        DiffTestCase {
+            description: "Don't call on_update_elem if no attributes changed",
+            old: html! { <div id="same"></div> },
+            new,
+            expected: vec![],
+        }
+        .test();
+    }
+
+    #[test]
+    fn set_and_clear_node_refs() {
+        let node_ref = NodeRef::new();
+
+        DiffTestCase {
+            description: "Set a node ref that was added to a kept element",
+            old: html! { <div></div> },
+            new: html! { <div ref=node_ref></div> },
+            expected: vec![Patch::SetNodeRef(0, &node_ref)],
+        }
+        .test();
+
+        DiffTestCase {
+            description: "Don't patch a node ref that did not change",
+            old: html! { <div ref=node_ref></div> },
+            new: html! { <div ref=node_ref></div> },
+            expected: vec![],
+        }
+        .test();
+
+        let other_ref = NodeRef::new();
+
+        DiffTestCase {
+            description: "Clear the old node ref before setting the new one",
+            old: html! { <div ref=node_ref></div> },
+            new: html! { <div ref=other_ref></div> },
+            expected: vec![
+                Patch::ClearNodeRef(0, &node_ref),
+                Patch::SetNodeRef(0, &other_ref),
+            ],
+        }
+        .test();
+
+        DiffTestCase {
+            description: "Clear the node refs of truncated children",
+            old: html! { <div> <b></b> <span> <em ref=node_ref></em> </span> </div> },
+            new: html! { <div> <b></b> </div> },
+            expected: vec![
+                Patch::ClearNodeRef(3, &node_ref),
+                Patch::TruncateChildren(0, 1),
+            ],
+        }
+        .test();
+
+        DiffTestCase {
+            description: "Clear the node ref of a replaced node",
+            old: html! { <div ref=node_ref></div> },
+            new: html! { <span></span> },
+            expected: vec![
+                Patch::ClearNodeRef(0, &node_ref),
+                Patch::Replace(0, &html! { <span></span> }),
+            ],
+        }
+        .test();
+    }
+
+    /// Give an element a placeholder hook such as `on_remove_elem`.
+    ///
+    /// Real hooks are closures that only get created on wasm32 targets, but diffing only
+    /// cares about whether or not the hook is there.
+    fn with_hook(node: &mut VirtualNode, hook: &str) -> ElemHook {
+        let closure: DynClosure = Rc::new(JsValue::NULL);
+        node.as_velement_mut()
+            .unwrap()
+            .events
+            .0
+            .insert(hook.to_string(), Rc::clone(&closure));
+
+        ElemHook(closure)
+    }
+
     //    // TODO: Key support
     //    #[test]
     //    fn reorder_chldren() {
@@ -0,0 +1,244 @@
+use crate::stats::{self, Phase};
+use crate::{Classes, PatchOwned, VText, VTree};
+use std::collections::HashMap;
+
+/// Given two `VTree`s generate the patches that would turn the old tree's real DOM node
+/// equivalent into the new tree's real DOM node equivalent.
+///
+/// A node's index within the old tree is the index that its patches use, so unlike `diff`
+/// we never need to count nodes as we walk over them. Replaced and appended nodes get copied
+/// out of the new tree into `VirtualNode`s, so the patches are owned.
+pub fn diff_tree(old: &VTree, new: &VTree) -> Vec<PatchOwned> {
+    stats::timed(Phase::Diff, || {
+        let mut patches = vec![];
+
+        if !old.is_empty() && !new.is_empty() {
+            diff_tree_node(old, 0, new, 0, &mut patches);
+        }
+
+        patches
+    })
+}
+
+fn diff_tree_node(
+    old: &VTree,
+    old_idx: usize,
+    new: &VTree,
+    new_idx: usize,
+    patches: &mut Vec<PatchOwned>,
+) {
+    stats::diff_node_visited();
+
+    let replace = match (old.tag(old_idx), new.tag(new_idx)) {
+        // Replace if there are different element tags or different keys, the same as `diff`
+        (Some(old_tag), Some(new_tag)) => {
+            let old_key = old.attr_value(old_idx, "key");
+
+            old_tag != new_tag || (old_key.is_some() && old_key != new.attr_value(new_idx, "key"))
+        }
+        (None, None) => false,
+        // An element and a text node
+        _ => true,
+    };
+
+    if replace {
+        patches.push(PatchOwned::Replace(old_idx, new.virtual_node(new_idx)));
+        return;
+    }
+
+    // We're comparing two text nodes
+    if let (Some(old_text), Some(new_text)) = (old.text_at(old_idx), new.text_at(new_idx)) {
+        if old_text != new_text {
+            patches.push(PatchOwned::ChangeText(old_idx, VText::from(new_text)));
+        }
+        return;
+    }
+
+    diff_tree_attributes(old, old_idx, new, new_idx, patches);
+
+    let old_child_count = old.children(old_idx).count();
+    let new_child_count = new.children(new_idx).count();
+
+    if new_child_count > old_child_count {
+        let append_children = new
+            .children(new_idx)
+            .skip(old_child_count)
+            .map(|child_idx| new.virtual_node(child_idx))
+            .collect();
+        patches.push(PatchOwned::AppendChildren(old_idx, append_children));
+    }
+
+    if new_child_count < old_child_count {
+        patches.push(PatchOwned::TruncateChildren(old_idx, new_child_count));
+    }
+
+    for (old_child, new_child) in old.children(old_idx).zip(new.children(new_idx)) {
+        diff_tree_node(old, old_child, new, new_child, patches);
+    }
+}
+
+/// The `class` attribute gets diffed as class tokens, the same as the classes of a `VElement`.
+fn diff_tree_attributes(
+    old: &VTree,
+    old_idx: usize,
+    new: &VTree,
+    new_idx: usize,
+    patches: &mut Vec<PatchOwned>,
+) {
+    let mut add_attributes = HashMap::new();
+    let mut remove_attributes = vec![];
+    let mut classes_changed = false;
+
+    for (name, new_value) in new.attrs(new_idx) {
+        if old.attr_value(old_idx, name) == Some(new_value) {
+            continue;
+        }
+
+        if name == "class" {
+            classes_changed = true;
+        } else {
+            add_attributes.insert(name.to_string(), new_value.to_string());
+        }
+    }
+
+    for (name, _old_value) in old.attrs(old_idx) {
+        if new.attr_value(new_idx, name).is_some() {
+            continue;
+        }
+
+        if name == "class" {
+            classes_changed = true;
+        } else {
+            remove_attributes.push(name.to_string());
+        }
+    }
+
+    if add_attributes.len() > 0 {
+        patches.push(PatchOwned::AddAttributes(old_idx, add_attributes));
+    }
+    if remove_attributes.len() > 0 {
+        patches.push(PatchOwned::RemoveAttributes(old_idx, remove_attributes));
+    }
+
+    if classes_changed {
+        diff_tree_classes(old, old_idx, new, new_idx, patches);
+    }
+}
+
+/// Only called once we know that the class attribute changed, which keeps the attribute loops
+/// above tight.
+fn diff_tree_classes(
+    old: &VTree,
+    old_idx: usize,
+    new: &VTree,
+    new_idx: usize,
+    patches: &mut Vec<PatchOwned>,
+) {
+    let old_classes = Classes::from(old.attr_value(old_idx, "class").unwrap_or(""));
+    let new_classes = Classes::from(new.attr_value(new_idx, "class").unwrap_or(""));
+
+    let add_classes: Vec<String> = new_classes
+        .iter()
+        .filter(|token| !old_classes.contains(token))
+        .map(|token| token.to_string())
+        .collect();
+    let remove_classes: Vec<String> = old_classes
+        .iter()
+        .filter(|token| !new_classes.contains(token))
+        .map(|token| token.to_string())
+        .collect();
+
+    if add_classes.len() > 0 {
+        patches.push(PatchOwned::AddClasses(old_idx, add_classes));
+    }
+    if remove_classes.len() > 0 {
+        patches.push(PatchOwned::RemoveClasses(old_idx, remove_classes));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::diff;
+    use crate::{html_tree, IntoTreeNodes, Patch, VirtualNode};
+
+    /// The tree diff should come up with the same patches as diffing the equivalent
+    /// `VirtualNode`s.
+    fn assert_same_patches(old: VTree, new: VTree, description: &str) {
+        let old_node = VirtualNode::from(&old);
+        let new_node = VirtualNode::from(&new);
+
+        let expected: Vec<PatchOwned> = diff(&old_node, &new_node)
+            .into_iter()
+            .map(Patch::into_owned)
+            .collect();
+
+        assert_eq!(diff_tree(&old, &new), expected, "{}", description);
+    }
+
+    #[test]
+    fn same_patches_as_diff() {
+        assert_same_patches(
+            html_tree! { <div> <b></b> </div> },
+            html_tree! { <span> <b></b> </span> },
+            "Replace the root",
+        );
+        assert_same_patches(
+            html_tree! { <div> <b>1</b> <b></b> </div> },
+            html_tree! { <div> <i>1</i> <i></i> </div> },
+            "Replace children",
+        );
+        assert_same_patches(
+            html_tree! { <div> <b> <i></i> <i></i> </b> <b></b> </div> },
+            html_tree! { <div> <b> <i></i> </b> <i></i> </div> },
+            "Truncate a child and replace the next node after its parent",
+        );
+        assert_same_patches(
+            html_tree! { <div> <b></b> </div> },
+            html_tree! { <div> <b></b> <em>New</em> Text </div> },
+            "Append children",
+        );
+        assert_same_patches(
+            html_tree! { <div id="a" title="t" key="1"> Old text </div> },
+            html_tree! { <div id="b" key="1"> New text </div> },
+            "Attributes and text",
+        );
+        assert_same_patches(
+            html_tree! { <div key="1"></div> },
+            html_tree! { <div key="2"></div> },
+            "Replace when the key changed",
+        );
+        assert_same_patches(
+            html_tree! { <div class="btn big"></div> },
+            html_tree! { <div class="btn active"></div> },
+            "Class tokens",
+        );
+    }
+
+    #[test]
+    fn braced_nodes() {
+        let items = |count: usize| -> Vec<VTree> {
+            (0..count)
+                .map(|idx| html_tree! { <li id=idx>{ idx.to_string() }</li> })
+                .collect()
+        };
+
+        assert_same_patches(
+            html_tree! { <ul> { items(3) } </ul> },
+            html_tree! { <ul> { items(1) } </ul> },
+            "Truncate braced children",
+        );
+        assert_same_patches(
+            html_tree! { <ul> { items(1) } { "Text" } </ul> },
+            html_tree! { <ul> { items(2) } { "Text" } </ul> },
+            "Braced children",
+        );
+    }
+
+    #[test]
+    fn no_patches_for_equal_trees() {
+        let tree = |text: &str| html_tree! { <div> <p class="a">{ text }</p> </div> };
+
+        assert_eq!(diff_tree(&tree("Hi"), &tree("Hi")), vec![]);
+    }
+}
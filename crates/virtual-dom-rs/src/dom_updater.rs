@@ -1,11 +1,17 @@
 //! Diff virtual-doms and patch the real DOM
 
-use crate::diff::diff;
-use crate::patch::patch;
+use crate::component_lifecycle::ComponentChanges;
+use crate::diff::{diff, diff_incremental, DiffBudget, DiffContinuation, DiffProgress};
+use crate::patch::{patch_fragment, patch_root, AsPatch, PatchError, PatchOwned};
+use crate::portal::{self, MountedPortal};
+#[cfg(feature = "stats")]
+use crate::stats::{self, UpdateStats};
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use virtual_node::DynClosure;
-use virtual_node::VirtualNode;
-use web_sys::{Element, Node};
+use virtual_node::{CreatedNode, DomBackend, RenderRequest, VElement, VirtualNode, WebSysBackend};
+use web_sys::Element;
 
 /// Closures that we are holding on to to make sure that they don't get invalidated after a
 /// VirtualNode is dropped.
@@ -20,9 +26,65 @@ use web_sys::{Element, Node};
 ///   appended or replaced and we will never free those closures.
 pub type ActiveClosures = HashMap<u32, Vec<DynClosure>>;
 
+/// What a `DomUpdater` should do when patching the DOM fails, typically because a third party
+/// script or a browser extension changed the DOM out from under us.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecoveryPolicy {
+    /// Return the `PatchError` from `update`, leaving the DOM as is. It might be partially
+    /// patched.
+    ReturnError,
+    /// Throw away the root node and re-create it from the new virtual dom.
+    ///
+    /// The new root node replaces the old one within its parent, so anything that was in the
+    /// old root node is lost.
+    RecreateRoot,
+}
+
+impl Default for RecoveryPolicy {
+    fn default() -> Self {
+        RecoveryPolicy::ReturnError
+    }
+}
+
+/// Whether or not an `update_in_slices` has patched the DOM yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpdateProgress {
+    /// The whole virtual dom was diffed and the DOM was patched.
+    Done,
+    /// There is more diffing to do, see `DomUpdater::continue_update`.
+    Pending,
+}
+
+/// The tag of the virtual element that holds the root nodes of a fragment. It never gets
+/// created in the DOM.
+const FRAGMENT_TAG: &str = "#document-fragment";
+
+/// The DOM nodes that the root of the virtual dom was rendered into.
+enum RootNodes<N> {
+    /// A single root node.
+    Node(N),
+    /// The root nodes of a fragment, followed by a comment node that marks the end of the
+    /// fragment so that we know where to insert new root nodes.
+    Fragment { nodes: Vec<N>, end: N },
+}
+
+/// An update whose diff is spread across several frames.
+struct PendingUpdate {
+    new_vdom: VirtualNode,
+    budget: DiffBudget,
+    continuation: DiffContinuation,
+    /// The stats of the slices that were diffed so far.
+    #[cfg(feature = "stats")]
+    stats: UpdateStats,
+}
+
 /// Used for keeping a real DOM node up to date based on the current VirtualNode
 /// and a new incoming VirtualNode that represents our latest DOM state.
-pub struct DomUpdater {
+///
+/// By default the real DOM is the browser's, but a `DomUpdater` can keep any `DomBackend`
+/// up to date.
+pub struct DomUpdater<B: DomBackend = WebSysBackend> {
+    backend: B,
     current_vdom: VirtualNode,
     /// The closures that are currently attached to elements in the page.
     ///
@@ -31,20 +93,26 @@ pub struct DomUpdater {
     /// FIXME: Drop them when the element is no longer in the page. Need to figure out
     /// a good strategy for when to do this.
     pub active_closures: ActiveClosures,
-    root_node: Node,
+    root_nodes: RootNodes<B::Node>,
+    /// The children of every portal in the current virtual dom, depth first.
+    portals: Vec<MountedPortal<B::Node>>,
+    /// Called when a mounted component asks for a re-render.
+    on_render_request: Rc<RefCell<Option<Box<dyn Fn()>>>>,
+    recovery_policy: RecoveryPolicy,
+    /// The update from `update_in_slices` that we're still diffing, if any.
+    pending_update: Option<PendingUpdate>,
+    /// Called with the stats of every `update`, and of every `update_in_slices` once its patches
+    /// were applied.
+    #[cfg(feature = "stats")]
+    on_update_stats: Option<Box<dyn Fn(&UpdateStats)>>,
 }
 
-impl DomUpdater {
+impl DomUpdater<WebSysBackend> {
     /// Create a new `DomUpdater`.
     ///
     /// A root `Node` will be created but not added to your DOM.
     pub fn new(current_vdom: VirtualNode) -> DomUpdater {
-        let created_node = current_vdom.create_dom_node();
-        DomUpdater {
-            current_vdom,
-            active_closures: created_node.closures,
-            root_node: created_node.node,
-        }
+        DomUpdater::with_backend(WebSysBackend::new(), current_vdom)
     }
 
     /// Create a new `DomUpdater`.
@@ -52,15 +120,14 @@ impl DomUpdater {
     /// A root `Node` will be created and appended (as a child) to your passed
     /// in mount element.
     pub fn new_append_to_mount(current_vdom: VirtualNode, mount: &Element) -> DomUpdater {
-        let created_node = current_vdom.create_dom_node();
+        let backend = WebSysBackend::new();
+        let created_node = current_vdom
+            .create_dom_node_with(&backend)
+            .expect("Could not create the root node");
         mount
             .append_child(&created_node.node)
             .expect("Could not append child to mount");
-        DomUpdater {
-            current_vdom,
-            active_closures: created_node.closures,
-            root_node: created_node.node,
-        }
+        DomUpdater::from_created_node(backend, current_vdom, created_node)
     }
 
     /// Create a new `DomUpdater`.
@@ -68,36 +135,551 @@ impl DomUpdater {
     /// A root `Node` will be created and it will replace your passed in mount
     /// element.
     pub fn new_replace_mount(current_vdom: VirtualNode, mount: Element) -> DomUpdater {
-        let created_node = current_vdom.create_dom_node();
+        let backend = WebSysBackend::new();
+        let created_node = current_vdom
+            .create_dom_node_with(&backend)
+            .expect("Could not create the root node");
         mount
             .replace_with_with_node_1(&created_node.node)
             .expect("Could not replace mount element");
-        DomUpdater {
+        DomUpdater::from_created_node(backend, current_vdom, created_node)
+    }
+
+    /// Create a new `DomUpdater` whose root is a fragment, a list of nodes without a parent
+    /// element of their own.
+    ///
+    /// The root nodes will be created and appended (as children) to your passed in mount
+    /// element. See `update_fragment`.
+    pub fn new_fragment_append_to_mount(roots: Vec<VirtualNode>, mount: &Element) -> DomUpdater {
+        DomUpdater::with_backend_fragment(WebSysBackend::new(), roots, mount.as_ref())
+    }
+}
+
+impl<B: DomBackend> DomUpdater<B> {
+    /// Create a new `DomUpdater` that keeps a `DomBackend` up to date.
+    ///
+    /// A root node will be created but not added to your DOM.
+    ///
+    /// The children of portals get created within their targets, so the targets need to be
+    /// in the DOM already. Panics if one of them can't be found, or if the backend fails to
+    /// create the root node.
+    pub fn with_backend(backend: B, current_vdom: VirtualNode) -> DomUpdater<B> {
+        let created_node = current_vdom
+            .create_dom_node_with(&backend)
+            .expect("Could not create the root node");
+        DomUpdater::from_created_node(backend, current_vdom, created_node)
+    }
+
+    /// Create a new `DomUpdater` whose root is a fragment, a list of nodes without a parent
+    /// element of their own, that keeps a `DomBackend` up to date.
+    ///
+    /// The root nodes will be created and appended (as children) to your passed in mount
+    /// element, followed by a comment node that marks the end of the fragment. Root nodes that
+    /// get added by later updates are inserted right before that comment, so the mount element
+    /// can have other children before and after the fragment.
+    ///
+    /// Panics if the backend fails to create the root nodes, or if a portal target can't be
+    /// found.
+    pub fn with_backend_fragment(
+        backend: B,
+        roots: Vec<VirtualNode>,
+        mount: &B::Node,
+    ) -> DomUpdater<B> {
+        // `ptfe` = Percy fragment end
+        let end = backend.create_comment("ptfe");
+        backend
+            .append_child(mount, &end)
+            .expect("Could not append the end of the fragment to mount");
+
+        let (nodes, active_closures) = insert_roots(&backend, &roots, &end)
+            .expect("Could not create the root nodes of the fragment");
+
+        DomUpdater::from_root_nodes(
+            backend,
+            fragment(roots),
+            RootNodes::Fragment { nodes, end },
+            active_closures,
+        )
+    }
+
+    fn from_created_node(
+        backend: B,
+        current_vdom: VirtualNode,
+        created_node: CreatedNode<B::Node>,
+    ) -> DomUpdater<B> {
+        DomUpdater::from_root_nodes(
+            backend,
             current_vdom,
-            active_closures: created_node.closures,
-            root_node: created_node.node,
-        }
+            RootNodes::Node(created_node.node),
+            created_node.closures,
+        )
+    }
+
+    fn from_root_nodes(
+        backend: B,
+        current_vdom: VirtualNode,
+        root_nodes: RootNodes<B::Node>,
+        mut active_closures: ActiveClosures,
+    ) -> DomUpdater<B> {
+        let (portals, portal_closures) =
+            portal::mount_portals(&backend, &portal::collect_portals(&current_vdom))
+                .expect("Could not create the children of a portal");
+
+        active_closures.extend(portal_closures);
+
+        let dom_updater = DomUpdater {
+            backend,
+            current_vdom,
+            active_closures,
+            root_nodes,
+            portals,
+            on_render_request: Rc::new(RefCell::new(None)),
+            recovery_policy: RecoveryPolicy::default(),
+            pending_update: None,
+            #[cfg(feature = "stats")]
+            on_update_stats: None,
+        };
+
+        ComponentChanges::mount_all(&dom_updater.current_vdom)
+            .mounted_and_updated(&dom_updater.render_request());
+
+        dom_updater
+    }
+
+    /// Set the callback that gets called whenever a mounted component wants to be
+    /// re-rendered, typically by calling `update` with your latest virtual dom.
+    pub fn on_render_request<F: Fn() + 'static>(&mut self, on_render_request: F) {
+        *self.on_render_request.borrow_mut() = Some(Box::new(on_render_request));
     }
 
     /// Diff the current virtual dom with the new virtual dom that is being passed in.
     ///
     /// Then use that diff to patch the real DOM in the user's browser so that they are
     /// seeing the latest state of the application.
-    pub fn update(&mut self, new_vdom: VirtualNode) {
-        let patches = diff(&self.current_vdom, &new_vdom);
+    ///
+    /// The children of portals get patched within their targets, and portals that are no
+    /// longer in the virtual dom have their children removed from their targets.
+    ///
+    /// Components that were added, kept or removed by the update have their `mounted`,
+    /// `updated` or `will_unmount` lifecycle methods called.
+    ///
+    /// If patching fails we either return the error or recover from it, depending on the
+    /// `RecoveryPolicy`. Either way the new virtual dom becomes the current virtual dom.
+    ///
+    /// An update from `update_in_slices` that is still pending gets discarded.
+    ///
+    /// When the root is a fragment the new virtual dom becomes its only root node.
+    pub fn update(&mut self, new_vdom: VirtualNode) -> Result<(), PatchError<B::Error>> {
+        self.update_fragment(vec![new_vdom])
+    }
+
+    /// Just like `update`, but the new virtual dom is a list of root nodes. See
+    /// `with_backend_fragment`.
+    ///
+    /// Root nodes are matched up by their order. Extra new root nodes get inserted at the end
+    /// of the fragment and extra old root nodes get removed.
+    ///
+    /// Panics if this `DomUpdater` has a single root node and `roots` isn't a single node.
+    pub fn update_fragment(&mut self, roots: Vec<VirtualNode>) -> Result<(), PatchError<B::Error>> {
+        #[cfg(feature = "stats")]
+        return self.diff_and_patch_with_stats(roots).map(|_stats| ());
+
+        #[cfg(not(feature = "stats"))]
+        self.diff_and_patch(roots)
+    }
+
+    /// Just like `update`, but also returns stats about the diffing and patching.
+    ///
+    /// The stats are also handed to the `on_update_stats` callback.
+    #[cfg(feature = "stats")]
+    pub fn update_with_stats(
+        &mut self,
+        new_vdom: VirtualNode,
+    ) -> Result<UpdateStats, PatchError<B::Error>> {
+        self.diff_and_patch_with_stats(vec![new_vdom])
+    }
+
+    #[cfg(feature = "stats")]
+    fn diff_and_patch_with_stats(
+        &mut self,
+        roots: Vec<VirtualNode>,
+    ) -> Result<UpdateStats, PatchError<B::Error>> {
+        let (result, stats) = stats::collect(|| self.diff_and_patch(roots));
+
+        if let Some(on_update_stats) = self.on_update_stats.as_ref() {
+            on_update_stats(&stats);
+        }
+
+        result.map(|()| stats)
+    }
+
+    /// Set a callback that gets called with the stats of every `update`, such as to log slow
+    /// renders.
+    ///
+    /// An `update_in_slices` is reported once, after its patches were applied, with the stats of
+    /// all of its slices added up.
+    #[cfg(feature = "stats")]
+    pub fn on_update_stats<F: Fn(&UpdateStats) + 'static>(&mut self, on_update_stats: F) {
+        self.on_update_stats = Some(Box::new(on_update_stats));
+    }
+
+    fn diff_and_patch(&mut self, roots: Vec<VirtualNode>) -> Result<(), PatchError<B::Error>> {
+        // The pending update was diffed against a virtual dom that is about to be outdated.
+        self.pending_update = None;
+
+        let new_vdom = self.root_vdom(roots);
+
+        let (component_changes, patched) = {
+            let patches = diff(&self.current_vdom, &new_vdom);
+
+            let component_changes =
+                ComponentChanges::between(&self.current_vdom, &new_vdom, &patches);
+            component_changes.will_unmount();
+
+            let patched = patch_dom(
+                &self.backend,
+                &mut self.root_nodes,
+                &mut self.portals,
+                &self.current_vdom,
+                &new_vdom,
+                patches,
+            );
+
+            (component_changes, patched)
+        };
+
+        self.finish_update(new_vdom, component_changes, patched)
+    }
+
+    /// Just like `update`, but the diffing gets spread across several calls so that diffing a
+    /// very large virtual dom doesn't hold up a frame.
+    ///
+    /// We diff until the budget is spent. If there is more to diff we return
+    /// `UpdateProgress::Pending`, and you call `continue_update`, typically once per animation
+    /// frame, until it's done. The DOM isn't touched until the whole diff is done, then all of
+    /// the patches get applied at once.
+    ///
+    /// An update that is still pending gets discarded, so the newest virtual dom always wins.
+    pub fn update_in_slices(
+        &mut self,
+        new_vdom: VirtualNode,
+        budget: DiffBudget,
+    ) -> Result<UpdateProgress, PatchError<B::Error>> {
+        self.pending_update = None;
+
+        let new_vdom = self.root_vdom(vec![new_vdom]);
+
+        #[cfg(feature = "stats")]
+        return self.diff_slice_with_stats(new_vdom, budget, None, UpdateStats::default());
+
+        #[cfg(not(feature = "stats"))]
+        self.diff_slice(new_vdom, budget, None)
+    }
+
+    /// Diff another slice of the pending update from `update_in_slices`, patching the DOM once
+    /// the diff is done.
+    ///
+    /// Does nothing if there is no pending update.
+    pub fn continue_update(&mut self) -> Result<UpdateProgress, PatchError<B::Error>> {
+        match self.pending_update.take() {
+            #[cfg(feature = "stats")]
+            Some(pending) => self.diff_slice_with_stats(
+                pending.new_vdom,
+                pending.budget,
+                Some(pending.continuation),
+                pending.stats,
+            ),
+            #[cfg(not(feature = "stats"))]
+            Some(pending) => {
+                self.diff_slice(pending.new_vdom, pending.budget, Some(pending.continuation))
+            }
+            None => Ok(UpdateProgress::Done),
+        }
+    }
+
+    /// Whether or not an update from `update_in_slices` is still being diffed.
+    pub fn has_pending_update(&self) -> bool {
+        self.pending_update.is_some()
+    }
+
+    /// Diff a slice while adding to the stats of the earlier slices. The stats get handed to the
+    /// `on_update_stats` callback once the patches were applied.
+    #[cfg(feature = "stats")]
+    fn diff_slice_with_stats(
+        &mut self,
+        new_vdom: VirtualNode,
+        budget: DiffBudget,
+        continuation: Option<DiffContinuation>,
+        stats: UpdateStats,
+    ) -> Result<UpdateProgress, PatchError<B::Error>> {
+        let (result, stats) =
+            stats::resume(stats, || self.diff_slice(new_vdom, budget, continuation));
+
+        match self.pending_update.as_mut() {
+            Some(pending) => pending.stats = stats,
+            None => {
+                if let Some(on_update_stats) = self.on_update_stats.as_ref() {
+                    on_update_stats(&stats);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn diff_slice(
+        &mut self,
+        new_vdom: VirtualNode,
+        budget: DiffBudget,
+        continuation: Option<DiffContinuation>,
+    ) -> Result<UpdateProgress, PatchError<B::Error>> {
+        match diff_incremental(&self.current_vdom, &new_vdom, budget, continuation) {
+            DiffProgress::Paused(continuation) => {
+                self.pending_update = Some(PendingUpdate {
+                    new_vdom,
+                    budget,
+                    continuation,
+                    #[cfg(feature = "stats")]
+                    stats: UpdateStats::default(),
+                });
+
+                Ok(UpdateProgress::Pending)
+            }
+            DiffProgress::Done(patches) => self
+                .patch_owned(new_vdom, patches)
+                .map(|()| UpdateProgress::Done),
+        }
+    }
+
+    fn patch_owned(
+        &mut self,
+        new_vdom: VirtualNode,
+        patches: Vec<PatchOwned>,
+    ) -> Result<(), PatchError<B::Error>> {
+        let component_changes = ComponentChanges::between(&self.current_vdom, &new_vdom, &patches);
+        component_changes.will_unmount();
 
-        let active_closures = patch(self.root_node.clone(), &patches).unwrap();
+        let patched = patch_dom(
+            &self.backend,
+            &mut self.root_nodes,
+            &mut self.portals,
+            &self.current_vdom,
+            &new_vdom,
+            patches,
+        );
 
-        self.active_closures.extend(active_closures);
+        self.finish_update(new_vdom, component_changes, patched)
+    }
 
+    /// Make the new virtual dom the current one once the DOM was patched, recovering from a
+    /// failed patch depending on the `RecoveryPolicy`.
+    fn finish_update(
+        &mut self,
+        new_vdom: VirtualNode,
+        component_changes: ComponentChanges,
+        patched: Result<ActiveClosures, PatchError<B::Error>>,
+    ) -> Result<(), PatchError<B::Error>> {
         self.current_vdom = new_vdom;
+
+        let result = match patched {
+            Ok(active_closures) => {
+                self.active_closures.extend(active_closures);
+                Ok(())
+            }
+            Err(err) => match self.recovery_policy {
+                RecoveryPolicy::ReturnError => Err(err),
+                RecoveryPolicy::RecreateRoot => self.recreate_root(),
+            },
+        };
+
+        component_changes.mounted_and_updated(&self.render_request());
+
+        result
+    }
+
+    /// Set what happens when patching the DOM fails during an `update`.
+    pub fn set_recovery_policy(&mut self, recovery_policy: RecoveryPolicy) {
+        self.recovery_policy = recovery_policy;
+    }
+
+    /// Throw away the root node and create a new one from the current virtual dom.
+    ///
+    /// The new root node replaces the old one within its parent and the children of portals
+    /// get re-created within their targets. This is useful for getting back to a known good
+    /// state after an `update` returned an error.
+    ///
+    /// The root nodes of a fragment get replaced by new ones at the end of the fragment.
+    ///
+    /// Returns `PatchError::DetachedRoot` if the root node was removed from its parent, since
+    /// there would be nowhere to put the new root node.
+    pub fn recreate_root(&mut self) -> Result<(), PatchError<B::Error>> {
+        let backend = &self.backend;
+
+        // The closures of the old root node's elements are no longer in the page.
+        self.active_closures = match &mut self.root_nodes {
+            RootNodes::Node(root_node) => {
+                if backend.parent_node(root_node).is_none() {
+                    return Err(PatchError::DetachedRoot);
+                }
+
+                let created_node = self.current_vdom.create_dom_node_with(backend)?;
+
+                backend.replace_with(root_node, &created_node.node)?;
+
+                *root_node = created_node.node;
+                created_node.closures
+            }
+            RootNodes::Fragment { nodes, end } => {
+                let (created_nodes, active_closures) =
+                    insert_roots(backend, fragment_roots(&self.current_vdom), end)?;
+
+                for node in nodes.iter() {
+                    if let Some(parent) = backend.parent_node(node) {
+                        backend.remove_child(&parent, node)?;
+                    }
+                }
+
+                *nodes = created_nodes;
+                active_closures
+            }
+        };
+
+        portal::remove_portals(&self.backend, &self.portals)?;
+        self.portals = vec![];
+
+        let (portals, portal_closures) = portal::mount_portals(
+            &self.backend,
+            &portal::collect_portals(&self.current_vdom),
+        )?;
+        self.portals = portals;
+        self.active_closures.extend(portal_closures);
+
+        Ok(())
     }
 
     /// Return the root node of your application, the highest ancestor of all other nodes in
     /// your real DOM tree.
-    pub fn root_node(&self) -> Node {
+    ///
+    /// This is either an element or a text node. When an update replaces the root node, such
+    /// as when the root element's tag changed, the new root node takes the old one's place
+    /// within its parent and gets returned from then on.
+    ///
+    /// When the root is a fragment this is its first root node, or the comment node that marks
+    /// the end of the fragment if it is empty. See `root_nodes`.
+    pub fn root_node(&self) -> B::Node {
         // Note that we're cloning the `web_sys::Node`, not the DOM element.
         // So we're effectively cloning a pointer here, which is fast.
-        self.root_node.clone()
+        match &self.root_nodes {
+            RootNodes::Node(root_node) => root_node.clone(),
+            RootNodes::Fragment { nodes, end } => nodes.first().unwrap_or(end).clone(),
+        }
+    }
+
+    /// Return every root node of your application, in order.
+    ///
+    /// This is a single node, unless the root is a fragment.
+    pub fn root_nodes(&self) -> Vec<B::Node> {
+        match &self.root_nodes {
+            RootNodes::Node(root_node) => vec![root_node.clone()],
+            RootNodes::Fragment { nodes, .. } => nodes.clone(),
+        }
+    }
+
+    /// The `DomBackend` that this `DomUpdater` keeps up to date.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// The virtual dom that gets diffed with the current one, for the given root nodes.
+    fn root_vdom(&self, mut roots: Vec<VirtualNode>) -> VirtualNode {
+        match self.root_nodes {
+            RootNodes::Node(_) => {
+                assert_eq!(
+                    roots.len(),
+                    1,
+                    "A DomUpdater with a single root node can only be updated with a single node"
+                );
+                roots.remove(0)
+            }
+            RootNodes::Fragment { .. } => fragment(roots),
+        }
+    }
+
+    /// The `RenderRequest` that we hand to mounted components.
+    fn render_request(&self) -> RenderRequest {
+        let on_render_request = Rc::clone(&self.on_render_request);
+
+        Rc::new(move || {
+            if let Some(on_render_request) = on_render_request.borrow().as_ref() {
+                on_render_request();
+            }
+        })
+    }
+}
+
+/// Patch the root nodes and the children of portals.
+fn patch_dom<B: DomBackend, P: AsPatch>(
+    backend: &B,
+    root_nodes: &mut RootNodes<B::Node>,
+    portals: &mut Vec<MountedPortal<B::Node>>,
+    old_vdom: &VirtualNode,
+    new_vdom: &VirtualNode,
+    patches: Vec<P>,
+) -> Result<ActiveClosures, PatchError<B::Error>> {
+    let old_portals = portal::collect_portals(old_vdom);
+    let new_portals = portal::collect_portals(new_vdom);
+
+    let mut active_closures = match root_nodes {
+        RootNodes::Node(root_node) => patch_root(backend, root_node, 0, patches)?,
+        RootNodes::Fragment { nodes, end } => {
+            patch_fragment(backend, nodes, end, fragment_roots(old_vdom), patches)?
+        }
+    };
+
+    let portal_closures = portal::patch_portals(backend, portals, &old_portals, &new_portals)?;
+    active_closures.extend(portal_closures);
+
+    Ok(active_closures)
+}
+
+/// The virtual element that holds the root nodes of a fragment. Diffing two of them gives us
+/// the patches for the root nodes, see `patch_fragment`.
+fn fragment(roots: Vec<VirtualNode>) -> VirtualNode {
+    let mut element = VElement::new(FRAGMENT_TAG);
+    element.children = roots;
+
+    VirtualNode::Element(element)
+}
+
+fn fragment_roots(vdom: &VirtualNode) -> &[VirtualNode] {
+    match vdom {
+        VirtualNode::Element(element) => element.rendered_children(),
+        _ => &[],
     }
 }
+
+/// Create root nodes and insert them right before the end of a fragment.
+fn insert_roots<B: DomBackend>(
+    backend: &B,
+    roots: &[VirtualNode],
+    end: &B::Node,
+) -> Result<(Vec<B::Node>, ActiveClosures), PatchError<B::Error>> {
+    let parent = backend
+        .parent_node(end)
+        .ok_or(PatchError::MissingNode(0))?;
+    let namespace = backend.children_namespace(&parent);
+
+    let mut nodes = vec![];
+    let mut active_closures = HashMap::new();
+
+    for root in roots {
+        let created_node = root.create_dom_node_within(backend, namespace)?;
+
+        backend.insert_before(&parent, &created_node.node, end)?;
+
+        nodes.push(created_node.node);
+        active_closures.extend(created_node.closures);
+    }
+
+    Ok((nodes, active_closures))
+}
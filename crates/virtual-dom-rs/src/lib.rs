@@ -26,18 +26,36 @@ pub use crate::diff::*;
 mod patch;
 pub use crate::patch::*;
 
-pub use html_macro::html;
+pub use html_macro::{html, html_tree};
+
+pub mod wire;
+
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(not(feature = "stats"))]
+mod stats;
+
+mod component_lifecycle;
+
+mod portal;
 
 mod dom_updater;
-pub use self::dom_updater::DomUpdater;
+pub use self::dom_updater::{DomUpdater, RecoveryPolicy, UpdateProgress};
+
+mod scheduler;
+pub use self::scheduler::{FrameSource, ManualFrameSource, RequestAnimationFrame, Scheduler};
 
 /// Exports structs and macros that you'll almost always want access to in a virtual-dom
 /// powered application
 pub mod prelude {
     pub use crate::dom_updater::DomUpdater;
     pub use crate::VirtualNode;
-    pub use html_macro::html;
+    pub use html_macro::{html, html_tree};
+    pub use virtual_node::classes;
+    pub use virtual_node::{Component, ComponentHandle, NodeRef};
     pub use std::vec::IntoIter;
     pub use virtual_node::IterableNodes;
     pub use virtual_node::View;
+    pub use virtual_node::{IntoTreeNodes, VTree};
+    pub use virtual_node::{InsertNativeEvent, NativeEvent, SkipNativeEvent};
 }
@@ -1,20 +1,113 @@
-use crate::patch::Patch;
-use std::cmp::min;
+use crate::patch::{AsPatch, Patch};
 use std::collections::HashMap;
 use std::collections::HashSet;
 
 use crate::dom_updater::ActiveClosures;
-use wasm_bindgen::JsCast;
+use crate::stats::{self, Phase};
+use std::fmt;
+use virtual_node::{DomBackend, NodeKind, VirtualNode, WebSysBackend};
 use wasm_bindgen::JsValue;
-use web_sys::{Element, Node, Text};
+use web_sys::Node;
+
+/// The reasons that patching a DOM can fail.
+///
+/// This typically happens when something other than virtual-dom-rs, such as a third party
+/// script or a browser extension, changed the DOM so that it no longer matches the old
+/// virtual dom.
+#[derive(Debug, PartialEq)]
+pub enum PatchError<E> {
+    /// There was no node at the index that a patch applies to.
+    MissingNode(usize),
+    /// The node at the index that a patch applies to can't receive that patch, such as an
+    /// element receiving a `ChangeText` patch.
+    UnexpectedNodeKind {
+        /// The index of the node that we tried to patch.
+        node_idx: usize,
+        /// The kind of node that we found at that index.
+        node_kind: NodeKind,
+    },
+    /// There was no element for a portal to render its children into.
+    MissingPortalTarget(String),
+    /// The root node had no parent, so there was nowhere to put a re-created root node.
+    DetachedRoot,
+    /// The `DomBackend` failed to carry out an operation. In the browser this is a JavaScript
+    /// exception.
+    Backend(E),
+}
+
+impl<E> From<E> for PatchError<E> {
+    fn from(err: E) -> Self {
+        PatchError::Backend(err)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for PatchError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::MissingNode(node_idx) => {
+                write!(f, "Could not find the node at index {} to patch", node_idx)
+            }
+            PatchError::UnexpectedNodeKind {
+                node_idx,
+                node_kind,
+            } => write!(
+                f,
+                "The {:?} node at index {} can not receive this patch",
+                node_kind, node_idx
+            ),
+            PatchError::MissingPortalTarget(target) => {
+                write!(f, "Could not find the portal target {}", target)
+            }
+            PatchError::DetachedRoot => write!(f, "The root node is not within a parent node"),
+            PatchError::Backend(err) => write!(f, "DOM operation failed: {:?}", err),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for PatchError<E> {}
+
+impl From<PatchError<JsValue>> for JsValue {
+    fn from(err: PatchError<JsValue>) -> JsValue {
+        match err {
+            PatchError::Backend(err) => err,
+            other => JsValue::from_str(&other.to_string()),
+        }
+    }
+}
 
 /// Apply all of the patches to our old root node in order to create the new root node
 /// that we desire.
 /// This is usually used after diffing two virtual nodes.
-pub fn patch<N: Into<Node>>(root_node: N, patches: &Vec<Patch>) -> Result<ActiveClosures, JsValue> {
-    let root_node: Node = root_node.into();
+///
+/// The patches can either be the `Patch`es that `diff` returns or `PatchOwned`s.
+pub fn patch<N: Into<Node>, P: AsPatch>(
+    root_node: N,
+    patches: &[P],
+) -> Result<ActiveClosures, PatchError<JsValue>> {
+    patch_with(&WebSysBackend::new(), root_node.into(), patches)
+}
+
+/// Apply all of the patches to our old root node within the given `DomBackend`.
+///
+/// If any of the nodes that the patches apply to can't be found we return an error before
+/// touching the DOM. Other errors can leave the DOM partially patched.
+pub fn patch_with<B: DomBackend, P: AsPatch>(
+    backend: &B,
+    root_node: B::Node,
+    patches: &[P],
+) -> Result<ActiveClosures, PatchError<B::Error>> {
+    patch_with_root_idx(backend, root_node, 0, patches)
+}
 
-    let mut cur_node_idx = 0;
+/// Just like `patch_with`, but the root node has the given index instead of 0, such as when
+/// it is one of the root nodes of a fragment.
+fn patch_with_root_idx<B: DomBackend, P: AsPatch>(
+    backend: &B,
+    root_node: B::Node,
+    root_idx: usize,
+    patches: &[P],
+) -> Result<ActiveClosures, PatchError<B::Error>> {
+    let mut cur_node_idx = root_idx;
 
     let mut nodes_to_find = HashSet::new();
 
@@ -28,152 +121,347 @@ pub fn patch<N: Into<Node>>(root_node: N, patches: &Vec<Patch>) -> Result<Active
     // Closures that were added to the DOM during this patch operation.
     let mut active_closures = HashMap::new();
 
-    find_nodes(
-        root_node,
-        &mut cur_node_idx,
-        &mut nodes_to_find,
-        &mut element_nodes_to_patch,
-        &mut text_nodes_to_patch,
-    );
+    stats::timed(Phase::FindNodes, || {
+        find_nodes(
+            backend,
+            root_node,
+            &mut cur_node_idx,
+            &mut nodes_to_find,
+            &mut element_nodes_to_patch,
+            &mut text_nodes_to_patch,
+        )
+    })?;
+
+    if let Some(missing_node_idx) = nodes_to_find.iter().min() {
+        return Err(PatchError::MissingNode(*missing_node_idx));
+    }
 
-    for patch in patches {
-        let patch_node_idx = patch.node_idx();
+    stats::timed(Phase::ApplyPatches, || {
+        for patch in patches {
+            let patch_node_idx = patch.node_idx();
+            let patch = patch.as_patch();
 
-        if let Some(element) = element_nodes_to_patch.get(&patch_node_idx) {
-            let new_closures = apply_element_patch(&element, &patch)?;
-            active_closures.extend(new_closures);
-            continue;
+            stats::patch_applied(&patch);
+
+            if let Some(element) = element_nodes_to_patch.get(&patch_node_idx) {
+                let new_closures =
+                    apply_element_patch(backend, &element, patch_node_idx, &patch)?;
+                active_closures.extend(new_closures);
+                continue;
+            }
+
+            if let Some(text_node) = text_nodes_to_patch.get(&patch_node_idx) {
+                let new_closures = apply_text_patch(backend, &text_node, patch_node_idx, &patch)?;
+                active_closures.extend(new_closures);
+                continue;
+            }
+
+            unreachable!("Getting here means we didn't find the element or next node that we were supposed to patch.")
         }
 
-        if let Some(text_node) = text_nodes_to_patch.get(&patch_node_idx) {
-            apply_text_patch(&text_node, &patch)?;
+        Ok(active_closures)
+    })
+}
+
+/// Just like `patch_with`, but if the root node gets replaced we point `root_node` at its
+/// replacement.
+///
+/// The root node has the given index, see `patch_with_root_idx`.
+pub(crate) fn patch_root<B: DomBackend, P: AsPatch>(
+    backend: &B,
+    root_node: &mut B::Node,
+    root_idx: usize,
+    mut patches: Vec<P>,
+) -> Result<ActiveClosures, PatchError<B::Error>> {
+    // A replaced root node is always the last patch, after the patches that get applied to the
+    // old root node before it gets removed.
+    let replaces_root = match patches.last().map(AsPatch::as_patch) {
+        Some(patch) => match patch.as_ref() {
+            Patch::Replace(node_idx, _) => *node_idx == root_idx,
+            _ => false,
+        },
+        None => false,
+    };
+    let replaced_root = if replaces_root { patches.pop() } else { None };
+
+    let mut active_closures = patch_with_root_idx(backend, root_node.clone(), root_idx, &patches)?;
+
+    if let Some(replaced_root) = replaced_root {
+        if let Patch::Replace(_node_idx, new_node) = replaced_root.as_patch().as_ref() {
+            stats::patch_applied(&Patch::Replace(root_idx, new_node));
+
+            let created_node =
+                new_node.create_dom_node_within(backend, parent_namespace(backend, root_node))?;
+
+            backend.replace_with(root_node, &created_node.node)?;
+
+            *root_node = created_node.node;
+            active_closures.extend(created_node.closures);
+        }
+    }
+
+    Ok(active_closures)
+}
+
+/// Patch the root nodes of a fragment, which are the children of a virtual element that
+/// doesn't exist in the DOM.
+///
+/// The patches are the diff of two such virtual elements, so the fragment's root nodes have
+/// the indices of the old element's children. New root nodes get inserted right before the
+/// `end` of the fragment.
+pub(crate) fn patch_fragment<B: DomBackend, P: AsPatch>(
+    backend: &B,
+    root_nodes: &mut Vec<B::Node>,
+    end: &B::Node,
+    old_roots: &[VirtualNode],
+    patches: Vec<P>,
+) -> Result<ActiveClosures, PatchError<B::Error>> {
+    let mut root_patches: Vec<(usize, Vec<P>)> = vec![];
+    let mut root_idx = 1;
+
+    for old_root in old_roots {
+        root_patches.push((root_idx, vec![]));
+        root_idx += node_count(old_root);
+    }
+
+    let mut fragment_patches = vec![];
+
+    for patch in patches {
+        let node_idx = patch.node_idx();
+
+        if node_idx == 0 {
+            fragment_patches.push(patch);
             continue;
         }
 
-        unreachable!("Getting here means we didn't find the element or next node that we were supposed to patch.")
+        match root_patches.iter().rposition(|(root_idx, _)| *root_idx <= node_idx) {
+            Some(root) => root_patches[root].1.push(patch),
+            None => return Err(PatchError::MissingNode(node_idx)),
+        };
+    }
+
+    let mut active_closures = HashMap::new();
+
+    // Truncated root nodes get their removal patches before they get removed below.
+    for (root_node, (root_idx, patches)) in root_nodes.iter_mut().zip(root_patches) {
+        active_closures.extend(patch_root(backend, root_node, root_idx, patches)?);
+    }
+
+    let parent = backend
+        .parent_node(end)
+        .ok_or(PatchError::MissingNode(0))?;
+
+    for patch in fragment_patches {
+        let patch = patch.as_patch();
+
+        stats::patch_applied(&patch);
+
+        match patch.as_ref() {
+            Patch::TruncateChildren(_node_idx, num_children_remaining) => {
+                for root_node in root_nodes.split_off(*num_children_remaining) {
+                    backend.remove_child(&parent, &root_node)?;
+                }
+            }
+            Patch::AppendChildren(_node_idx, new_nodes) => {
+                let namespace = backend.children_namespace(&parent);
+
+                for new_node in new_nodes {
+                    let created_node = new_node.create_dom_node_within(backend, namespace)?;
+
+                    backend.insert_before(&parent, &created_node.node, end)?;
+                    root_nodes.push(created_node.node);
+
+                    active_closures.extend(created_node.closures);
+                }
+            }
+            _ => unreachable!("Only the children of a fragment get patched"),
+        }
     }
 
     Ok(active_closures)
 }
 
-fn find_nodes(
-    root_node: Node,
+/// The number of node indices that a node and its descendants take up.
+fn node_count(node: &VirtualNode) -> usize {
+    match node {
+        VirtualNode::Element(element) => {
+            1 + element
+                .rendered_children()
+                .iter()
+                .map(node_count)
+                .sum::<usize>()
+        }
+        VirtualNode::Text(_) | VirtualNode::Portal(_) => 1,
+    }
+}
+
+fn find_nodes<B: DomBackend>(
+    backend: &B,
+    root_node: B::Node,
     cur_node_idx: &mut usize,
     nodes_to_find: &mut HashSet<usize>,
-    element_nodes_to_patch: &mut HashMap<usize, Element>,
-    text_nodes_to_patch: &mut HashMap<usize, Text>,
-) {
+    element_nodes_to_patch: &mut HashMap<usize, B::Node>,
+    text_nodes_to_patch: &mut HashMap<usize, B::Node>,
+) -> Result<(), PatchError<B::Error>> {
     if nodes_to_find.len() == 0 {
-        return;
+        return Ok(());
     }
 
-    // We use child_nodes() instead of children() because children() ignores text nodes
-    let children = root_node.child_nodes();
-    let child_node_count = children.length();
+    stats::find_node_visited();
+
+    // We use child nodes instead of children because children ignores text nodes
+    let children = backend.child_nodes(&root_node);
+
+    // The children of raw HTML aren't a part of the virtual dom, so they don't get indexed.
+    let is_raw_html =
+        backend.node_kind(&root_node) == NodeKind::Element && backend.has_inner_html(&root_node);
 
     // If the root node matches, mark it for patching
     if nodes_to_find.get(&cur_node_idx).is_some() {
-        match root_node.node_type() {
-            Node::ELEMENT_NODE => {
-                element_nodes_to_patch.insert(*cur_node_idx, root_node.unchecked_into());
+        match backend.node_kind(&root_node) {
+            NodeKind::Element => {
+                element_nodes_to_patch.insert(*cur_node_idx, root_node);
             }
-            Node::TEXT_NODE => {
-                text_nodes_to_patch.insert(*cur_node_idx, root_node.unchecked_into());
+            NodeKind::Text => {
+                text_nodes_to_patch.insert(*cur_node_idx, root_node);
+            }
+            node_kind => {
+                return Err(PatchError::UnexpectedNodeKind {
+                    node_idx: *cur_node_idx,
+                    node_kind,
+                });
             }
-            other => unimplemented!("Unsupported root node type: {}", other),
         }
         nodes_to_find.remove(&cur_node_idx);
     }
 
     *cur_node_idx += 1;
 
-    for i in 0..child_node_count {
-        let node = children.item(i).unwrap();
+    if is_raw_html {
+        return Ok(());
+    }
 
-        match node.node_type() {
-            Node::ELEMENT_NODE => {
+    for node in children {
+        match backend.node_kind(&node) {
+            NodeKind::Element => {
                 find_nodes(
+                    backend,
                     node,
                     cur_node_idx,
                     nodes_to_find,
                     element_nodes_to_patch,
                     text_nodes_to_patch,
-                );
+                )?;
             }
-            Node::TEXT_NODE => {
-                if nodes_to_find.get(&cur_node_idx).is_some() {
-                    text_nodes_to_patch.insert(*cur_node_idx, node.unchecked_into());
+            NodeKind::Text => {
+                stats::find_node_visited();
+
+                if nodes_to_find.remove(&cur_node_idx) {
+                    text_nodes_to_patch.insert(*cur_node_idx, node);
                 }
 
                 *cur_node_idx += 1;
             }
-            Node::COMMENT_NODE => {
+            NodeKind::Comment => {
                 // At this time we do not support user entered comment nodes, so if we see a comment
                 // then it was a delimiter created by virtual-dom-rs in order to ensure that two
                 // neighboring text nodes did not get merged into one by the browser. So we skip
                 // over this virtual-dom-rs generated comment node.
             }
-            _other => {
+            NodeKind::Other => {
                 // Ignoring unsupported child node type
                 // TODO: What do we do with this situation? Log a warning?
             }
         }
     }
+
+    Ok(())
 }
 
-fn apply_element_patch(node: &Element, patch: &Patch) -> Result<ActiveClosures, JsValue> {
+fn apply_element_patch<B: DomBackend>(
+    backend: &B,
+    node: &B::Node,
+    node_idx: usize,
+    patch: &Patch,
+) -> Result<ActiveClosures, PatchError<B::Error>> {
     let active_closures = HashMap::new();
 
     match patch {
         Patch::AddAttributes(_node_idx, attributes) => {
             for (attrib_name, attrib_val) in attributes.iter() {
-                node.set_attribute(attrib_name, attrib_val)?;
+                backend.apply_attribute(node, attrib_name, attrib_val)?;
             }
 
             Ok(active_closures)
         }
         Patch::RemoveAttributes(_node_idx, attributes) => {
             for attrib_name in attributes.iter() {
-                node.remove_attribute(attrib_name)?;
+                backend.remove_attribute(node, attrib_name)?;
+            }
+
+            Ok(active_closures)
+        }
+        Patch::AddClasses(_node_idx, tokens) => {
+            for token in tokens.iter() {
+                backend.add_class(node, token)?;
+            }
+
+            Ok(active_closures)
+        }
+        Patch::RemoveClasses(_node_idx, tokens) => {
+            for token in tokens.iter() {
+                backend.remove_class(node, token)?;
+            }
+
+            Ok(active_closures)
+        }
+        Patch::SetStyleProperties(_node_idx, properties) => {
+            for (name, value) in properties.iter() {
+                backend.set_style_property(node, name, value)?;
             }
 
             Ok(active_closures)
         }
+        Patch::RemoveStyleProperties(_node_idx, properties) => {
+            for name in properties.iter() {
+                backend.remove_style_property(node, name)?;
+            }
+
+            Ok(active_closures)
+        }
+        Patch::SetInnerHtml(_node_idx, html) => {
+            backend.set_inner_html(node, html);
+
+            Ok(active_closures)
+        }
         Patch::Replace(_node_idx, new_node) => {
-            let created_node = new_node.create_dom_node();
+            let created_node =
+                new_node.create_dom_node_within(backend, parent_namespace(backend, node))?;
 
-            node.replace_with_with_node_1(&created_node.node)?;
+            backend.replace_with(node, &created_node.node)?;
 
             Ok(created_node.closures)
         }
         Patch::TruncateChildren(_node_idx, num_children_remaining) => {
-            let children = node.child_nodes();
-            let mut child_count = children.length();
-
             // We skip over any separators that we placed between two text nodes
             //   -> `<!--ptns-->`
             //  and trim all children that come after our new desired `num_children_remaining`
             let mut non_separator_children_found = 0;
 
-            for index in 0 as u32..child_count {
-                let child = children
-                    .get(min(index, child_count - 1))
-                    .expect("Potential child to truncate");
-
+            for child in backend.child_nodes(node) {
                 // If this is a comment node then we know that it is a `<!--ptns-->`
                 // text node separator that was created in virtual_node/mod.rs.
-                if child.node_type() == Node::COMMENT_NODE {
+                if backend.node_kind(&child) == NodeKind::Comment {
                     continue;
                 }
 
                 non_separator_children_found += 1;
 
-                if non_separator_children_found <= *num_children_remaining as u32 {
+                if non_separator_children_found <= *num_children_remaining {
                     continue;
                 }
 
-                node.remove_child(&child).expect("Truncated children");
-                child_count -= 1;
+                backend.remove_child(node, &child)?;
             }
 
             Ok(active_closures)
@@ -183,35 +471,73 @@ fn apply_element_patch(node: &Element, patch: &Patch) -> Result<ActiveClosures,
 
             let mut active_closures = HashMap::new();
 
+            let namespace = backend.children_namespace(parent);
+
             for new_node in new_nodes {
-                let created_node = new_node.create_dom_node();
+                let created_node = new_node.create_dom_node_within(backend, namespace)?;
 
-                parent.append_child(&created_node.node)?;
+                backend.append_child(parent, &created_node.node)?;
 
                 active_closures.extend(created_node.closures);
             }
 
             Ok(active_closures)
         }
-        Patch::ChangeText(_node_idx, _new_node) => {
-            unreachable!("Elements should not receive ChangeText patches.")
+        // Elements should not receive ChangeText patches.
+        Patch::ChangeText(_node_idx, _new_node) => Err(PatchError::UnexpectedNodeKind {
+            node_idx,
+            node_kind: NodeKind::Element,
+        }),
+        Patch::CallOnRemoveElem(_node_idx, hook) | Patch::CallOnUpdateElem(_node_idx, hook) => {
+            backend.call_elem_hook(&hook.0, node)?;
+
+            Ok(active_closures)
+        }
+        Patch::SetNodeRef(_node_idx, node_ref) => {
+            backend.set_node_ref(node_ref, node);
+
+            Ok(active_closures)
+        }
+        Patch::ClearNodeRef(_node_idx, node_ref) => {
+            backend.clear_node_ref(node_ref, node);
+
+            Ok(active_closures)
         }
     }
 }
 
-fn apply_text_patch(node: &Text, patch: &Patch) -> Result<(), JsValue> {
+/// The namespace that the children of a node's parent are in, so that we can create a node
+/// to replace it with.
+fn parent_namespace<B: DomBackend>(backend: &B, node: &B::Node) -> Option<&'static str> {
+    backend
+        .parent_node(node)
+        .and_then(|parent| backend.children_namespace(&parent))
+}
+
+fn apply_text_patch<B: DomBackend>(
+    backend: &B,
+    node: &B::Node,
+    node_idx: usize,
+    patch: &Patch,
+) -> Result<ActiveClosures, PatchError<B::Error>> {
     match patch {
         Patch::ChangeText(_node_idx, new_node) => {
-            node.set_node_value(Some(&new_node.text));
+            backend.set_text(node, &new_node.text);
+
+            Ok(HashMap::new())
         }
         Patch::Replace(_node_idx, new_node) => {
-            node.replace_with_with_node_1(&new_node.create_dom_node().node)?;
-        }
-        other => unreachable!(
-            "Text nodes should only receive ChangeText or Replace patches, not {:?}.",
-            other,
-        ),
-    };
+            let created_node =
+                new_node.create_dom_node_within(backend, parent_namespace(backend, node))?;
 
-    Ok(())
+            backend.replace_with(node, &created_node.node)?;
+
+            Ok(created_node.closures)
+        }
+        // Text nodes should only receive ChangeText or Replace patches.
+        _ => Err(PatchError::UnexpectedNodeKind {
+            node_idx,
+            node_kind: NodeKind::Text,
+        }),
+    }
 }
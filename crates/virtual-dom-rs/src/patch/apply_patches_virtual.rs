@@ -0,0 +1,222 @@
+use crate::patch::{AsPatch, Patch};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use virtual_node::VirtualNode;
+
+/// Apply all of the patches to an old virtual root node, turning it into the new virtual root
+/// node that the patches were diffed against.
+///
+/// This mutates the virtual dom the same way that `patch` mutates the real DOM, using the same
+/// depth first node indices. It is useful as a reference implementation in tests
+/// (`apply_patches_virtual(old, diff(old, new)) == new`) and for keeping a shadow copy of a tree
+/// that lives elsewhere, such as in a browser that you're sending patches to.
+///
+/// `on_remove_elem` and `on_update_elem` hooks don't get called since there are no real DOM
+/// elements to call them with.
+///
+/// A portal is only an empty text node within the tree, so the diff never patches it. It keeps
+/// its old target and children unless a node around it gets replaced.
+pub fn apply_patches_virtual<P: AsPatch>(root_node: &mut VirtualNode, patches: &[P]) {
+    let mut nodes_to_find = HashSet::new();
+
+    for patch in patches {
+        nodes_to_find.insert(patch.node_idx());
+    }
+
+    // Patches use the node indices of the old tree, so we find all of the nodes before we
+    // start mutating the tree.
+    //
+    // We store the path of child indices from the root node to each node, since we can't hold
+    // on to mutable references to many nodes at once. The diff only ever appends or removes
+    // children after any that it patches, so these paths stay valid while we patch.
+    let mut node_paths = HashMap::new();
+
+    find_node_paths(
+        root_node,
+        &mut 0,
+        &mut vec![],
+        &mut nodes_to_find,
+        &mut node_paths,
+    );
+
+    for patch in patches {
+        let patch_node_idx = patch.node_idx();
+        let patch = patch.as_patch();
+
+        let path = node_paths
+            .get(&patch_node_idx)
+            .expect("Getting here means we didn't find the node that we were supposed to patch.");
+
+        apply_patch(node_at_path(root_node, path), &patch);
+    }
+}
+
+fn find_node_paths(
+    node: &VirtualNode,
+    cur_node_idx: &mut usize,
+    cur_path: &mut Vec<usize>,
+    nodes_to_find: &mut HashSet<usize>,
+    node_paths: &mut HashMap<usize, Vec<usize>>,
+) {
+    if nodes_to_find.len() == 0 {
+        return;
+    }
+
+    if nodes_to_find.remove(cur_node_idx) {
+        node_paths.insert(*cur_node_idx, cur_path.clone());
+    }
+
+    *cur_node_idx += 1;
+
+    if let VirtualNode::Element(element) = node {
+        for (child_idx, child) in element.rendered_children().iter().enumerate() {
+            cur_path.push(child_idx);
+            find_node_paths(child, cur_node_idx, cur_path, nodes_to_find, node_paths);
+            cur_path.pop();
+        }
+    }
+}
+
+fn node_at_path<'a>(root_node: &'a mut VirtualNode, path: &[usize]) -> &'a mut VirtualNode {
+    let mut node = root_node;
+
+    for child_idx in path {
+        node = match node {
+            VirtualNode::Element(element) => &mut element.children[*child_idx],
+            VirtualNode::Text(_) | VirtualNode::Portal(_) => {
+                unreachable!("Text nodes and portals do not have children.")
+            }
+        };
+    }
+
+    node
+}
+
+fn apply_patch(node: &mut VirtualNode, patch: &Patch) {
+    if let Patch::Replace(_node_idx, new_node) = patch {
+        *node = (*new_node).clone();
+        return;
+    }
+
+    match node {
+        VirtualNode::Element(element) => match patch {
+            Patch::AddAttributes(_node_idx, attributes) => {
+                for (attrib_name, attrib_val) in attributes.iter() {
+                    element
+                        .attrs
+                        .insert(attrib_name.to_string(), attrib_val.to_string());
+                }
+            }
+            Patch::RemoveAttributes(_node_idx, attributes) => {
+                for attrib_name in attributes.iter() {
+                    element.attrs.remove(*attrib_name);
+                }
+            }
+            Patch::AddClasses(_node_idx, tokens) => {
+                for token in tokens.iter() {
+                    element.classes.insert(*token);
+                }
+            }
+            Patch::RemoveClasses(_node_idx, tokens) => {
+                for token in tokens.iter() {
+                    element.classes.remove(token);
+                }
+            }
+            Patch::SetStyleProperties(_node_idx, properties) => {
+                for (name, value) in properties.iter() {
+                    element.styles.insert(*name, *value);
+                }
+            }
+            Patch::RemoveStyleProperties(_node_idx, properties) => {
+                for name in properties.iter() {
+                    element.styles.remove(name);
+                }
+            }
+            Patch::SetInnerHtml(_node_idx, html) => {
+                element.unsafe_inner_html = Some(html.to_string());
+            }
+            Patch::TruncateChildren(_node_idx, num_children_remaining) => {
+                element.children.truncate(*num_children_remaining);
+            }
+            Patch::AppendChildren(_node_idx, new_nodes) => {
+                for new_node in new_nodes {
+                    element.children.push((*new_node).clone());
+                }
+            }
+            Patch::SetNodeRef(_node_idx, node_ref) => {
+                element.node_ref = Some((*node_ref).clone());
+            }
+            Patch::ClearNodeRef(_node_idx, node_ref) => {
+                if element.node_ref.as_ref() == Some(*node_ref) {
+                    element.node_ref = None;
+                }
+            }
+            Patch::CallOnRemoveElem(_node_idx, _) | Patch::CallOnUpdateElem(_node_idx, _) => {}
+            Patch::ChangeText(_node_idx, _new_node) => {
+                unreachable!("Elements should not receive ChangeText patches.")
+            }
+            Patch::Replace(_node_idx, _new_node) => unreachable!("Handled above."),
+        },
+        VirtualNode::Text(text_node) => match patch {
+            Patch::ChangeText(_node_idx, new_node) => {
+                text_node.text = new_node.text.clone();
+            }
+            other => unreachable!(
+                "Text nodes should only receive ChangeText or Replace patches, not {:?}.",
+                other,
+            ),
+        },
+        VirtualNode::Portal(_) => {
+            unreachable!("Portals should only receive Replace patches, not {:?}.", patch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, html, NodeRef};
+
+    #[test]
+    fn patch_virtual_tree() {
+        let test_cases = vec![
+            (
+                "Replace the root node",
+                html! { <div> <b></b> </div> },
+                html! { <span>Hello</span> },
+            ),
+            (
+                "Patch attributes and text",
+                html! { <div id="old" class="a"> Old text </div> },
+                html! { <div class="b"> New text </div> },
+            ),
+            (
+                "Truncate, append and replace deeply nested children",
+                html! { <div> <ul> <li>1</li> <li>2</li> </ul> <em><b></b></em> <p></p> </div> },
+                html! { <div> <ul> <li>1</li> <li>2</li> <li>3</li> </ul> <strong></strong> </div> },
+            ),
+        ];
+
+        for (desc, old, new) in test_cases {
+            let mut shadow = old.clone();
+
+            apply_patches_virtual(&mut shadow, &diff(&old, &new));
+
+            assert_eq!(shadow, new, "{}", desc);
+        }
+    }
+
+    #[test]
+    fn patch_node_refs() {
+        let first_ref = NodeRef::new();
+        let second_ref = NodeRef::new();
+
+        let old = html! { <div ref=first_ref> <em ref=first_ref></em> </div> };
+        let new = html! { <div ref=second_ref></div> };
+
+        let mut shadow = old.clone();
+        apply_patches_virtual(&mut shadow, &diff(&old, &new));
+
+        assert_eq!(shadow, new);
+    }
+}
@@ -1,11 +1,19 @@
 //! Our Patch enum is intentionally kept in it's own file for easy inclusion into
 //! The Percy Book.
 
-use crate::{VText, VirtualNode};
+use crate::{DynClosure, NodeRef, VText, VirtualNode};
 use std::collections::HashMap;
+use std::fmt;
 
 mod apply_patches;
-pub use apply_patches::patch;
+pub use apply_patches::{patch, patch_with, PatchError};
+pub(crate) use apply_patches::{patch_fragment, patch_root};
+
+mod apply_patches_virtual;
+pub use apply_patches_virtual::apply_patches_virtual;
+
+mod owned;
+pub use owned::{AsPatch, PatchOwned};
 
 /// A Patch encodes an operation that modifies a real DOM element.
 ///
@@ -40,7 +48,7 @@ pub use apply_patches::patch;
 /// ```
 ///
 /// The patching process is tested in a real browser in crates/virtual-dom-rs/tests/diff_patch.rs
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Patch<'a> {
     /// Append a vector of child nodes to a parent node id.
     AppendChildren(NodeIdx, Vec<&'a VirtualNode>),
@@ -53,12 +61,54 @@ pub enum Patch<'a> {
     AddAttributes(NodeIdx, HashMap<&'a str, &'a str>),
     /// Remove attributes that the old node had that the new node doesn't
     RemoveAttributes(NodeIdx, Vec<&'a str>),
+    /// Add the class tokens that the new node has that the old node does not
+    AddClasses(NodeIdx, Vec<&'a str>),
+    /// Remove the class tokens that the old node had that the new node doesn't
+    RemoveClasses(NodeIdx, Vec<&'a str>),
+    /// Set the inline style properties that the new node has that the old node does not,
+    /// or that have a different value.
+    SetStyleProperties(NodeIdx, Vec<(&'a str, &'a str)>),
+    /// Remove the inline style properties that the old node had that the new node doesn't
+    RemoveStyleProperties(NodeIdx, Vec<&'a str>),
+    /// Replace the raw HTML content of an element that has `unsafe_inner_html`.
+    SetInnerHtml(NodeIdx, &'a str),
     /// Change the text of a Text node.
     ChangeText(NodeIdx, &'a VText),
+    /// Call the `on_remove_elem` hook of an old element that is about to be removed, either
+    /// because it or one of its ancestors is being replaced or truncated.
+    CallOnRemoveElem(NodeIdx, ElemHook),
+    /// Call the `on_update_elem` hook of the new element after its attributes were patched.
+    CallOnUpdateElem(NodeIdx, ElemHook),
+    /// Point a node ref at an element that was kept but given a different ref.
+    SetNodeRef(NodeIdx, &'a NodeRef),
+    /// Clear the node ref of an old element that is about to be removed or that was given a
+    /// different ref.
+    ClearNodeRef(NodeIdx, &'a NodeRef),
 }
 
 type NodeIdx = usize;
 
+/// An element's `on_remove_elem` or `on_update_elem` hook.
+///
+/// Only the hook is kept, not the element, so owning the patch is as cheap as cloning an `Rc`.
+/// Two hooks are equal if they are the same closure.
+#[derive(Clone)]
+pub struct ElemHook(pub DynClosure);
+
+impl PartialEq for ElemHook {
+    fn eq(&self, other: &Self) -> bool {
+        // Compare the data pointers, the vtable of the same closure can differ between
+        // codegen units
+        &*self.0 as *const _ as *const u8 == &*other.0 as *const _ as *const u8
+    }
+}
+
+impl fmt::Debug for ElemHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ElemHook")
+    }
+}
+
 impl<'a> Patch<'a> {
     /// Every Patch is meant to be applied to a specific node within the DOM. Get the
     /// index of the DOM node that this patch should apply to. DOM nodes are indexed
@@ -70,7 +120,16 @@ impl<'a> Patch<'a> {
             Patch::Replace(node_idx, _) => *node_idx,
             Patch::AddAttributes(node_idx, _) => *node_idx,
             Patch::RemoveAttributes(node_idx, _) => *node_idx,
+            Patch::AddClasses(node_idx, _) => *node_idx,
+            Patch::RemoveClasses(node_idx, _) => *node_idx,
+            Patch::SetStyleProperties(node_idx, _) => *node_idx,
+            Patch::RemoveStyleProperties(node_idx, _) => *node_idx,
+            Patch::SetInnerHtml(node_idx, _) => *node_idx,
             Patch::ChangeText(node_idx, _) => *node_idx,
+            Patch::CallOnRemoveElem(node_idx, _) => *node_idx,
+            Patch::CallOnUpdateElem(node_idx, _) => *node_idx,
+            Patch::SetNodeRef(node_idx, _) => *node_idx,
+            Patch::ClearNodeRef(node_idx, _) => *node_idx,
         }
     }
 }
@@ -0,0 +1,266 @@
+//! Patches that own their data, so that they can outlive the virtual doms that they were
+//! diffed from.
+
+use crate::patch::{ElemHook, Patch};
+use crate::{NodeRef, VText, VirtualNode};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// An owned version of [`Patch`].
+///
+/// A `Patch` borrows from the old and new virtual doms that it was diffed from. Convert it into
+/// a `PatchOwned` with [`Patch::into_owned`] in order to store it, queue it up or apply it after
+/// the virtual doms were dropped.
+///
+/// ```ignore
+/// let patches: Vec<PatchOwned> = diff(&old, &new)
+///     .into_iter()
+///     .map(Patch::into_owned)
+///     .collect();
+///
+/// drop(old);
+/// drop(new);
+///
+/// patch(root_node, &patches).unwrap();
+/// ```
+///
+/// [`Patch`]: enum.Patch.html
+/// [`Patch::into_owned`]: enum.Patch.html#method.into_owned
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOwned {
+    /// See [`Patch::AppendChildren`](enum.Patch.html#variant.AppendChildren)
+    AppendChildren(NodeIdx, Vec<VirtualNode>),
+    /// See [`Patch::TruncateChildren`](enum.Patch.html#variant.TruncateChildren)
+    TruncateChildren(NodeIdx, usize),
+    /// See [`Patch::Replace`](enum.Patch.html#variant.Replace)
+    Replace(NodeIdx, VirtualNode),
+    /// See [`Patch::AddAttributes`](enum.Patch.html#variant.AddAttributes)
+    AddAttributes(NodeIdx, HashMap<String, String>),
+    /// See [`Patch::RemoveAttributes`](enum.Patch.html#variant.RemoveAttributes)
+    RemoveAttributes(NodeIdx, Vec<String>),
+    /// See [`Patch::AddClasses`](enum.Patch.html#variant.AddClasses)
+    AddClasses(NodeIdx, Vec<String>),
+    /// See [`Patch::RemoveClasses`](enum.Patch.html#variant.RemoveClasses)
+    RemoveClasses(NodeIdx, Vec<String>),
+    /// See [`Patch::SetStyleProperties`](enum.Patch.html#variant.SetStyleProperties)
+    SetStyleProperties(NodeIdx, Vec<(String, String)>),
+    /// See [`Patch::RemoveStyleProperties`](enum.Patch.html#variant.RemoveStyleProperties)
+    RemoveStyleProperties(NodeIdx, Vec<String>),
+    /// See [`Patch::SetInnerHtml`](enum.Patch.html#variant.SetInnerHtml)
+    SetInnerHtml(NodeIdx, String),
+    /// See [`Patch::ChangeText`](enum.Patch.html#variant.ChangeText)
+    ChangeText(NodeIdx, VText),
+    /// See [`Patch::CallOnRemoveElem`](enum.Patch.html#variant.CallOnRemoveElem)
+    CallOnRemoveElem(NodeIdx, ElemHook),
+    /// See [`Patch::CallOnUpdateElem`](enum.Patch.html#variant.CallOnUpdateElem)
+    CallOnUpdateElem(NodeIdx, ElemHook),
+    /// See [`Patch::SetNodeRef`](enum.Patch.html#variant.SetNodeRef)
+    SetNodeRef(NodeIdx, NodeRef),
+    /// See [`Patch::ClearNodeRef`](enum.Patch.html#variant.ClearNodeRef)
+    ClearNodeRef(NodeIdx, NodeRef),
+}
+
+type NodeIdx = usize;
+
+impl<'a> Patch<'a> {
+    /// Copy everything that this patch borrows from the old and new virtual doms into a
+    /// `PatchOwned`.
+    pub fn into_owned(self) -> PatchOwned {
+        match self {
+            Patch::AppendChildren(node_idx, new_nodes) => {
+                PatchOwned::AppendChildren(node_idx, new_nodes.into_iter().cloned().collect())
+            }
+            Patch::TruncateChildren(node_idx, len) => PatchOwned::TruncateChildren(node_idx, len),
+            Patch::Replace(node_idx, new_node) => PatchOwned::Replace(node_idx, new_node.clone()),
+            Patch::AddAttributes(node_idx, attributes) => PatchOwned::AddAttributes(
+                node_idx,
+                attributes
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            ),
+            Patch::RemoveAttributes(node_idx, attributes) => PatchOwned::RemoveAttributes(
+                node_idx,
+                attributes
+                    .into_iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+            ),
+            Patch::AddClasses(node_idx, tokens) => PatchOwned::AddClasses(
+                node_idx,
+                tokens.into_iter().map(|token| token.to_string()).collect(),
+            ),
+            Patch::RemoveClasses(node_idx, tokens) => PatchOwned::RemoveClasses(
+                node_idx,
+                tokens.into_iter().map(|token| token.to_string()).collect(),
+            ),
+            Patch::SetStyleProperties(node_idx, properties) => PatchOwned::SetStyleProperties(
+                node_idx,
+                properties
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            ),
+            Patch::RemoveStyleProperties(node_idx, properties) => {
+                PatchOwned::RemoveStyleProperties(
+                    node_idx,
+                    properties
+                        .into_iter()
+                        .map(|name| name.to_string())
+                        .collect(),
+                )
+            }
+            Patch::SetInnerHtml(node_idx, html) => {
+                PatchOwned::SetInnerHtml(node_idx, html.to_string())
+            }
+            Patch::ChangeText(node_idx, text) => PatchOwned::ChangeText(node_idx, text.clone()),
+            Patch::CallOnRemoveElem(node_idx, hook) => PatchOwned::CallOnRemoveElem(node_idx, hook),
+            Patch::CallOnUpdateElem(node_idx, hook) => PatchOwned::CallOnUpdateElem(node_idx, hook),
+            Patch::SetNodeRef(node_idx, node_ref) => {
+                PatchOwned::SetNodeRef(node_idx, node_ref.clone())
+            }
+            Patch::ClearNodeRef(node_idx, node_ref) => {
+                PatchOwned::ClearNodeRef(node_idx, node_ref.clone())
+            }
+        }
+    }
+}
+
+impl PatchOwned {
+    /// Borrow this patch as a `Patch`.
+    pub fn as_patch(&self) -> Patch<'_> {
+        match self {
+            PatchOwned::AppendChildren(node_idx, new_nodes) => {
+                Patch::AppendChildren(*node_idx, new_nodes.iter().collect())
+            }
+            PatchOwned::TruncateChildren(node_idx, len) => Patch::TruncateChildren(*node_idx, *len),
+            PatchOwned::Replace(node_idx, new_node) => Patch::Replace(*node_idx, new_node),
+            PatchOwned::AddAttributes(node_idx, attributes) => Patch::AddAttributes(
+                *node_idx,
+                attributes
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect(),
+            ),
+            PatchOwned::RemoveAttributes(node_idx, attributes) => Patch::RemoveAttributes(
+                *node_idx,
+                attributes.iter().map(|name| name.as_str()).collect(),
+            ),
+            PatchOwned::AddClasses(node_idx, tokens) => Patch::AddClasses(
+                *node_idx,
+                tokens.iter().map(|token| token.as_str()).collect(),
+            ),
+            PatchOwned::RemoveClasses(node_idx, tokens) => Patch::RemoveClasses(
+                *node_idx,
+                tokens.iter().map(|token| token.as_str()).collect(),
+            ),
+            PatchOwned::SetStyleProperties(node_idx, properties) => Patch::SetStyleProperties(
+                *node_idx,
+                properties
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect(),
+            ),
+            PatchOwned::RemoveStyleProperties(node_idx, properties) => {
+                Patch::RemoveStyleProperties(
+                    *node_idx,
+                    properties.iter().map(|name| name.as_str()).collect(),
+                )
+            }
+            PatchOwned::SetInnerHtml(node_idx, html) => Patch::SetInnerHtml(*node_idx, html),
+            PatchOwned::ChangeText(node_idx, text) => Patch::ChangeText(*node_idx, text),
+            PatchOwned::CallOnRemoveElem(node_idx, hook) => {
+                Patch::CallOnRemoveElem(*node_idx, hook.clone())
+            }
+            PatchOwned::CallOnUpdateElem(node_idx, hook) => {
+                Patch::CallOnUpdateElem(*node_idx, hook.clone())
+            }
+            PatchOwned::SetNodeRef(node_idx, node_ref) => Patch::SetNodeRef(*node_idx, node_ref),
+            PatchOwned::ClearNodeRef(node_idx, node_ref) => {
+                Patch::ClearNodeRef(*node_idx, node_ref)
+            }
+        }
+    }
+
+    /// See [`Patch::node_idx`](enum.Patch.html#method.node_idx)
+    pub fn node_idx(&self) -> usize {
+        match self {
+            PatchOwned::AppendChildren(node_idx, _) => *node_idx,
+            PatchOwned::TruncateChildren(node_idx, _) => *node_idx,
+            PatchOwned::Replace(node_idx, _) => *node_idx,
+            PatchOwned::AddAttributes(node_idx, _) => *node_idx,
+            PatchOwned::RemoveAttributes(node_idx, _) => *node_idx,
+            PatchOwned::AddClasses(node_idx, _) => *node_idx,
+            PatchOwned::RemoveClasses(node_idx, _) => *node_idx,
+            PatchOwned::SetStyleProperties(node_idx, _) => *node_idx,
+            PatchOwned::RemoveStyleProperties(node_idx, _) => *node_idx,
+            PatchOwned::SetInnerHtml(node_idx, _) => *node_idx,
+            PatchOwned::ChangeText(node_idx, _) => *node_idx,
+            PatchOwned::CallOnRemoveElem(node_idx, _) => *node_idx,
+            PatchOwned::CallOnUpdateElem(node_idx, _) => *node_idx,
+            PatchOwned::SetNodeRef(node_idx, _) => *node_idx,
+            PatchOwned::ClearNodeRef(node_idx, _) => *node_idx,
+        }
+    }
+}
+
+impl<'a> From<Patch<'a>> for PatchOwned {
+    fn from(patch: Patch<'a>) -> Self {
+        patch.into_owned()
+    }
+}
+
+/// A patch that `patch` knows how to apply to the real DOM, either a borrowed `Patch` or a
+/// `PatchOwned`.
+pub trait AsPatch {
+    /// The index of the DOM node that this patch applies to.
+    fn node_idx(&self) -> usize;
+
+    /// Borrow this patch as a `Patch`.
+    fn as_patch(&self) -> Cow<'_, Patch<'_>>;
+}
+
+impl<'a> AsPatch for Patch<'a> {
+    fn node_idx(&self) -> usize {
+        Patch::node_idx(self)
+    }
+
+    fn as_patch(&self) -> Cow<'_, Patch<'_>> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl AsPatch for PatchOwned {
+    fn node_idx(&self) -> usize {
+        PatchOwned::node_idx(self)
+    }
+
+    fn as_patch(&self) -> Cow<'_, Patch<'_>> {
+        Cow::Owned(PatchOwned::as_patch(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, html};
+
+    #[test]
+    fn round_trip_through_owned() {
+        let old = || html! { <div id="old"> <b>Hello</b> <em></em> <span></span> </div> };
+        let new = || html! { <div class="new"> <i></i> <em>World</em> </div> };
+
+        // The owned patches outlive the virtual doms that they were diffed from
+        let owned: Vec<PatchOwned> = {
+            let (old, new) = (old(), new());
+            diff(&old, &new)
+                .into_iter()
+                .map(Patch::into_owned)
+                .collect()
+        };
+
+        let borrowed: Vec<Patch> = owned.iter().map(PatchOwned::as_patch).collect();
+
+        assert_eq!(borrowed, diff(&old(), &new()));
+    }
+}
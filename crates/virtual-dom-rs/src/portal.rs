@@ -0,0 +1,211 @@
+//! Create and patch the children of portals within their target elements.
+
+use crate::diff::{diff, removal_patches};
+use crate::dom_updater::ActiveClosures;
+use crate::patch::{patch_root, patch_with, PatchError};
+use std::cmp::min;
+use std::collections::HashMap;
+use virtual_node::{DomBackend, VPortal, VirtualNode};
+
+/// The children of a portal that were created within its target element.
+pub(crate) struct MountedPortal<N> {
+    target: N,
+    nodes: Vec<N>,
+}
+
+/// Every portal in the virtual dom, depth first. Portals within the children of a portal
+/// come right after it.
+pub(crate) fn collect_portals(node: &VirtualNode) -> Vec<&VPortal> {
+    let mut portals = vec![];
+    collect_portals_recursive(node, &mut portals);
+    portals
+}
+
+fn collect_portals_recursive<'a>(node: &'a VirtualNode, portals: &mut Vec<&'a VPortal>) {
+    match node {
+        VirtualNode::Element(element_node) => {
+            for child in element_node.rendered_children().iter() {
+                collect_portals_recursive(child, portals);
+            }
+        }
+        VirtualNode::Portal(portal) => {
+            portals.push(portal);
+
+            for child in portal.children.iter() {
+                collect_portals_recursive(child, portals);
+            }
+        }
+        VirtualNode::Text(_) => {}
+    }
+}
+
+/// Create the children of every portal within its target.
+pub(crate) fn mount_portals<B: DomBackend>(
+    backend: &B,
+    portals: &[&VPortal],
+) -> Result<(Vec<MountedPortal<B::Node>>, ActiveClosures), PatchError<B::Error>> {
+    let mut mounted = vec![];
+    let mut active_closures = HashMap::new();
+
+    for portal in portals {
+        let (mounted_portal, closures) = mount_portal(backend, portal)?;
+
+        mounted.push(mounted_portal);
+        active_closures.extend(closures);
+    }
+
+    Ok((mounted, active_closures))
+}
+
+/// Patch the children of the old portals within their targets so that they match the new
+/// portals.
+///
+/// Portals are matched up by their order in the virtual dom. A portal whose target changed
+/// gets its children removed from the old target and created within the new one.
+pub(crate) fn patch_portals<B: DomBackend>(
+    backend: &B,
+    mounted: &mut Vec<MountedPortal<B::Node>>,
+    old_portals: &[&VPortal],
+    new_portals: &[&VPortal],
+) -> Result<ActiveClosures, PatchError<B::Error>> {
+    let mut active_closures = HashMap::new();
+
+    let min_count = min(old_portals.len(), new_portals.len());
+
+    for idx in 0..min_count {
+        let (old, new) = (old_portals[idx], new_portals[idx]);
+
+        if old.target == new.target {
+            active_closures.extend(patch_portal(backend, &mut mounted[idx], old, new)?);
+            continue;
+        }
+
+        unmount_portal(backend, &mounted[idx], old)?;
+
+        let (mounted_portal, closures) = mount_portal(backend, new)?;
+        mounted[idx] = mounted_portal;
+        active_closures.extend(closures);
+    }
+
+    for (old, mounted_portal) in old_portals[min_count..]
+        .iter()
+        .zip(mounted.split_off(min_count))
+    {
+        unmount_portal(backend, &mounted_portal, old)?;
+    }
+
+    for new in new_portals[min_count..].iter() {
+        let (mounted_portal, closures) = mount_portal(backend, new)?;
+
+        mounted.push(mounted_portal);
+        active_closures.extend(closures);
+    }
+
+    Ok(active_closures)
+}
+
+/// Remove the children of every portal from their targets, without calling any hooks.
+pub(crate) fn remove_portals<B: DomBackend>(
+    backend: &B,
+    mounted: &[MountedPortal<B::Node>],
+) -> Result<(), B::Error> {
+    for mounted_portal in mounted.iter() {
+        for node in mounted_portal.nodes.iter() {
+            backend.remove_child(&mounted_portal.target, node)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn mount_portal<B: DomBackend>(
+    backend: &B,
+    portal: &VPortal,
+) -> Result<(MountedPortal<B::Node>, ActiveClosures), PatchError<B::Error>> {
+    let target = backend
+        .portal_target(&portal.target)
+        .ok_or_else(|| PatchError::MissingPortalTarget(portal.target.clone()))?;
+
+    let mut mounted_portal = MountedPortal {
+        target,
+        nodes: vec![],
+    };
+    let mut active_closures = HashMap::new();
+
+    for child in portal.children.iter() {
+        active_closures.extend(append_child(backend, &mut mounted_portal, child)?);
+    }
+
+    Ok((mounted_portal, active_closures))
+}
+
+fn patch_portal<B: DomBackend>(
+    backend: &B,
+    mounted_portal: &mut MountedPortal<B::Node>,
+    old: &VPortal,
+    new: &VPortal,
+) -> Result<ActiveClosures, PatchError<B::Error>> {
+    let mut active_closures = HashMap::new();
+
+    let min_count = min(old.children.len(), new.children.len());
+
+    // Each child is the root node of its own tree within the target.
+    for idx in 0..min_count {
+        let patches = diff(&old.children[idx], &new.children[idx]);
+        let node = &mut mounted_portal.nodes[idx];
+
+        active_closures.extend(patch_root(backend, node, 0, patches)?);
+    }
+
+    for (old_child, node) in old.children[min_count..]
+        .iter()
+        .zip(mounted_portal.nodes.split_off(min_count))
+    {
+        remove_child(backend, &mounted_portal.target, old_child, &node)?;
+    }
+
+    for new_child in new.children[min_count..].iter() {
+        active_closures.extend(append_child(backend, mounted_portal, new_child)?);
+    }
+
+    Ok(active_closures)
+}
+
+fn unmount_portal<B: DomBackend>(
+    backend: &B,
+    mounted_portal: &MountedPortal<B::Node>,
+    old: &VPortal,
+) -> Result<(), PatchError<B::Error>> {
+    for (old_child, node) in old.children.iter().zip(mounted_portal.nodes.iter()) {
+        remove_child(backend, &mounted_portal.target, old_child, node)?;
+    }
+
+    Ok(())
+}
+
+fn append_child<B: DomBackend>(
+    backend: &B,
+    mounted_portal: &mut MountedPortal<B::Node>,
+    new_child: &VirtualNode,
+) -> Result<ActiveClosures, PatchError<B::Error>> {
+    let namespace = backend.children_namespace(&mounted_portal.target);
+    let created_node = new_child.create_dom_node_within(backend, namespace)?;
+
+    backend.append_child(&mounted_portal.target, &created_node.node)?;
+    mounted_portal.nodes.push(created_node.node);
+
+    Ok(created_node.closures)
+}
+
+/// Call the `on_remove_elem` hooks and clear the node refs within a child before removing it.
+fn remove_child<B: DomBackend>(
+    backend: &B,
+    target: &B::Node,
+    old_child: &VirtualNode,
+    node: &B::Node,
+) -> Result<(), PatchError<B::Error>> {
+    patch_with(backend, node.clone(), &removal_patches(old_child))?;
+    backend.remove_child(target, node)?;
+
+    Ok(())
+}
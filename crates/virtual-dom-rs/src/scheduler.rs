@@ -0,0 +1,269 @@
+//! Coalesce many render requests into at most one render per frame.
+
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::JsCast;
+
+/// Something that can call a callback at the start of the next frame.
+///
+/// In the browser this is `RequestAnimationFrame`. In native tests you'll typically use a
+/// `ManualFrameSource` and decide when frames happen yourself.
+pub trait FrameSource {
+    /// Call the callback once, at the start of the next frame.
+    fn request_frame(&self, callback: Box<dyn FnOnce()>);
+}
+
+/// Frames that are driven by the browser's `requestAnimationFrame`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RequestAnimationFrame;
+
+impl FrameSource for RequestAnimationFrame {
+    fn request_frame(&self, callback: Box<dyn FnOnce()>) {
+        let callback = Closure::once_into_js(callback);
+
+        web_sys::window()
+            .expect("No window")
+            .request_animation_frame(callback.unchecked_ref())
+            .expect("Could not request animation frame");
+    }
+}
+
+/// Frames that only happen when you call `run_frame`.
+///
+/// Clones share the same frames, so you can keep a clone around to drive a `Scheduler`
+/// that owns the other one.
+#[derive(Default, Clone)]
+pub struct ManualFrameSource {
+    callbacks: Rc<RefCell<Vec<Box<dyn FnOnce()>>>>,
+}
+
+impl ManualFrameSource {
+    /// Create a frame source that has no requested frames.
+    pub fn new() -> ManualFrameSource {
+        ManualFrameSource::default()
+    }
+
+    /// Call all of the callbacks that requested a frame.
+    ///
+    /// Callbacks that request a frame while we're running this frame will get called
+    /// during the next frame.
+    pub fn run_frame(&self) {
+        let callbacks: Vec<_> = self.callbacks.borrow_mut().drain(..).collect();
+
+        for callback in callbacks {
+            callback();
+        }
+    }
+
+    /// The number of callbacks that are waiting for the next frame.
+    pub fn pending_callbacks(&self) -> usize {
+        self.callbacks.borrow().len()
+    }
+}
+
+impl FrameSource for ManualFrameSource {
+    fn request_frame(&self, callback: Box<dyn FnOnce()>) {
+        self.callbacks.borrow_mut().push(callback);
+    }
+}
+
+/// Renders your application at most once per frame, no matter how many times it was
+/// marked as dirty during that frame.
+///
+/// ```ignore
+/// let dom_updater = Rc::new(RefCell::new(DomUpdater::new_replace_mount(app.render(), mount)));
+///
+/// let scheduler = Scheduler::new(RequestAnimationFrame, move || {
+///     dom_updater.borrow_mut().update(app.render()).unwrap();
+/// });
+///
+/// // Ten messages in the same tick lead to one render during the next animation frame.
+/// let scheduler_clone = scheduler.clone();
+/// store.subscribe(Box::new(move || scheduler_clone.mark_dirty()));
+/// ```
+///
+/// Cloning a `Scheduler` is cheap. All clones schedule the same render.
+pub struct Scheduler<F: FrameSource = RequestAnimationFrame> {
+    inner: Rc<SchedulerInner<F>>,
+}
+
+struct SchedulerInner<F: FrameSource> {
+    frame_source: F,
+    dirty: Cell<bool>,
+    frame_requested: Cell<bool>,
+    render: RefCell<Box<dyn FnMut()>>,
+}
+
+impl<F: FrameSource + 'static> Scheduler<F> {
+    /// Create a scheduler that calls `render` during frames from the `FrameSource`.
+    pub fn new<R: FnMut() + 'static>(frame_source: F, render: R) -> Scheduler<F> {
+        Scheduler {
+            inner: Rc::new(SchedulerInner {
+                frame_source,
+                dirty: Cell::new(false),
+                frame_requested: Cell::new(false),
+                render: RefCell::new(Box::new(render)),
+            }),
+        }
+    }
+
+    /// Mark the application as needing a re-render.
+    ///
+    /// A frame gets requested the first time that this is called after a render, so calling
+    /// it many times before the next frame only leads to one render.
+    pub fn mark_dirty(&self) {
+        self.inner.dirty.set(true);
+
+        if self.inner.frame_requested.get() {
+            return;
+        }
+        self.inner.frame_requested.set(true);
+
+        // Hold a weak reference so that a pending frame doesn't keep the scheduler alive.
+        let inner = Rc::downgrade(&self.inner);
+        self.inner
+            .frame_source
+            .request_frame(Box::new(move || on_frame(inner)));
+    }
+
+    /// Whether or not the application has been marked as dirty since the last render.
+    pub fn is_dirty(&self) -> bool {
+        self.inner.dirty.get()
+    }
+
+    /// Render right away if the application is dirty, instead of waiting for the next frame.
+    ///
+    /// This is mainly useful in tests, where you want to assert on the DOM right after
+    /// sending some messages.
+    ///
+    /// If this gets called while we're already rendering (say, `render` sends a message that
+    /// marks the application as dirty and then flushes) the render happens during the
+    /// next frame instead.
+    pub fn flush(&self) {
+        if !self.inner.dirty.get() {
+            return;
+        }
+
+        let mut render = match self.inner.render.try_borrow_mut() {
+            Ok(render) => render,
+            Err(_) => {
+                self.mark_dirty();
+                return;
+            }
+        };
+
+        self.inner.dirty.set(false);
+        (render)();
+    }
+
+    /// The `FrameSource` that renders are scheduled with.
+    pub fn frame_source(&self) -> &F {
+        &self.inner.frame_source
+    }
+}
+
+fn on_frame<F: FrameSource + 'static>(inner: Weak<SchedulerInner<F>>) {
+    if let Some(inner) = inner.upgrade() {
+        inner.frame_requested.set(false);
+        Scheduler { inner }.flush();
+    }
+}
+
+impl<F: FrameSource> Clone for Scheduler<F> {
+    fn clone(&self) -> Self {
+        Scheduler {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counting_scheduler() -> (
+        Scheduler<ManualFrameSource>,
+        ManualFrameSource,
+        Rc<Cell<u32>>,
+    ) {
+        let frames = ManualFrameSource::new();
+        let renders = Rc::new(Cell::new(0));
+        let renders_clone = Rc::clone(&renders);
+
+        let scheduler = Scheduler::new(frames.clone(), move || {
+            renders_clone.set(renders_clone.get() + 1);
+        });
+
+        (scheduler, frames, renders)
+    }
+
+    #[test]
+    fn renders_once_per_frame() {
+        let (scheduler, frames, renders) = counting_scheduler();
+
+        for _ in 0..10 {
+            scheduler.mark_dirty();
+        }
+        assert_eq!(frames.pending_callbacks(), 1);
+        assert_eq!(renders.get(), 0);
+
+        frames.run_frame();
+        assert_eq!(renders.get(), 1);
+        assert!(!scheduler.is_dirty());
+
+        // Nothing to do if nothing changed since the last render
+        frames.run_frame();
+        assert_eq!(renders.get(), 1);
+
+        scheduler.mark_dirty();
+        frames.run_frame();
+        assert_eq!(renders.get(), 2);
+    }
+
+    #[test]
+    fn flush_renders_synchronously() {
+        let (scheduler, frames, renders) = counting_scheduler();
+
+        scheduler.flush();
+        assert_eq!(renders.get(), 0, "Nothing to flush");
+
+        scheduler.mark_dirty();
+        scheduler.flush();
+        assert_eq!(renders.get(), 1);
+
+        // The frame that was requested has nothing left to render
+        frames.run_frame();
+        assert_eq!(renders.get(), 1);
+    }
+
+    #[test]
+    fn dirty_during_render() {
+        let frames = ManualFrameSource::new();
+        let renders = Rc::new(Cell::new(0));
+
+        let scheduler: Rc<RefCell<Option<Scheduler<ManualFrameSource>>>> =
+            Rc::new(RefCell::new(None));
+
+        let renders_clone = Rc::clone(&renders);
+        let scheduler_clone = Rc::clone(&scheduler);
+        *scheduler.borrow_mut() = Some(Scheduler::new(frames.clone(), move || {
+            renders_clone.set(renders_clone.get() + 1);
+
+            // Rendering sends a message that marks us as dirty again
+            if renders_clone.get() == 1 {
+                let scheduler = scheduler_clone.borrow();
+                let scheduler = scheduler.as_ref().unwrap();
+                scheduler.mark_dirty();
+                scheduler.flush();
+            }
+        }));
+
+        scheduler.borrow().as_ref().unwrap().mark_dirty();
+        frames.run_frame();
+        assert_eq!(renders.get(), 1);
+
+        frames.run_frame();
+        assert_eq!(renders.get(), 2);
+    }
+}
@@ -0,0 +1,239 @@
+//! Instrumentation for finding out why diffing and patching is slow.
+//!
+//! Enable the `stats` cargo feature to collect `UpdateStats`. Without the feature the
+//! instrumentation compiles down to nothing.
+
+#[cfg(not(feature = "stats"))]
+pub(crate) use self::disabled::*;
+#[cfg(feature = "stats")]
+pub use self::enabled::*;
+
+/// The parts of diffing and patching that we time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Phase {
+    Diff,
+    FindNodes,
+    ApplyPatches,
+}
+
+#[cfg(not(feature = "stats"))]
+mod disabled {
+    use super::Phase;
+    use crate::Patch;
+
+    #[inline(always)]
+    pub(crate) fn timed<R, F: FnOnce() -> R>(_phase: Phase, f: F) -> R {
+        f()
+    }
+
+    #[inline(always)]
+    pub(crate) fn diff_node_visited() {}
+
+    #[inline(always)]
+    pub(crate) fn find_node_visited() {}
+
+    #[inline(always)]
+    pub(crate) fn patch_applied(_patch: &Patch) {}
+}
+
+#[cfg(feature = "stats")]
+mod enabled {
+    use super::Phase;
+    use crate::Patch;
+    use std::cell::RefCell;
+    use std::time::Duration;
+
+    thread_local! {
+        static COLLECTING: RefCell<Option<UpdateStats>> = RefCell::new(None);
+    }
+
+    /// What happened while diffing and patching, typically during one `DomUpdater::update`.
+    ///
+    /// ```ignore
+    /// let stats = dom_updater.update_with_stats(new_vdom)?;
+    ///
+    /// assert!(stats.patches.total() <= 3, "Clicking a todo should be cheap");
+    /// ```
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct UpdateStats {
+        /// The number of patches of each kind that were applied.
+        pub patches: PatchCounts,
+        /// The number of virtual nodes that the diff compared.
+        pub diff_nodes_visited: usize,
+        /// The number of DOM nodes that we walked over while looking for the nodes to patch.
+        pub find_nodes_visited: usize,
+        /// Time spent diffing the old and new virtual doms.
+        pub diff_time: Duration,
+        /// Time spent looking for the DOM nodes to patch.
+        pub find_nodes_time: Duration,
+        /// Time spent applying patches to the DOM nodes.
+        pub apply_patches_time: Duration,
+    }
+
+    /// The number of patches of each `Patch` variant.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    #[allow(missing_docs)]
+    pub struct PatchCounts {
+        pub append_children: usize,
+        pub truncate_children: usize,
+        pub replace: usize,
+        pub add_attributes: usize,
+        pub remove_attributes: usize,
+        pub add_classes: usize,
+        pub remove_classes: usize,
+        pub set_style_properties: usize,
+        pub remove_style_properties: usize,
+        pub set_inner_html: usize,
+        pub change_text: usize,
+        pub call_on_remove_elem: usize,
+        pub call_on_update_elem: usize,
+        pub set_node_ref: usize,
+        pub clear_node_ref: usize,
+    }
+
+    impl PatchCounts {
+        /// The number of patches of any kind.
+        pub fn total(&self) -> usize {
+            self.append_children
+                + self.truncate_children
+                + self.replace
+                + self.add_attributes
+                + self.remove_attributes
+                + self.add_classes
+                + self.remove_classes
+                + self.set_style_properties
+                + self.remove_style_properties
+                + self.set_inner_html
+                + self.change_text
+                + self.call_on_remove_elem
+                + self.call_on_update_elem
+                + self.set_node_ref
+                + self.clear_node_ref
+        }
+
+        fn count(&mut self, patch: &Patch) {
+            let count = match patch {
+                Patch::AppendChildren(_, _) => &mut self.append_children,
+                Patch::TruncateChildren(_, _) => &mut self.truncate_children,
+                Patch::Replace(_, _) => &mut self.replace,
+                Patch::AddAttributes(_, _) => &mut self.add_attributes,
+                Patch::RemoveAttributes(_, _) => &mut self.remove_attributes,
+                Patch::AddClasses(_, _) => &mut self.add_classes,
+                Patch::RemoveClasses(_, _) => &mut self.remove_classes,
+                Patch::SetStyleProperties(_, _) => &mut self.set_style_properties,
+                Patch::RemoveStyleProperties(_, _) => &mut self.remove_style_properties,
+                Patch::SetInnerHtml(_, _) => &mut self.set_inner_html,
+                Patch::ChangeText(_, _) => &mut self.change_text,
+                Patch::CallOnRemoveElem(_, _) => &mut self.call_on_remove_elem,
+                Patch::CallOnUpdateElem(_, _) => &mut self.call_on_update_elem,
+                Patch::SetNodeRef(_, _) => &mut self.set_node_ref,
+                Patch::ClearNodeRef(_, _) => &mut self.clear_node_ref,
+            };
+
+            *count += 1;
+        }
+    }
+
+    /// Collect the stats of all of the diffing and patching that happens within `f`.
+    ///
+    /// `DomUpdater::update_with_stats` uses this, but you can also use it when calling
+    /// `diff` and `patch` yourself.
+    pub fn collect<R, F: FnOnce() -> R>(f: F) -> (R, UpdateStats) {
+        resume(UpdateStats::default(), f)
+    }
+
+    /// Just like `collect`, but adds to stats that were collected earlier, such as during the
+    /// previous slices of a `DomUpdater::update_in_slices`.
+    pub(crate) fn resume<R, F: FnOnce() -> R>(stats: UpdateStats, f: F) -> (R, UpdateStats) {
+        // Support nested collection by putting back whatever was being collected before.
+        let outer = COLLECTING.with(|c| c.replace(Some(stats)));
+
+        let result = f();
+
+        let stats = COLLECTING.with(|c| c.replace(outer)).unwrap_or_default();
+
+        (result, stats)
+    }
+
+    fn record<F: FnOnce(&mut UpdateStats)>(f: F) {
+        COLLECTING.with(|c| {
+            if let Some(stats) = c.borrow_mut().as_mut() {
+                f(stats);
+            }
+        });
+    }
+
+    pub(crate) fn timed<R, F: FnOnce() -> R>(phase: Phase, f: F) -> R {
+        let start = now_ms();
+        let result = f();
+        let elapsed = Duration::from_micros(((now_ms() - start) * 1000.) as u64);
+
+        record(|stats| match phase {
+            Phase::Diff => stats.diff_time += elapsed,
+            Phase::FindNodes => stats.find_nodes_time += elapsed,
+            Phase::ApplyPatches => stats.apply_patches_time += elapsed,
+        });
+
+        result
+    }
+
+    pub(crate) fn diff_node_visited() {
+        record(|stats| stats.diff_nodes_visited += 1);
+    }
+
+    pub(crate) fn find_node_visited() {
+        record(|stats| stats.find_nodes_visited += 1);
+    }
+
+    pub(crate) fn patch_applied(patch: &Patch) {
+        record(|stats| stats.patches.count(patch));
+    }
+
+    // `std::time::Instant` isn't available in the browser, so we use `performance.now()`.
+    #[cfg(target_arch = "wasm32")]
+    fn now_ms() -> f64 {
+        web_sys::window()
+            .and_then(|window| window.performance())
+            .map(|performance| performance.now())
+            .unwrap_or(0.)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now_ms() -> f64 {
+        use std::time::Instant;
+
+        thread_local! {
+            static START: Instant = Instant::now();
+        }
+
+        START.with(|start| start.elapsed().as_secs_f64() * 1000.)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{diff, html, VirtualNode};
+
+        #[test]
+        fn collect_diff_stats() {
+            let old = html! { <div> <span>Old</span> <em></em> </div> };
+            let new = html! { <div> <span>New</span> </div> };
+
+            let (_patches, stats) = collect(|| diff(&old, &new));
+
+            // The div, the span and its text node. The em gets truncated without being compared.
+            assert_eq!(stats.diff_nodes_visited, 3);
+            assert_eq!(
+                stats.patches,
+                PatchCounts::default(),
+                "Only patching counts patches"
+            );
+            assert_eq!(stats.find_nodes_visited, 0);
+
+            // Nothing gets recorded outside of `collect`
+            diff(&old, &new);
+            let ((), stats) = collect(|| {});
+            assert_eq!(stats, UpdateStats::default());
+        }
+    }
+}
@@ -0,0 +1,671 @@
+//! A compact binary encoding of patches.
+//!
+//! This lets you diff in a web worker or on a server and then send the patches over to the
+//! main thread, where they get decoded and applied to the real DOM.
+//!
+//! ```ignore
+//! // In a web worker
+//! let bytes = wire::encode(&diff(&old, &new));
+//!
+//! // On the main thread
+//! wire::patch(root_node, &bytes).unwrap();
+//! ```
+//!
+//! # Format
+//!
+//! All integers are unsigned LEB128 varints and all strings are a varint byte length
+//! followed by UTF-8 bytes.
+//!
+//! ```text
+//! patches   = version:u8 count:varint patch*
+//! patch     = kind:u8 node_idx:varint payload
+//! node      = 0 tag:string attr_count:varint (name:string value:string)*
+//!               class_count:varint class:string*
+//!               style_count:varint (name:string value:string)*
+//!               (0 | 1 inner_html:string)
+//!               child_count:varint node*
+//!           | 1 text:string
+//!           | 2 target:string child_count:varint node*
+//! ```
+//!
+//! Events, components and node refs only mean something on the thread that created them, so
+//! they are not encoded. Patches that only call hooks or update node refs are skipped.
+
+use crate::dom_updater::ActiveClosures;
+use crate::patch::{AsPatch, Patch, PatchError, PatchOwned};
+use crate::{VElement, VPortal, VText, VirtualNode};
+use std::collections::HashMap;
+use std::fmt;
+use wasm_bindgen::JsValue;
+use web_sys::Node;
+
+/// The version of the encoding that `encode` writes. Bumped whenever the encoding changes.
+pub const VERSION: u8 = 1;
+
+/// How deeply nodes can be nested inside of a decoded patch.
+pub const MAX_DEPTH: usize = 128;
+
+const APPEND_CHILDREN: u8 = 0;
+const TRUNCATE_CHILDREN: u8 = 1;
+const REPLACE: u8 = 2;
+const ADD_ATTRIBUTES: u8 = 3;
+const REMOVE_ATTRIBUTES: u8 = 4;
+const CHANGE_TEXT: u8 = 5;
+const SET_STYLE_PROPERTIES: u8 = 6;
+const REMOVE_STYLE_PROPERTIES: u8 = 7;
+const ADD_CLASSES: u8 = 8;
+const REMOVE_CLASSES: u8 = 9;
+const SET_INNER_HTML: u8 = 10;
+
+const ELEMENT_NODE: u8 = 0;
+const TEXT_NODE: u8 = 1;
+const PORTAL_NODE: u8 = 2;
+
+/// Encode patches into bytes that can be sent to another thread or over the network.
+pub fn encode<P: AsPatch>(patches: &[P]) -> Vec<u8> {
+    let mut encoded_patches = vec![];
+    let mut count = 0;
+
+    for patch in patches {
+        if encode_patch(&patch.as_patch(), &mut encoded_patches) {
+            count += 1;
+        }
+    }
+
+    let mut bytes = vec![VERSION];
+    write_varint(count, &mut bytes);
+    bytes.extend(encoded_patches);
+
+    bytes
+}
+
+/// Decode patches that were encoded with `encode`.
+pub fn decode(bytes: &[u8]) -> Result<Vec<PatchOwned>, DecodeError> {
+    let mut reader = Reader { bytes, pos: 0 };
+
+    let version = reader.read_u8()?;
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+
+    let count = reader.read_varint()?;
+    let mut patches = vec![];
+
+    for _ in 0..count {
+        patches.push(reader.read_patch()?);
+    }
+
+    if reader.pos != bytes.len() {
+        return Err(DecodeError::TrailingBytes);
+    }
+
+    Ok(patches)
+}
+
+/// Decode patches that were encoded with `encode` and apply them to the root node.
+pub fn patch<N: Into<Node>>(root_node: N, bytes: &[u8]) -> Result<ActiveClosures, ApplyError> {
+    let patches = decode(bytes)?;
+
+    Ok(crate::patch::patch(root_node, &patches)?)
+}
+
+/// The reasons that `wire::patch` can fail.
+#[derive(Debug, PartialEq)]
+pub enum ApplyError {
+    /// The bytes could not be decoded into patches.
+    Decode(DecodeError),
+    /// The decoded patches could not be applied to the DOM.
+    Patch(PatchError<JsValue>),
+}
+
+impl From<DecodeError> for ApplyError {
+    fn from(err: DecodeError) -> Self {
+        ApplyError::Decode(err)
+    }
+}
+
+impl From<PatchError<JsValue>> for ApplyError {
+    fn from(err: PatchError<JsValue>) -> Self {
+        ApplyError::Patch(err)
+    }
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApplyError::Decode(err) => write!(f, "Could not decode patches: {}", err),
+            ApplyError::Patch(err) => write!(f, "Could not apply patches: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ApplyError {}
+
+impl From<ApplyError> for JsValue {
+    fn from(err: ApplyError) -> JsValue {
+        match err {
+            ApplyError::Decode(err) => JsValue::from_str(&err.to_string()),
+            ApplyError::Patch(err) => err.into(),
+        }
+    }
+}
+
+/// The reasons that decoding patches can fail.
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    /// The bytes were encoded with a different version of the encoding.
+    UnsupportedVersion(u8),
+    /// The bytes ended in the middle of a patch.
+    UnexpectedEnd,
+    /// A varint did not fit into 64 bits.
+    VarintOverflow,
+    /// A string was not valid UTF-8.
+    InvalidUtf8,
+    /// Unknown patch kind.
+    InvalidPatchKind(u8),
+    /// Unknown node kind.
+    InvalidNodeKind(u8),
+    /// The byte that says whether an element has inner HTML was neither 0 nor 1.
+    InvalidInnerHtmlFlag(u8),
+    /// There were bytes left over after the last patch.
+    TrailingBytes,
+    /// Nodes were nested more than `MAX_DEPTH` levels deep.
+    TooDeep,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnsupportedVersion(version) => write!(
+                f,
+                "Patches were encoded with version {} but version {} is supported",
+                version, VERSION
+            ),
+            DecodeError::UnexpectedEnd => write!(f, "Unexpected end of encoded patches"),
+            DecodeError::VarintOverflow => write!(f, "Varint is too large"),
+            DecodeError::InvalidUtf8 => write!(f, "String is not valid UTF-8"),
+            DecodeError::InvalidPatchKind(kind) => write!(f, "Invalid patch kind {}", kind),
+            DecodeError::InvalidNodeKind(kind) => write!(f, "Invalid node kind {}", kind),
+            DecodeError::InvalidInnerHtmlFlag(flag) => {
+                write!(f, "Invalid inner HTML flag {}, expected 0 or 1", flag)
+            }
+            DecodeError::TrailingBytes => write!(f, "Unexpected bytes after the last patch"),
+            DecodeError::TooDeep => {
+                write!(f, "Nodes are nested more than {} levels deep", MAX_DEPTH)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Returns false if the patch gets skipped.
+fn encode_patch(patch: &Patch, bytes: &mut Vec<u8>) -> bool {
+    let node_idx = patch.node_idx() as u64;
+
+    match patch {
+        Patch::AppendChildren(_, new_nodes) => {
+            bytes.push(APPEND_CHILDREN);
+            write_varint(node_idx, bytes);
+            write_varint(new_nodes.len() as u64, bytes);
+            for new_node in new_nodes {
+                write_node(new_node, bytes);
+            }
+        }
+        Patch::TruncateChildren(_, len) => {
+            bytes.push(TRUNCATE_CHILDREN);
+            write_varint(node_idx, bytes);
+            write_varint(*len as u64, bytes);
+        }
+        Patch::Replace(_, new_node) => {
+            bytes.push(REPLACE);
+            write_varint(node_idx, bytes);
+            write_node(new_node, bytes);
+        }
+        Patch::AddAttributes(_, attributes) => {
+            bytes.push(ADD_ATTRIBUTES);
+            write_varint(node_idx, bytes);
+            write_varint(attributes.len() as u64, bytes);
+            for (name, value) in attributes.iter() {
+                write_str(name, bytes);
+                write_str(value, bytes);
+            }
+        }
+        Patch::RemoveAttributes(_, attributes) => {
+            bytes.push(REMOVE_ATTRIBUTES);
+            write_varint(node_idx, bytes);
+            write_varint(attributes.len() as u64, bytes);
+            for name in attributes.iter() {
+                write_str(name, bytes);
+            }
+        }
+        Patch::AddClasses(_, tokens) => {
+            bytes.push(ADD_CLASSES);
+            write_varint(node_idx, bytes);
+            write_varint(tokens.len() as u64, bytes);
+            for token in tokens.iter() {
+                write_str(token, bytes);
+            }
+        }
+        Patch::RemoveClasses(_, tokens) => {
+            bytes.push(REMOVE_CLASSES);
+            write_varint(node_idx, bytes);
+            write_varint(tokens.len() as u64, bytes);
+            for token in tokens.iter() {
+                write_str(token, bytes);
+            }
+        }
+        Patch::SetStyleProperties(_, properties) => {
+            bytes.push(SET_STYLE_PROPERTIES);
+            write_varint(node_idx, bytes);
+            write_varint(properties.len() as u64, bytes);
+            for (name, value) in properties.iter() {
+                write_str(name, bytes);
+                write_str(value, bytes);
+            }
+        }
+        Patch::RemoveStyleProperties(_, properties) => {
+            bytes.push(REMOVE_STYLE_PROPERTIES);
+            write_varint(node_idx, bytes);
+            write_varint(properties.len() as u64, bytes);
+            for name in properties.iter() {
+                write_str(name, bytes);
+            }
+        }
+        Patch::SetInnerHtml(_, html) => {
+            bytes.push(SET_INNER_HTML);
+            write_varint(node_idx, bytes);
+            write_str(html, bytes);
+        }
+        Patch::ChangeText(_, text) => {
+            bytes.push(CHANGE_TEXT);
+            write_varint(node_idx, bytes);
+            write_str(&text.text, bytes);
+        }
+        Patch::CallOnRemoveElem(_, _)
+        | Patch::CallOnUpdateElem(_, _)
+        | Patch::SetNodeRef(_, _)
+        | Patch::ClearNodeRef(_, _) => return false,
+    };
+
+    true
+}
+
+fn write_node(node: &VirtualNode, bytes: &mut Vec<u8>) {
+    match node {
+        VirtualNode::Element(element) => {
+            bytes.push(ELEMENT_NODE);
+            write_str(&element.tag, bytes);
+
+            write_varint(element.attrs.len() as u64, bytes);
+            for (name, value) in element.attrs.iter() {
+                write_str(name, bytes);
+                write_str(value, bytes);
+            }
+
+            write_varint(element.classes.len() as u64, bytes);
+            for token in element.classes.iter() {
+                write_str(token, bytes);
+            }
+
+            write_varint(element.styles.len() as u64, bytes);
+            for (name, value) in element.styles.iter() {
+                write_str(name, bytes);
+                write_str(value, bytes);
+            }
+
+            match &element.unsafe_inner_html {
+                Some(html) => {
+                    bytes.push(1);
+                    write_str(html, bytes);
+                }
+                None => bytes.push(0),
+            };
+
+            write_varint(element.children.len() as u64, bytes);
+            for child in element.children.iter() {
+                write_node(child, bytes);
+            }
+        }
+        VirtualNode::Text(text) => {
+            bytes.push(TEXT_NODE);
+            write_str(&text.text, bytes);
+        }
+        VirtualNode::Portal(portal) => {
+            bytes.push(PORTAL_NODE);
+            write_str(&portal.target, bytes);
+
+            write_varint(portal.children.len() as u64, bytes);
+            for child in portal.children.iter() {
+                write_node(child, bytes);
+            }
+        }
+    }
+}
+
+fn write_str(string: &str, bytes: &mut Vec<u8>) {
+    write_varint(string.len() as u64, bytes);
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+fn write_varint(mut value: u64, bytes: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+
+        bytes.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_patch(&mut self) -> Result<PatchOwned, DecodeError> {
+        let kind = self.read_u8()?;
+        let node_idx = self.read_usize()?;
+
+        let patch = match kind {
+            APPEND_CHILDREN => {
+                let count = self.read_varint()?;
+                let mut new_nodes = vec![];
+                for _ in 0..count {
+                    new_nodes.push(self.read_node(0)?);
+                }
+                PatchOwned::AppendChildren(node_idx, new_nodes)
+            }
+            TRUNCATE_CHILDREN => PatchOwned::TruncateChildren(node_idx, self.read_usize()?),
+            REPLACE => PatchOwned::Replace(node_idx, self.read_node(0)?),
+            ADD_ATTRIBUTES => {
+                let count = self.read_varint()?;
+                let mut attributes = HashMap::new();
+                for _ in 0..count {
+                    attributes.insert(self.read_string()?, self.read_string()?);
+                }
+                PatchOwned::AddAttributes(node_idx, attributes)
+            }
+            REMOVE_ATTRIBUTES => {
+                let count = self.read_varint()?;
+                let mut attributes = vec![];
+                for _ in 0..count {
+                    attributes.push(self.read_string()?);
+                }
+                PatchOwned::RemoveAttributes(node_idx, attributes)
+            }
+            ADD_CLASSES => {
+                let count = self.read_varint()?;
+                let mut tokens = vec![];
+                for _ in 0..count {
+                    tokens.push(self.read_string()?);
+                }
+                PatchOwned::AddClasses(node_idx, tokens)
+            }
+            REMOVE_CLASSES => {
+                let count = self.read_varint()?;
+                let mut tokens = vec![];
+                for _ in 0..count {
+                    tokens.push(self.read_string()?);
+                }
+                PatchOwned::RemoveClasses(node_idx, tokens)
+            }
+            SET_STYLE_PROPERTIES => {
+                let count = self.read_varint()?;
+                let mut properties = vec![];
+                for _ in 0..count {
+                    properties.push((self.read_string()?, self.read_string()?));
+                }
+                PatchOwned::SetStyleProperties(node_idx, properties)
+            }
+            REMOVE_STYLE_PROPERTIES => {
+                let count = self.read_varint()?;
+                let mut properties = vec![];
+                for _ in 0..count {
+                    properties.push(self.read_string()?);
+                }
+                PatchOwned::RemoveStyleProperties(node_idx, properties)
+            }
+            SET_INNER_HTML => PatchOwned::SetInnerHtml(node_idx, self.read_string()?),
+            CHANGE_TEXT => PatchOwned::ChangeText(node_idx, VText::new(self.read_string()?)),
+            other => return Err(DecodeError::InvalidPatchKind(other)),
+        };
+
+        Ok(patch)
+    }
+
+    fn read_node(&mut self, depth: usize) -> Result<VirtualNode, DecodeError> {
+        if depth >= MAX_DEPTH {
+            return Err(DecodeError::TooDeep);
+        }
+
+        match self.read_u8()? {
+            ELEMENT_NODE => {
+                let mut element = VElement::new(self.read_string()?);
+
+                let attr_count = self.read_varint()?;
+                for _ in 0..attr_count {
+                    element
+                        .attrs
+                        .insert(self.read_string()?, self.read_string()?);
+                }
+
+                let class_count = self.read_varint()?;
+                for _ in 0..class_count {
+                    element.classes.insert(self.read_string()?);
+                }
+
+                let style_count = self.read_varint()?;
+                for _ in 0..style_count {
+                    element
+                        .styles
+                        .insert(self.read_string()?, self.read_string()?);
+                }
+
+                match self.read_u8()? {
+                    0 => {}
+                    1 => element.unsafe_inner_html = Some(self.read_string()?),
+                    other => return Err(DecodeError::InvalidInnerHtmlFlag(other)),
+                };
+
+                let child_count = self.read_varint()?;
+                for _ in 0..child_count {
+                    element.children.push(self.read_node(depth + 1)?);
+                }
+
+                Ok(VirtualNode::Element(element))
+            }
+            TEXT_NODE => Ok(VirtualNode::text(self.read_string()?)),
+            PORTAL_NODE => {
+                let target = self.read_string()?;
+
+                let child_count = self.read_varint()?;
+                let mut children = vec![];
+                for _ in 0..child_count {
+                    children.push(self.read_node(depth + 1)?);
+                }
+
+                Ok(VirtualNode::Portal(VPortal { target, children }))
+            }
+            other => Err(DecodeError::InvalidNodeKind(other)),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, DecodeError> {
+        let len = self.read_usize()?;
+
+        if self.bytes.len() - self.pos < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+
+        let string = std::str::from_utf8(&self.bytes[self.pos..self.pos + len])
+            .map_err(|_| DecodeError::InvalidUtf8)?;
+        self.pos += len;
+
+        Ok(string.to_string())
+    }
+
+    fn read_usize(&mut self) -> Result<usize, DecodeError> {
+        let value = self.read_varint()?;
+
+        if value > usize::max_value() as u64 {
+            return Err(DecodeError::VarintOverflow);
+        }
+
+        Ok(value as usize)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+
+            if shift > 63 || (shift == 63 && byte > 1) {
+                return Err(DecodeError::VarintOverflow);
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+
+            shift += 7;
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self.bytes.get(self.pos).ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos += 1;
+
+        Ok(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, html, IterableNodes};
+
+    #[test]
+    fn round_trip() {
+        let old = html! {
+        <div id="old" class="old" style={ ("top", "0") }>
+          <b>Hello</b>
+          <em>Old text</em>
+          <p unsafe_inner_html="<b>Old</b>"></p>
+          <span></span>
+          <br>
+        </div>
+        };
+        let new = html! {
+        <div class="new" style={ ("width", "10px") }>
+          <i style={ ("color", "red") }>Hello</i>
+          <em>New text</em>
+          <p unsafe_inner_html="<b>New</b>"></p>
+          <section unsafe_inner_html="<i>Raw</i>"></section>
+        </div>
+        };
+
+        let patches = diff(&old, &new);
+        let decoded = decode(&encode(&patches)).unwrap();
+
+        let decoded: Vec<Patch> = decoded.iter().map(PatchOwned::as_patch).collect();
+        assert_eq!(decoded, patches);
+    }
+
+    #[test]
+    fn round_trip_appended_children() {
+        let old = html! { <ul></ul> };
+        let new = html! {
+        <ul>
+          <li class="a">One</li>
+          <li>Two <b>2</b></li>
+          { VirtualNode::portal("body", html! { <div>Modal</div> }) }
+        </ul>
+        };
+
+        let patches = diff(&old, &new);
+        let decoded = decode(&encode(&patches)).unwrap();
+
+        let decoded: Vec<Patch> = decoded.iter().map(PatchOwned::as_patch).collect();
+        assert_eq!(decoded, patches);
+    }
+
+    #[test]
+    fn varints() {
+        for value in [
+            0,
+            1,
+            127,
+            128,
+            300,
+            u32::max_value() as u64,
+            u64::max_value(),
+        ]
+        .iter()
+        {
+            let mut bytes = vec![];
+            write_varint(*value, &mut bytes);
+
+            let mut reader = Reader {
+                bytes: &bytes,
+                pos: 0,
+            };
+            assert_eq!(reader.read_varint(), Ok(*value));
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode(&[]), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(
+            decode(&[VERSION + 1, 0]),
+            Err(DecodeError::UnsupportedVersion(VERSION + 1))
+        );
+        assert_eq!(
+            decode(&[VERSION, 1, 99, 0]),
+            Err(DecodeError::InvalidPatchKind(99))
+        );
+        assert_eq!(
+            decode(&[VERSION, 1, REPLACE, 0, 7]),
+            Err(DecodeError::InvalidNodeKind(7))
+        );
+        assert_eq!(
+            decode(&[VERSION, 1, CHANGE_TEXT, 0, 5, b'a']),
+            Err(DecodeError::UnexpectedEnd)
+        );
+        assert_eq!(
+            decode(&[VERSION, 1, CHANGE_TEXT, 0, 1, 0xff]),
+            Err(DecodeError::InvalidUtf8)
+        );
+        assert_eq!(
+            decode(&[VERSION, 1, REPLACE, 0, ELEMENT_NODE, 1, b'a', 0, 0, 0, 2, 0]),
+            Err(DecodeError::InvalidInnerHtmlFlag(2))
+        );
+        assert_eq!(decode(&[VERSION, 0, 0]), Err(DecodeError::TrailingBytes));
+    }
+
+    #[test]
+    fn decode_too_deep() {
+        let nested = |depth: usize| {
+            let mut bytes = vec![VERSION, 1, REPLACE, 0];
+            for _ in 0..depth {
+                bytes.extend(&[PORTAL_NODE, 0, 1]);
+            }
+            bytes.extend(&[TEXT_NODE, 0]);
+            bytes
+        };
+
+        assert!(decode(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_eq!(decode(&nested(MAX_DEPTH)), Err(DecodeError::TooDeep));
+        assert_eq!(decode(&nested(100_000)), Err(DecodeError::TooDeep));
+    }
+}
@@ -62,7 +62,7 @@ fn closure_not_dropped() {
             .attrs
             .insert("id".into(), "new-input-elem".into());
 
-        dom_updater.update(new_node);
+        dom_updater.update(new_node).unwrap();
     }
 
     let dom_updater = dom_updater.as_ref().unwrap();
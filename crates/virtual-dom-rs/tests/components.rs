@@ -0,0 +1,88 @@
+//! Ensure that the DomUpdater drives the lifecycle of stateful components.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test components
+
+#![feature(proc_macro_hygiene)]
+
+use console_error_panic_hook;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use virtual_dom_rs::prelude::*;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+struct Toggle {
+    open: bool,
+    calls: Rc<RefCell<Vec<&'static str>>>,
+}
+
+impl Component for Toggle {
+    fn render(&self, _handle: &ComponentHandle<Self>) -> VirtualNode {
+        if self.open {
+            html! { <div class="open"></div> }
+        } else {
+            html! { <div></div> }
+        }
+    }
+
+    fn mounted(&mut self) {
+        self.calls.borrow_mut().push("mounted");
+    }
+
+    fn updated(&mut self) {
+        self.calls.borrow_mut().push("updated");
+    }
+
+    fn will_unmount(&mut self) {
+        self.calls.borrow_mut().push("will_unmount");
+    }
+}
+
+// Verify that components get mounted, updated and unmounted as their root element is
+// created, patched and removed.
+#[wasm_bindgen_test]
+fn lifecycle() {
+    console_error_panic_hook::set_once();
+
+    let calls = Rc::new(RefCell::new(vec![]));
+    let toggle = ComponentHandle::new(Toggle {
+        open: false,
+        calls: Rc::clone(&calls),
+    });
+
+    let mut dom_updater = DomUpdater::new(html! { <main> { toggle.render() } </main> });
+    assert_eq!(*calls.borrow(), vec!["mounted"]);
+
+    toggle.update(|toggle| toggle.open = true);
+    dom_updater.update(html! { <main> { toggle.render() } </main> }).unwrap();
+    assert_eq!(*calls.borrow(), vec!["mounted", "updated"]);
+
+    dom_updater.update(html! { <main></main> }).unwrap();
+    assert_eq!(*calls.borrow(), vec!["mounted", "updated", "will_unmount"]);
+}
+
+// Verify that a mounted component can ask the DomUpdater for a re-render.
+#[wasm_bindgen_test]
+fn request_render() {
+    console_error_panic_hook::set_once();
+
+    let render_requests = Rc::new(Cell::new(0));
+    let render_requests_clone = Rc::clone(&render_requests);
+
+    let toggle = ComponentHandle::new(Toggle {
+        open: false,
+        calls: Rc::new(RefCell::new(vec![])),
+    });
+
+    let mut dom_updater = DomUpdater::new(toggle.render());
+    dom_updater.on_render_request(move || {
+        render_requests_clone.set(render_requests_clone.get() + 1);
+    });
+
+    toggle.update(|toggle| toggle.open = true);
+
+    assert_eq!(render_requests.get(), 1);
+}
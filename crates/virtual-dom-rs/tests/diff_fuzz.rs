@@ -0,0 +1,401 @@
+//! Property based tests that generate random virtual doms and random mutations of them,
+//! then check that applying `diff(old, new)` to `old` gives us `new` (both virtually and in a
+//! `MockDom`), and that `diff_incremental` comes up with the same patches.
+//!
+//! When a case fails proptest shrinks it down to a minimal old tree and list of mutations.
+//!
+//! To run the tests in this file:
+//!
+//! cargo test -p virtual-dom-rs --test diff_fuzz
+//!
+//! To fuzz for a long time (as many cases as you'd like):
+//!
+//! PROPTEST_CASES=1000000 cargo test --release -p virtual-dom-rs --test diff_fuzz -- --ignored
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use mock_dom::MockDom;
+use proptest::prelude::*;
+use proptest::test_runner::Config;
+use virtual_dom_rs::{
+    apply_patches_virtual, diff, diff_incremental, patch_with, DiffBudget, DiffProgress,
+    DomBackend, Patch, PatchOwned, VirtualNode,
+};
+
+/// A small set of tags so that we often generate old and new nodes with the same tag.
+const TAGS: &[&str] = &["div", "span", "p"];
+
+/// `key` is special cased by the diff, changing it forces a node to be replaced.
+const ATTRIBUTES: &[&str] = &["id", "class", "key"];
+
+const CLASSES: &[&str] = &["big", "active"];
+
+const STYLE_PROPERTIES: &[&str] = &["width", "color"];
+
+/// A change that we make to a random node within an old virtual dom in order to get
+/// a new virtual dom.
+#[derive(Debug, Clone)]
+enum Mutation {
+    ChangeText(String),
+    ChangeTag(&'static str),
+    SetAttribute(&'static str, String),
+    RemoveAttribute(&'static str),
+    AddClass(&'static str),
+    RemoveClass(&'static str),
+    SetStyleProperty(&'static str, String),
+    RemoveStyleProperty(&'static str),
+    InsertChild(usize, VirtualNode),
+    RemoveChild(usize),
+    Replace(VirtualNode),
+}
+
+/// A mutation along with a seed that selects the depth first index of the node to mutate.
+type TargetedMutation = (usize, Mutation);
+
+fn arb_text() -> impl Strategy<Value = String> {
+    "[a-c ]{0,3}"
+}
+
+fn arb_attr_value() -> impl Strategy<Value = String> {
+    "[a-c]{1,2}"
+}
+
+fn arb_tag() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(TAGS)
+}
+
+fn arb_attr_name() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(ATTRIBUTES)
+}
+
+fn arb_class() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(CLASSES)
+}
+
+fn arb_style_property() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(STYLE_PROPERTIES)
+}
+
+fn arb_node() -> impl Strategy<Value = VirtualNode> {
+    let leaf = prop_oneof![
+        4 => arb_text().prop_map(VirtualNode::text),
+        1 => arb_text().prop_map(|text| VirtualNode::portal("body", text)),
+    ];
+
+    leaf.prop_recursive(4, 32, 4, |inner| {
+        (
+            arb_tag(),
+            prop::collection::vec((arb_attr_name(), arb_attr_value()), 0..3),
+            prop::collection::vec(arb_class(), 0..2),
+            prop::collection::vec((arb_style_property(), arb_attr_value()), 0..2),
+            prop::collection::vec(inner, 0..4),
+        )
+            .prop_map(|(tag, attrs, classes, styles, children)| {
+                let mut node = VirtualNode::element(tag);
+
+                {
+                    let element = node.as_velement_mut().unwrap();
+
+                    for (name, value) in attrs {
+                        element.attrs.insert(name.to_string(), value);
+                    }
+                    for token in classes {
+                        element.classes.insert(token);
+                    }
+                    for (name, value) in styles {
+                        element.styles.insert(name, value);
+                    }
+                    element.children = children;
+                }
+
+                node
+            })
+    })
+}
+
+fn arb_mutation() -> impl Strategy<Value = TargetedMutation> {
+    let mutation = prop_oneof![
+        arb_text().prop_map(Mutation::ChangeText),
+        arb_tag().prop_map(Mutation::ChangeTag),
+        (arb_attr_name(), arb_attr_value())
+            .prop_map(|(name, value)| Mutation::SetAttribute(name, value)),
+        arb_attr_name().prop_map(Mutation::RemoveAttribute),
+        arb_class().prop_map(Mutation::AddClass),
+        arb_class().prop_map(Mutation::RemoveClass),
+        (arb_style_property(), arb_attr_value())
+            .prop_map(|(name, value)| Mutation::SetStyleProperty(name, value)),
+        arb_style_property().prop_map(Mutation::RemoveStyleProperty),
+        (any::<usize>(), arb_node()).prop_map(|(idx, node)| Mutation::InsertChild(idx, node)),
+        any::<usize>().prop_map(Mutation::RemoveChild),
+        arb_node().prop_map(Mutation::Replace),
+    ];
+
+    (any::<usize>(), mutation)
+}
+
+fn arb_old_and_mutations() -> impl Strategy<Value = (VirtualNode, Vec<TargetedMutation>)> {
+    (arb_node(), prop::collection::vec(arb_mutation(), 0..6))
+}
+
+fn node_count(node: &VirtualNode) -> usize {
+    match node {
+        VirtualNode::Element(element) => 1 + element.children.iter().map(node_count).sum::<usize>(),
+        // Portals are replaced as a whole, so we don't mutate their children
+        VirtualNode::Text(_) | VirtualNode::Portal(_) => 1,
+    }
+}
+
+/// Get the node at the given depth first index.
+fn nth_node(node: &mut VirtualNode, mut idx: usize) -> &mut VirtualNode {
+    if idx == 0 {
+        return node;
+    }
+    idx -= 1;
+
+    let element = node.as_velement_mut().unwrap();
+
+    for child in element.children.iter_mut() {
+        let count = node_count(child);
+
+        if idx < count {
+            return nth_node(child, idx);
+        }
+        idx -= count;
+    }
+
+    unreachable!("The index is always smaller than the node count")
+}
+
+/// Mutations that don't make sense for the selected node (such as changing the tag of
+/// a text node) are skipped.
+fn mutate(old: &VirtualNode, mutations: &[TargetedMutation]) -> VirtualNode {
+    let mut new = old.clone();
+
+    for (seed, mutation) in mutations {
+        let idx = seed % node_count(&new);
+        let node = nth_node(&mut new, idx);
+
+        match (mutation, node) {
+            (Mutation::Replace(replacement), node) => *node = replacement.clone(),
+            (Mutation::ChangeText(text), VirtualNode::Text(text_node)) => {
+                text_node.text = text.clone()
+            }
+            (Mutation::ChangeTag(tag), VirtualNode::Element(element)) => {
+                element.tag = tag.to_string()
+            }
+            (Mutation::SetAttribute(name, value), VirtualNode::Element(element)) => {
+                element.attrs.insert(name.to_string(), value.clone());
+            }
+            (Mutation::RemoveAttribute(name), VirtualNode::Element(element)) => {
+                element.attrs.remove(*name);
+            }
+            (Mutation::AddClass(token), VirtualNode::Element(element)) => {
+                element.classes.insert(*token);
+            }
+            (Mutation::RemoveClass(token), VirtualNode::Element(element)) => {
+                element.classes.remove(token);
+            }
+            (Mutation::SetStyleProperty(name, value), VirtualNode::Element(element)) => {
+                element.styles.insert(*name, value.clone());
+            }
+            (Mutation::RemoveStyleProperty(name), VirtualNode::Element(element)) => {
+                element.styles.remove(name);
+            }
+            (Mutation::InsertChild(child_idx, child), VirtualNode::Element(element)) => {
+                let child_idx = child_idx % (element.children.len() + 1);
+                element.children.insert(child_idx, child.clone());
+            }
+            (Mutation::RemoveChild(child_idx), VirtualNode::Element(element)) => {
+                if element.children.len() > 0 {
+                    let child_idx = child_idx % element.children.len();
+                    element.children.remove(child_idx);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    new
+}
+
+fn check_diff_patch(
+    old: &VirtualNode,
+    mutations: &[TargetedMutation],
+) -> Result<(), TestCaseError> {
+    let new = mutate(old, mutations);
+
+    let patches = diff(old, &new);
+
+    let mut patched = old.clone();
+    apply_patches_virtual(&mut patched, &patches);
+
+    prop_assert_eq!(
+        &without_portal_children(&patched),
+        &without_portal_children(&new),
+        "\nold: {}\nnew: {}\npatches: {:#?}",
+        old,
+        new,
+        patches
+    );
+
+    check_mock_dom_patch(old, &new, &patches)?;
+    check_incremental_diff(old, &new, &patches)
+}
+
+/// The diff leaves portals alone since their children get patched by the `DomUpdater`, so we
+/// only compare the placeholders that portals leave behind.
+fn without_portal_children(node: &VirtualNode) -> VirtualNode {
+    match node {
+        VirtualNode::Element(element) => {
+            let mut element = element.clone();
+            element.children = element
+                .children
+                .iter()
+                .map(without_portal_children)
+                .collect();
+
+            VirtualNode::Element(element)
+        }
+        VirtualNode::Text(_) => node.clone(),
+        VirtualNode::Portal(_) => VirtualNode::portal("", vec![]),
+    }
+}
+
+/// Applying the patches to real (mock) DOM nodes that were created from `old` should give us
+/// the HTML of `new`.
+///
+/// The root node is mounted in a container since replacing it swaps it out of its parent.
+fn check_mock_dom_patch(
+    old: &VirtualNode,
+    new: &VirtualNode,
+    patches: &[Patch],
+) -> Result<(), TestCaseError> {
+    let container = MockDom.create_element("div").unwrap();
+    let root_node = old.create_dom_node_with(&MockDom).unwrap().node;
+    MockDom.append_child(&container, &root_node).unwrap();
+
+    let result = patch_with(&MockDom, root_node.clone(), patches);
+    prop_assert!(
+        result.is_ok(),
+        "\nold: {}\nnew: {}\nerror: {:?}",
+        old,
+        new,
+        result.err()
+    );
+
+    // Neighboring text nodes get `<!--ptns-->` separators in the DOM, which `new.to_string()`
+    // doesn't have.
+    prop_assert_eq!(
+        sort_html(&container.inner_html().replace("<!--ptns-->", "")),
+        sort_html(&new.to_string()),
+        "\nold: {}\nnew: {}\npatches: {:#?}",
+        old,
+        new,
+        patches
+    );
+
+    Ok(())
+}
+
+/// Attributes are stored in a `HashMap`, so their order in `VirtualNode::to_string` is
+/// arbitrary while the DOM keeps them in the order that they were set. The same goes for the
+/// order of class tokens and style properties after they have been patched. We sort them all so
+/// that the HTML can be compared.
+///
+/// Only works for the HTML of our generated nodes, whose text and attribute values never
+/// contain `<`, `>` or `"`.
+fn sort_html(html: &str) -> String {
+    let mut sorted = String::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>').unwrap();
+        sorted += &rest[..start];
+
+        let tag = &rest[start + 1..end];
+        let name_end = tag.find(' ').unwrap_or(tag.len());
+
+        let parts: Vec<&str> = tag[name_end..].split('"').collect();
+        let mut attributes: Vec<String> = parts
+            .chunks(2)
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| {
+                let separator = if pair[0] == " style=" { "; " } else { " " };
+
+                let mut tokens: Vec<&str> = pair[1].split(separator).collect();
+                tokens.sort();
+
+                format!(r#"{}"{}""#, pair[0], tokens.join(separator))
+            })
+            .collect();
+        attributes.sort();
+
+        sorted += "<";
+        sorted += &tag[..name_end];
+        sorted += &attributes.concat();
+        sorted += ">";
+        rest = &rest[end + 1..];
+    }
+
+    sorted + rest
+}
+
+/// Diffing a slice at a time should give us the same patches whatever the budget.
+fn check_incremental_diff(
+    old: &VirtualNode,
+    new: &VirtualNode,
+    patches: &[Patch],
+) -> Result<(), TestCaseError> {
+    let expected: Vec<PatchOwned> = patches.iter().cloned().map(Patch::into_owned).collect();
+
+    for max_nodes in 1..4 {
+        let mut continuation = None;
+
+        let incremental_patches = loop {
+            match diff_incremental(old, new, DiffBudget::Nodes(max_nodes), continuation.take()) {
+                DiffProgress::Done(patches) => break patches,
+                DiffProgress::Paused(paused) => continuation = Some(paused),
+            }
+        };
+
+        prop_assert_eq!(
+            &incremental_patches,
+            &expected,
+            "\nold: {}\nnew: {}\nmax nodes: {}",
+            old,
+            new,
+            max_nodes
+        );
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn diff_then_patch_reproduces_new((old, mutations) in arb_old_and_mutations()) {
+        check_diff_patch(&old, &mutations)?;
+    }
+
+    #[test]
+    fn diff_then_patch_unrelated_trees(old in arb_node(), new in arb_node()) {
+        check_diff_patch(&old, &[(0, Mutation::Replace(new))])?;
+    }
+}
+
+proptest! {
+    // Run with `-- --ignored`. Uses `PROPTEST_CASES` if it is set.
+    #![proptest_config(Config {
+        cases: std::env::var("PROPTEST_CASES")
+            .ok()
+            .and_then(|cases| cases.parse().ok())
+            .unwrap_or(100_000),
+        ..Config::default()
+    })]
+
+    #[test]
+    #[ignore]
+    fn fuzz_diff((old, mutations) in arb_old_and_mutations()) {
+        check_diff_patch(&old, &mutations)?;
+    }
+}
@@ -195,3 +195,24 @@ fn replace_text_with_element() {
     }
     .test();
 }
+
+#[wasm_bindgen_test]
+fn apply_owned_patches() {
+    let old = html! { <div id="old"> <b>Hello</b> <em></em> </div> };
+    let root_node = old.create_dom_node().node;
+
+    // The owned patches can be applied after the virtual doms were dropped
+    let patches: Vec<virtual_dom_rs::PatchOwned> = {
+        let new = html! { <div class="new"> <i></i> </div> };
+        virtual_dom_rs::diff(&old, &new)
+            .into_iter()
+            .map(virtual_dom_rs::Patch::into_owned)
+            .collect()
+    };
+    drop(old);
+
+    virtual_dom_rs::patch(root_node.clone(), &patches).unwrap();
+
+    let root_element: web_sys::Element = wasm_bindgen::JsCast::unchecked_into(root_node);
+    assert_eq!(root_element.outer_html(), r#"<div class="new"><i></i></div>"#);
+}
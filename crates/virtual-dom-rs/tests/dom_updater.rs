@@ -35,7 +35,7 @@ fn patches_dom() {
     let mut dom_updater = DomUpdater::new(vdom);
 
     let new_vdom = html! { <div id="patched"></div> };
-    dom_updater.update(new_vdom);
+    dom_updater.update(new_vdom).unwrap();
 
     document
         .body()
@@ -78,7 +78,7 @@ fn updates_active_closure_on_replace() {
         // New node replaces old node.
         // We are testing that we've stored this new node's closures even though `new` will be dropped
         // at the end of this block.
-        dom_updater.update(replace_node);
+        dom_updater.update(replace_node).unwrap();
     }
 
     let input_event = InputEvent::new("input").unwrap();
@@ -130,7 +130,7 @@ fn updates_active_closures_on_append() {
         // New node gets appended into the DOM.
         // We are testing that we've stored this new node's closures even though `new` will be dropped
         // at the end of this block.
-        dom_updater.update(append_node);
+        dom_updater.update(append_node).unwrap();
     }
 
     let input_event = InputEvent::new("input").unwrap();
@@ -0,0 +1,101 @@
+//! Ensure that patching calls the on_remove_elem and on_update_elem hooks.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test elem_hooks
+
+#![feature(proc_macro_hygiene)]
+
+use console_error_panic_hook;
+use std::cell::RefCell;
+use std::rc::Rc;
+use virtual_dom_rs::prelude::*;
+use wasm_bindgen_test::*;
+use web_sys::Element;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test elem_hooks on_remove_elem
+/// @book start on-remove-elem
+#[wasm_bindgen_test]
+fn on_remove_elem() {
+    console_error_panic_hook::set_once();
+
+    let removed: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(vec![]));
+    let removed_clone = Rc::clone(&removed);
+
+    let chart = html! {
+    <div>
+      <canvas
+        id="chart"
+        on_remove_elem=move |elem: Element| {
+          // Tear down the third-party widget that was rendered into this element
+          removed_clone.borrow_mut().push(elem.id());
+        }
+      >
+      </canvas>
+    </div>
+    };
+
+    let mut dom_updater = DomUpdater::new(chart);
+
+    // The canvas gets truncated
+    dom_updater.update(html! { <div></div> }).unwrap();
+
+    assert_eq!(*removed.borrow(), vec!["chart".to_string()]);
+}
+// @book end on-remove-elem
+
+/// wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test elem_hooks on_remove_elem_descendant
+#[wasm_bindgen_test]
+fn on_remove_elem_descendant() {
+    console_error_panic_hook::set_once();
+
+    let removed: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(vec![]));
+    let removed_clone = Rc::clone(&removed);
+
+    let old = html! {
+    <div>
+      <span>
+        <em id="descendant" on_remove_elem=move |elem: Element| {
+          removed_clone.borrow_mut().push(elem.id());
+        }>
+        </em>
+      </span>
+    </div>
+    };
+
+    let mut dom_updater = DomUpdater::new(old);
+
+    // The span (and thus its em child) gets replaced
+    dom_updater.update(html! { <div> <strong></strong> </div> }).unwrap();
+
+    assert_eq!(*removed.borrow(), vec!["descendant".to_string()]);
+}
+
+/// wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test elem_hooks on_update_elem
+/// @book start on-update-elem
+#[wasm_bindgen_test]
+fn on_update_elem() {
+    console_error_panic_hook::set_once();
+
+    let updated: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(vec![]));
+
+    let mut dom_updater = DomUpdater::new(html! { <div class="one"></div> });
+
+    let updated_clone = Rc::clone(&updated);
+    let new = html! {
+    <div
+      class="two"
+      on_update_elem=move |elem: Element| {
+        // Let the third-party widget know that its attributes changed
+        updated_clone.borrow_mut().push(elem.class_name());
+      }
+    >
+    </div>
+    };
+    dom_updater.update(new).unwrap();
+
+    assert_eq!(*updated.borrow(), vec!["two".to_string()]);
+}
+// @book end on-update-elem
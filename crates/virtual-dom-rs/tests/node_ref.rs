@@ -0,0 +1,67 @@
+//! Ensure that node refs point to the real DOM elements that they were given to.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test node_ref
+
+#![feature(proc_macro_hygiene)]
+
+use console_error_panic_hook;
+use virtual_dom_rs::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::HtmlInputElement;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test node_ref set_on_create
+/// @book start node-ref
+#[wasm_bindgen_test]
+fn set_on_create() {
+    console_error_panic_hook::set_once();
+
+    let input_ref = NodeRef::new();
+
+    let _dom_updater = DomUpdater::new(html! {
+    <div>
+      <input id="name" ref=input_ref />
+    </div>
+    });
+
+    // Grab the real DOM element without querying the document by id
+    let input: HtmlInputElement = input_ref.get().unwrap().dyn_into().unwrap();
+
+    assert_eq!(input.id(), "name");
+}
+// @book end node-ref
+
+/// wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test node_ref set_on_patch
+#[wasm_bindgen_test]
+fn set_on_patch() {
+    console_error_panic_hook::set_once();
+
+    let node_ref = NodeRef::new();
+
+    let mut dom_updater = DomUpdater::new(html! { <div> <span id="kept"></span> </div> });
+    assert!(node_ref.get().is_none());
+
+    dom_updater.update(html! { <div> <span id="kept" ref=node_ref></span> </div> }).unwrap();
+    assert_eq!(node_ref.get().unwrap().id(), "kept");
+
+    dom_updater.update(html! { <div> <em id="appended" ref=node_ref></em> </div> }).unwrap();
+    assert_eq!(node_ref.get().unwrap().id(), "appended");
+}
+
+/// wasm-pack test crates/virtual-dom-rs --chrome --headless -- --test node_ref clear_on_remove
+#[wasm_bindgen_test]
+fn clear_on_remove() {
+    console_error_panic_hook::set_once();
+
+    let node_ref = NodeRef::new();
+
+    let mut dom_updater = DomUpdater::new(html! { <div> <span ref=node_ref></span> </div> });
+    assert!(node_ref.get().is_some());
+
+    dom_updater.update(html! { <div></div> }).unwrap();
+    assert!(node_ref.get().is_none());
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+
+/// The class tokens of an element, such as `btn` and `active`.
+///
+/// A `class` attribute is a single string, so toggling one class rewrites the whole attribute
+/// and wipes out classes that were added by other code through `classList`. Class tokens are
+/// instead added and removed one at a time through the element's `classList`.
+///
+/// Tokens are kept in the order that they were first inserted and duplicates are ignored.
+///
+/// ```
+/// use virtual_node::Classes;
+///
+/// let mut classes = Classes::from("btn big");
+/// classes.insert("active btn");
+///
+/// assert!(classes.contains("active"));
+/// assert_eq!(classes.to_string(), "btn big active");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Classes(Vec<String>);
+
+impl Classes {
+    /// Create an empty set of class tokens.
+    pub fn new() -> Self {
+        Classes(vec![])
+    }
+
+    /// Insert one or more class tokens, such as `"btn"`, `"btn active"` or another `Classes`.
+    pub fn insert<C: Into<Classes>>(&mut self, classes: C) {
+        for token in classes.into().0 {
+            if !self.contains(&token) {
+                self.0.push(token);
+            }
+        }
+    }
+
+    /// Remove a class token, returning whether or not it was present.
+    pub fn remove(&mut self, token: &str) -> bool {
+        let len = self.0.len();
+        self.0.retain(|existing| existing != token);
+
+        self.0.len() != len
+    }
+
+    /// Whether or not the class token is present.
+    pub fn contains(&self, token: &str) -> bool {
+        self.0.iter().any(|existing| existing == token)
+    }
+
+    /// The class tokens, in the order that they were first inserted.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|token| token.as_str())
+    }
+
+    /// The number of class tokens.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether or not there are no class tokens.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Build `Classes` out of class tokens, some of which are only included if their condition
+/// is true.
+///
+/// ```
+/// use virtual_node::classes;
+///
+/// let is_active = true;
+/// let is_disabled = false;
+///
+/// let classes = classes!["btn", "active" => is_active, "disabled" => is_disabled];
+///
+/// assert_eq!(classes.to_string(), "btn active");
+/// ```
+#[macro_export]
+macro_rules! classes {
+    ($($token:expr $(=> $condition:expr)?),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut classes = $crate::Classes::new();
+        $(
+            if true $(&& $condition)? {
+                classes.insert($token);
+            }
+        )*
+        classes
+    }};
+}
+
+// Split a class attribute such as "btn active" into its tokens
+impl From<&str> for Classes {
+    fn from(tokens: &str) -> Self {
+        let mut classes = Classes::new();
+
+        for token in tokens.split_whitespace() {
+            if !classes.contains(token) {
+                classes.0.push(token.to_string());
+            }
+        }
+
+        classes
+    }
+}
+
+impl From<String> for Classes {
+    fn from(tokens: String) -> Self {
+        Classes::from(tokens.as_str())
+    }
+}
+
+impl From<&String> for Classes {
+    fn from(tokens: &String) -> Self {
+        Classes::from(tokens.as_str())
+    }
+}
+
+// The order that tokens were inserted in doesn't matter
+impl PartialEq for Classes {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.len() == rhs.len() && self.iter().all(|token| rhs.contains(token))
+    }
+}
+
+// Turn the tokens into the value of a `class` attribute, such as `btn active`
+impl fmt::Display for Classes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, token) in self.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+
+            write!(f, "{}", token)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deduplicates_tokens() {
+        let mut classes = Classes::from("a  b a");
+        classes.insert(String::from("c b"));
+        classes.insert(classes!["a", "d"]);
+
+        assert_eq!(classes.to_string(), "a b c d");
+
+        assert!(classes.remove("b"));
+        assert!(!classes.remove("b"));
+        assert_eq!(classes.to_string(), "a c d");
+    }
+
+    #[test]
+    fn conditional_tokens() {
+        let count = 2;
+
+        let classes = classes![
+            "counter",
+            "even" => count % 2 == 0,
+            "odd" => count % 2 == 1,
+            format!("count-{}", count),
+        ];
+
+        assert_eq!(classes, Classes::from("count-2 even counter"));
+        assert_eq!(classes.to_string(), "counter even count-2");
+    }
+}
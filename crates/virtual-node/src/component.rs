@@ -0,0 +1,277 @@
+//! Stateful components that own their local state and receive lifecycle callbacks when
+//! their root element is created, patched or removed.
+
+use crate::{View, VirtualNode};
+use lazy_static::lazy_static;
+use std::cell::{Ref, RefCell};
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Mutex;
+
+// Used to uniquely identify components so that the DomUpdater can tell whether a component
+// that it sees in a new virtual dom was already mounted.
+lazy_static! {
+    static ref COMPONENT_UNIQUE_ID: Mutex<u32> = Mutex::new(0);
+}
+
+/// Called by a mounted component when it wants the application to re-render.
+pub type RenderRequest = Rc<dyn Fn()>;
+
+/// A view that owns local state.
+///
+/// Components are rendered through a [`ComponentHandle`]. Clone the handle into your
+/// event handlers in order to update the component's state and request a re-render.
+///
+/// ```ignore
+/// struct Dropdown {
+///     open: bool,
+/// }
+///
+/// impl Component for Dropdown {
+///     fn render(&self, handle: &ComponentHandle<Self>) -> VirtualNode {
+///         let handle = handle.clone();
+///
+///         html! {
+///           <button onclick=move |_: web_sys::Event| { handle.update(|d| d.open = !d.open) }>
+///             { if self.open { "Close" } else { "Open" } }
+///           </button>
+///         }
+///     }
+/// }
+/// ```
+///
+/// The `DomUpdater` calls `mounted`, `updated` and `will_unmount` when the component's root
+/// element is created, patched or removed. Components that render a text node as their root
+/// do not receive lifecycle callbacks.
+///
+/// [`ComponentHandle`]: struct.ComponentHandle.html
+pub trait Component: Sized + 'static {
+    /// Render the component's current state.
+    fn render(&self, handle: &ComponentHandle<Self>) -> VirtualNode;
+
+    /// Called after the component's root element was created and added to the DOM.
+    fn mounted(&mut self) {}
+
+    /// Called after the DOM was patched while the component stayed mounted.
+    fn updated(&mut self) {}
+
+    /// Called right before the component's root element is removed from the DOM.
+    fn will_unmount(&mut self) {}
+}
+
+/// A shared handle to a [`Component`] and its state.
+///
+/// Cloning a handle is cheap. All clones point to the same component.
+///
+/// [`Component`]: trait.Component.html
+pub struct ComponentHandle<C> {
+    inner: Rc<ComponentInner<C>>,
+}
+
+struct ComponentInner<C> {
+    id: u32,
+    state: RefCell<C>,
+    /// Set while the component is mounted.
+    render_request: RefCell<Option<RenderRequest>>,
+}
+
+impl<C: Component> ComponentHandle<C> {
+    /// Create a new handle that owns the given component.
+    pub fn new(component: C) -> Self {
+        ComponentHandle {
+            inner: Rc::new(ComponentInner {
+                id: create_unique_identifier(),
+                state: RefCell::new(component),
+                render_request: RefCell::new(None),
+            }),
+        }
+    }
+
+    /// Borrow the component's state.
+    pub fn state(&self) -> Ref<'_, C> {
+        self.inner.state.borrow()
+    }
+
+    /// Mutate the component's state and then request a re-render.
+    pub fn update<F>(&self, update: F)
+    where
+        F: FnOnce(&mut C),
+    {
+        update(&mut self.inner.state.borrow_mut());
+
+        self.request_render();
+    }
+
+    /// Ask the application to re-render. Does nothing if the component is not mounted.
+    pub fn request_render(&self) {
+        let render_request = self.inner.render_request.borrow().clone();
+
+        if let Some(render_request) = render_request {
+            render_request();
+        }
+    }
+
+    /// Whether or not the component's root element is currently in the DOM.
+    pub fn is_mounted(&self) -> bool {
+        self.inner.render_request.borrow().is_some()
+    }
+}
+
+impl<C> Clone for ComponentHandle<C> {
+    fn clone(&self) -> Self {
+        ComponentHandle {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<C: Component> View for ComponentHandle<C> {
+    /// Render the component and mark its root element so that the `DomUpdater` can drive
+    /// the component's lifecycle.
+    fn render(&self) -> VirtualNode {
+        let mut node = self.inner.state.borrow().render(self);
+
+        if let Some(element) = node.as_velement_mut() {
+            let lifecycle: Rc<dyn ComponentLifecycle> = self.inner.clone();
+            element.components.0.push(lifecycle);
+        }
+
+        node
+    }
+}
+
+/// The type erased lifecycle of a component.
+///
+/// Stored on the component's root `VElement` so that the `DomUpdater` can see which
+/// components were added, kept or removed by an update.
+pub trait ComponentLifecycle {
+    /// Uniquely identifies the component.
+    fn component_id(&self) -> u32;
+    /// The component's root element was added to the DOM.
+    fn mounted(&self, render_request: RenderRequest);
+    /// The DOM was patched and the component is still mounted.
+    fn updated(&self);
+    /// The component's root element is about to be removed from the DOM.
+    fn will_unmount(&self);
+}
+
+impl<C: Component> ComponentLifecycle for ComponentInner<C> {
+    fn component_id(&self) -> u32 {
+        self.id
+    }
+
+    fn mounted(&self, render_request: RenderRequest) {
+        *self.render_request.borrow_mut() = Some(render_request);
+        self.state.borrow_mut().mounted();
+    }
+
+    fn updated(&self) {
+        self.state.borrow_mut().updated();
+    }
+
+    fn will_unmount(&self) {
+        self.state.borrow_mut().will_unmount();
+        *self.render_request.borrow_mut() = None;
+    }
+}
+
+/// The components whose root element is a `VElement`.
+///
+/// Usually there is zero or one, but a component that renders another component as its root
+/// shares its root element with that component.
+#[derive(Clone)]
+pub struct Components(pub Vec<Rc<dyn ComponentLifecycle>>);
+
+impl PartialEq for Components {
+    // Components don't impact what gets rendered, so just like `Events` they don't
+    // take part in comparisons.
+    fn eq(&self, _rhs: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Components {
+    // Print out the ids of the components
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ids: Vec<u32> = self.0.iter().map(|c| c.component_id()).collect();
+        write!(f, "{:?}", ids)
+    }
+}
+
+fn create_unique_identifier() -> u32 {
+    let mut component_unique_id = COMPONENT_UNIQUE_ID.lock().unwrap();
+
+    *component_unique_id += 1;
+
+    *component_unique_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct Counter {
+        count: u32,
+    }
+
+    impl Component for Counter {
+        fn render(&self, _handle: &ComponentHandle<Self>) -> VirtualNode {
+            VirtualNode::text(self.count.to_string())
+        }
+    }
+
+    struct Wrapper {
+        mounted: u32,
+    }
+
+    impl Component for Wrapper {
+        fn render(&self, _handle: &ComponentHandle<Self>) -> VirtualNode {
+            VirtualNode::element("div")
+        }
+
+        fn mounted(&mut self) {
+            self.mounted += 1;
+        }
+    }
+
+    #[test]
+    fn update_requests_render_once_mounted() {
+        let renders = Rc::new(Cell::new(0));
+        let renders_clone = Rc::clone(&renders);
+
+        let counter = ComponentHandle::new(Counter { count: 0 });
+
+        counter.update(|c| c.count += 1);
+        assert_eq!(
+            renders.get(),
+            0,
+            "Unmounted components don't request renders"
+        );
+
+        counter
+            .inner
+            .mounted(Rc::new(move || renders_clone.set(renders_clone.get() + 1)));
+        counter.update(|c| c.count += 1);
+
+        assert_eq!(renders.get(), 1);
+        assert_eq!(counter.render().to_string(), "2");
+    }
+
+    #[test]
+    fn marks_root_element() {
+        let wrapper = ComponentHandle::new(Wrapper { mounted: 0 });
+
+        let node = wrapper.render();
+        let components = &node.as_velement_ref().unwrap().components;
+
+        assert_eq!(components.0.len(), 1);
+
+        components.0[0].mounted(Rc::new(|| {}));
+        assert_eq!(wrapper.state().mounted, 1);
+        assert!(wrapper.is_mounted());
+
+        components.0[0].will_unmount();
+        assert!(!wrapper.is_mounted());
+    }
+}
@@ -0,0 +1,342 @@
+//! The operations that we need from a DOM in order to create and patch nodes.
+//!
+//! In the browser this is `web_sys`, but any implementation of `DomBackend` can be targeted.
+//! For example an in-memory DOM, so that the whole diff -> patch pipeline can run in a plain
+//! `cargo test`.
+
+use crate::{DynClosure, NodeRef};
+use std::fmt;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+use web_sys::{CssStyleDeclaration, Document, Element, EventTarget, Node, Text};
+
+/// The kinds of nodes that a `DomBackend` can hand back to us.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    /// An element such as `<div>`
+    Element,
+    /// A text node
+    Text,
+    /// A comment node, such as the `<!--ptns-->` separators that we place between neighboring
+    /// text nodes.
+    Comment,
+    /// Any other kind of node
+    Other,
+}
+
+/// A DOM that virtual nodes can be created in and patched into.
+///
+/// `Node` is a cheap handle to a node in the DOM. Cloning it should not clone the underlying
+/// node.
+pub trait DomBackend {
+    /// A handle to an element, text or comment node.
+    type Node: Clone;
+    /// The error that DOM operations can fail with.
+    type Error: fmt::Debug;
+
+    /// Create an element with the given tag.
+    fn create_element(&self, tag: &str) -> Result<Self::Node, Self::Error>;
+    /// Create an element with the given tag within a namespace such as
+    /// `http://www.w3.org/2000/svg`.
+    fn create_element_ns(&self, namespace: &str, tag: &str) -> Result<Self::Node, Self::Error>;
+    /// Create a text node.
+    fn create_text_node(&self, text: &str) -> Self::Node;
+    /// Create a comment node.
+    fn create_comment(&self, text: &str) -> Self::Node;
+
+    /// What kind of node this is.
+    fn node_kind(&self, node: &Self::Node) -> NodeKind;
+    /// All of the node's children, including text and comment nodes.
+    fn child_nodes(&self, node: &Self::Node) -> Vec<Self::Node>;
+    /// The node that this node is a child of.
+    fn parent_node(&self, node: &Self::Node) -> Option<Self::Node>;
+    /// The tag of an element without any prefix, such as `foreignObject`.
+    fn local_name(&self, elem: &Self::Node) -> String;
+    /// The namespace of an element, such as `http://www.w3.org/2000/svg`.
+    fn namespace_uri(&self, elem: &Self::Node) -> Option<String>;
+    /// The element that a portal with the given target renders its children into.
+    fn portal_target(&self, target: &str) -> Option<Self::Node>;
+
+    /// Set an attribute on an element.
+    fn set_attribute(&self, elem: &Self::Node, name: &str, value: &str) -> Result<(), Self::Error>;
+    /// Set an attribute that has a namespace, such as `xlink:href`, on an element.
+    fn set_attribute_ns(
+        &self,
+        elem: &Self::Node,
+        namespace: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<(), Self::Error>;
+    /// Remove an attribute from an element.
+    fn remove_attribute(&self, elem: &Self::Node, name: &str) -> Result<(), Self::Error>;
+    /// Add a token to an element's class list.
+    fn add_class(&self, elem: &Self::Node, token: &str) -> Result<(), Self::Error>;
+    /// Remove a token from an element's class list.
+    fn remove_class(&self, elem: &Self::Node, token: &str) -> Result<(), Self::Error>;
+    /// Set a property of an element's inline style, such as `width`.
+    fn set_style_property(
+        &self,
+        elem: &Self::Node,
+        name: &str,
+        value: &str,
+    ) -> Result<(), Self::Error>;
+    /// Remove a property from an element's inline style.
+    fn remove_style_property(&self, elem: &Self::Node, name: &str) -> Result<(), Self::Error>;
+    /// Replace an element's children with the given HTML.
+    fn set_inner_html(&self, elem: &Self::Node, html: &str);
+    /// Whether or not the element's children were set with `set_inner_html`.
+    ///
+    /// Those children aren't part of the virtual dom, so we skip over them when looking for
+    /// the nodes to patch.
+    fn has_inner_html(&self, elem: &Self::Node) -> bool;
+    /// Set the text of a text node.
+    fn set_text(&self, node: &Self::Node, text: &str);
+
+    /// Append a child to a node.
+    fn append_child(&self, parent: &Self::Node, child: &Self::Node) -> Result<(), Self::Error>;
+    /// Insert a child right before one of the parent's other children.
+    fn insert_before(
+        &self,
+        parent: &Self::Node,
+        child: &Self::Node,
+        reference: &Self::Node,
+    ) -> Result<(), Self::Error>;
+    /// Put a new node in the place of an old node.
+    fn replace_with(&self, old: &Self::Node, new: &Self::Node) -> Result<(), Self::Error>;
+    /// Remove a child from its parent.
+    fn remove_child(&self, parent: &Self::Node, child: &Self::Node) -> Result<(), Self::Error>;
+
+    /// Listen to an event such as `click` on an element.
+    fn add_event_listener(
+        &self,
+        elem: &Self::Node,
+        event: &str,
+        callback: &DynClosure,
+    ) -> Result<(), Self::Error>;
+    /// Call an element hook such as `on_create_elem` with the element.
+    fn call_elem_hook(&self, hook: &DynClosure, elem: &Self::Node) -> Result<(), Self::Error>;
+
+    /// Point a `NodeRef` at an element.
+    fn set_node_ref(&self, node_ref: &NodeRef, elem: &Self::Node);
+    /// Clear a `NodeRef` if it points at the element.
+    fn clear_node_ref(&self, node_ref: &NodeRef, elem: &Self::Node);
+
+    /// The namespace that a new child of this node would be in, or `None` if this node
+    /// isn't an element.
+    fn children_namespace(&self, node: &Self::Node) -> Option<&'static str> {
+        if self.node_kind(node) != NodeKind::Element {
+            return None;
+        }
+
+        let namespace = self.namespace_uri(node);
+        let namespace = namespace.as_ref().map(|n| n.as_str());
+
+        Some(html_validation::children_namespace(
+            &self.local_name(node),
+            namespace.unwrap_or(html_validation::HTML_NAMESPACE),
+        ))
+    }
+
+    /// Set an attribute on an element, within the attribute's namespace if it has one.
+    fn apply_attribute(
+        &self,
+        elem: &Self::Node,
+        name: &str,
+        value: &str,
+    ) -> Result<(), Self::Error> {
+        match html_validation::attribute_namespace(name) {
+            Some(namespace) => self.set_attribute_ns(elem, namespace, name, value),
+            None => self.set_attribute(elem, name, value),
+        }
+    }
+}
+
+/// The property that marks elements whose children were set with `set_inner_html`.
+const INNER_HTML_KEY: &str = "__percyInnerHtml";
+
+/// The real DOM in a browser.
+pub struct WebSysBackend {
+    document: Document,
+}
+
+impl WebSysBackend {
+    /// Create and patch nodes in the window's document.
+    pub fn new() -> Self {
+        WebSysBackend {
+            document: web_sys::window().unwrap().document().unwrap(),
+        }
+    }
+}
+
+impl DomBackend for WebSysBackend {
+    type Node = Node;
+    type Error = JsValue;
+
+    fn create_element(&self, tag: &str) -> Result<Node, JsValue> {
+        Ok(self.document.create_element(tag)?.into())
+    }
+
+    fn create_element_ns(&self, namespace: &str, tag: &str) -> Result<Node, JsValue> {
+        Ok(self
+            .document
+            .create_element_ns(Some(namespace), tag)?
+            .into())
+    }
+
+    fn create_text_node(&self, text: &str) -> Node {
+        self.document.create_text_node(text).into()
+    }
+
+    fn create_comment(&self, text: &str) -> Node {
+        self.document.create_comment(text).into()
+    }
+
+    fn node_kind(&self, node: &Node) -> NodeKind {
+        match node.node_type() {
+            Node::ELEMENT_NODE => NodeKind::Element,
+            Node::TEXT_NODE => NodeKind::Text,
+            Node::COMMENT_NODE => NodeKind::Comment,
+            _ => NodeKind::Other,
+        }
+    }
+
+    fn child_nodes(&self, node: &Node) -> Vec<Node> {
+        // We use child_nodes() instead of children() because children() ignores text nodes
+        let children = node.child_nodes();
+
+        (0..children.length())
+            .map(|i| children.item(i).unwrap())
+            .collect()
+    }
+
+    fn parent_node(&self, node: &Node) -> Option<Node> {
+        node.parent_node()
+    }
+
+    fn local_name(&self, elem: &Node) -> String {
+        elem.unchecked_ref::<Element>().local_name()
+    }
+
+    fn namespace_uri(&self, elem: &Node) -> Option<String> {
+        elem.unchecked_ref::<Element>().namespace_uri()
+    }
+
+    /// The first element in the document that matches the target as a CSS selector.
+    fn portal_target(&self, target: &str) -> Option<Node> {
+        match self.document.query_selector(target) {
+            Ok(Some(elem)) => Some(elem.into()),
+            _ => None,
+        }
+    }
+
+    fn set_attribute(&self, elem: &Node, name: &str, value: &str) -> Result<(), JsValue> {
+        elem.unchecked_ref::<Element>().set_attribute(name, value)
+    }
+
+    fn set_attribute_ns(
+        &self,
+        elem: &Node,
+        namespace: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<(), JsValue> {
+        elem.unchecked_ref::<Element>()
+            .set_attribute_ns(Some(namespace), name, value)
+    }
+
+    fn remove_attribute(&self, elem: &Node, name: &str) -> Result<(), JsValue> {
+        elem.unchecked_ref::<Element>().remove_attribute(name)
+    }
+
+    fn add_class(&self, elem: &Node, token: &str) -> Result<(), JsValue> {
+        elem.unchecked_ref::<Element>().class_list().add_1(token)
+    }
+
+    fn remove_class(&self, elem: &Node, token: &str) -> Result<(), JsValue> {
+        elem.unchecked_ref::<Element>().class_list().remove_1(token)
+    }
+
+    fn set_style_property(&self, elem: &Node, name: &str, value: &str) -> Result<(), JsValue> {
+        inline_style(elem)?.set_property(name, value)
+    }
+
+    fn remove_style_property(&self, elem: &Node, name: &str) -> Result<(), JsValue> {
+        inline_style(elem)?.remove_property(name)?;
+        Ok(())
+    }
+
+    fn set_inner_html(&self, elem: &Node, html: &str) {
+        elem.unchecked_ref::<Element>().set_inner_html(html);
+
+        // Remember that the children came from raw HTML without showing up in the markup
+        js_sys::Reflect::set(elem, &INNER_HTML_KEY.into(), &JsValue::from_bool(true)).unwrap();
+    }
+
+    fn has_inner_html(&self, elem: &Node) -> bool {
+        js_sys::Reflect::get(elem, &INNER_HTML_KEY.into())
+            .ok()
+            .and_then(|has_inner_html| has_inner_html.as_bool())
+            .unwrap_or(false)
+    }
+
+    fn set_text(&self, node: &Node, text: &str) {
+        node.set_node_value(Some(text));
+    }
+
+    fn append_child(&self, parent: &Node, child: &Node) -> Result<(), JsValue> {
+        parent.append_child(child)?;
+        Ok(())
+    }
+
+    fn insert_before(&self, parent: &Node, child: &Node, reference: &Node) -> Result<(), JsValue> {
+        parent.insert_before(child, Some(reference))?;
+        Ok(())
+    }
+
+    fn replace_with(&self, old: &Node, new: &Node) -> Result<(), JsValue> {
+        match self.node_kind(old) {
+            NodeKind::Element => old.unchecked_ref::<Element>().replace_with_with_node_1(new),
+            _ => old.unchecked_ref::<Text>().replace_with_with_node_1(new),
+        }
+    }
+
+    fn remove_child(&self, parent: &Node, child: &Node) -> Result<(), JsValue> {
+        parent.remove_child(child)?;
+        Ok(())
+    }
+
+    fn add_event_listener(
+        &self,
+        elem: &Node,
+        event: &str,
+        callback: &DynClosure,
+    ) -> Result<(), JsValue> {
+        let current_elem: &EventTarget = elem.dyn_ref().unwrap();
+
+        current_elem
+            .add_event_listener_with_callback(event, callback.as_ref().as_ref().unchecked_ref())
+    }
+
+    fn call_elem_hook(&self, hook: &DynClosure, elem: &Node) -> Result<(), JsValue> {
+        let hook: &js_sys::Function = hook.as_ref().as_ref().unchecked_ref();
+        hook.call1(&JsValue::NULL, elem)?;
+
+        Ok(())
+    }
+
+    fn set_node_ref(&self, node_ref: &NodeRef, elem: &Node) {
+        node_ref.set(elem.clone().unchecked_into());
+    }
+
+    fn clear_node_ref(&self, node_ref: &NodeRef, elem: &Node) {
+        node_ref.clear(elem.unchecked_ref());
+    }
+}
+
+/// The `CSSStyleDeclaration` of an element's inline styles.
+///
+/// SVG and MathML elements have a `style` too, so we don't cast the element to an
+/// `HtmlElement`.
+fn inline_style(elem: &Node) -> Result<CssStyleDeclaration, JsValue> {
+    Ok(js_sys::Reflect::get(elem, &JsValue::from_str("style"))?.unchecked_into())
+}
@@ -0,0 +1,137 @@
+//! The closures that can be attached to elements as event handlers and hooks.
+
+use crate::Events;
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::closure::{Closure, WasmClosure};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// An event handler or hook such as `onclick` or `on_create_elem`.
+///
+/// In the browser these are `wasm_bindgen` `Closure`s that get handed to JavaScript. Outside of
+/// the browser the `html!` macro creates `NativeClosure`s instead, so that DOMs that don't live
+/// in a browser can still call them.
+pub trait EventHandler: AsRef<JsValue> {
+    /// Call the handler without going through JavaScript.
+    ///
+    /// Does nothing for handlers that can only be called from JavaScript.
+    fn call_native(&self, _arg: JsValue) {}
+}
+
+impl<T: WasmClosure + ?Sized> EventHandler for Closure<T> {}
+
+impl EventHandler for JsValue {}
+
+/// An event handler that can be called from Rust outside of the browser.
+///
+/// Since there are no real DOM events or elements outside of the browser the handler gets called
+/// with a placeholder (`null`) argument, so handlers that inspect their argument only work in
+/// the browser.
+pub struct NativeClosure {
+    js_value: JsValue,
+    callback: RefCell<Box<dyn FnMut(JsValue)>>,
+}
+
+impl NativeClosure {
+    /// Wrap a handler such as `move |event: MouseEvent| { .. }`.
+    pub fn new<F, A>(mut callback: F) -> Self
+    where
+        F: FnMut(A) + 'static,
+        A: JsCast,
+    {
+        NativeClosure {
+            js_value: JsValue::NULL,
+            callback: RefCell::new(Box::new(move |arg: JsValue| callback(arg.unchecked_into()))),
+        }
+    }
+}
+
+impl AsRef<JsValue> for NativeClosure {
+    fn as_ref(&self) -> &JsValue {
+        &self.js_value
+    }
+}
+
+impl EventHandler for NativeClosure {
+    fn call_native(&self, arg: JsValue) {
+        (self.callback.borrow_mut())(arg)
+    }
+}
+
+/// A closure from the `html!` macro that is waiting to be added to an element's events outside
+/// of the browser.
+///
+/// Closures that take a single `JsCast` argument get inserted as `NativeClosure`s through
+/// `InsertNativeEvent`. Every other closure (`|| {}`, `|_: u8| {}`, ...) can't be called
+/// without JavaScript, so `SkipNativeEvent` drops it. Calling
+/// `(&NativeEvent::new(closure)).insert_native_event(..)` picks the first of the two that applies.
+pub struct NativeEvent<F>(Cell<Option<F>>);
+
+impl<F> NativeEvent<F> {
+    /// Wrap a closure from an `html!` event attribute.
+    pub fn new(callback: F) -> Self {
+        NativeEvent(Cell::new(Some(callback)))
+    }
+}
+
+/// Inserts closures that can be called outside of the browser into an element's events.
+pub trait InsertNativeEvent<A> {
+    /// Insert the wrapped closure as the handler for the `name` event.
+    fn insert_native_event(&self, events: &mut Events, name: &str);
+}
+
+impl<F, A> InsertNativeEvent<A> for NativeEvent<F>
+where
+    F: FnMut(A) + 'static,
+    A: JsCast,
+{
+    fn insert_native_event(&self, events: &mut Events, name: &str) {
+        if let Some(callback) = self.0.take() {
+            events.insert_native(name, callback);
+        }
+    }
+}
+
+/// Drops closures that can only be called from JavaScript.
+pub trait SkipNativeEvent {
+    /// Does nothing, the closure is dropped along with the `NativeEvent`.
+    fn insert_native_event(&self, _events: &mut Events, _name: &str) {}
+}
+
+impl<'a, F> SkipNativeEvent for &'a NativeEvent<F> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use web_sys::MouseEvent;
+
+    #[test]
+    fn call_native_closure() {
+        let clicks = Rc::new(Cell::new(0));
+        let clicks_clone = Rc::clone(&clicks);
+
+        let closure = NativeClosure::new(move |_event: MouseEvent| {
+            clicks_clone.set(clicks_clone.get() + 1);
+        });
+
+        closure.call_native(JsValue::NULL);
+        closure.call_native(JsValue::NULL);
+
+        assert_eq!(clicks.get(), 2);
+    }
+
+    #[test]
+    fn only_js_cast_closures_become_native_events() {
+        let mut events = Events(HashMap::new());
+
+        (&NativeEvent::new(|_: MouseEvent| {})).insert_native_event(&mut events, "onclick");
+        (&NativeEvent::new(|_: u8| {})).insert_native_event(&mut events, "onbyte");
+        (&NativeEvent::new(|| {})).insert_native_event(&mut events, "oninput");
+
+        assert!(events.0.contains_key("onclick"));
+        assert!(!events.0.contains_key("onbyte"));
+        assert!(!events.0.contains_key("oninput"));
+    }
+}
@@ -9,16 +9,40 @@
 //
 // Around in order to get rid of dependencies that we don't need in non wasm32 targets
 
+use std::cell::Cell;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::rc::Rc;
 
 pub mod virtual_node_test_utils;
 
-use web_sys::{self, Element, EventTarget, Node, Text};
+mod component;
+pub use self::component::*;
+
+mod node_ref;
+pub use self::node_ref::*;
+
+mod dom_backend;
+pub use self::dom_backend::*;
+
+mod event_handler;
+pub use self::event_handler::*;
+
+mod style;
+pub use self::style::*;
+
+mod classes;
+pub use self::classes::*;
+
+mod portal;
+pub use self::portal::*;
+
+mod tree;
+pub use self::tree::*;
+
+use web_sys::{self, Element, Node, Text};
 
 use wasm_bindgen::JsCast;
-use wasm_bindgen::JsValue;
 
 use std::iter::FromIterator;
 use std::ops::Deref;
@@ -49,7 +73,7 @@ lazy_static! {
 /// TODO: Make all of these fields private and create accessor methods
 /// TODO: Create a builder to create instances of VirtualNode::Element with
 /// attrs and children without having to explicitly create a VElement
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub enum VirtualNode {
     /// An element node (node type `ELEMENT_NODE`).
     Element(VElement),
@@ -59,22 +83,35 @@ pub enum VirtualNode {
     /// order to enable custom methods like `create_text_node()` on the
     /// wrapped type.
     Text(VText),
+    /// Children that get rendered into a different element, such as `document.body`.
+    Portal(VPortal),
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct VElement {
     /// The HTML tag, such as "div"
     pub tag: String,
     /// HTML attributes such as id, class, style, etc
     pub attrs: HashMap<String, String>,
+    /// Class tokens that get added and removed one at a time, such as `btn` or `active`
+    pub classes: Classes,
+    /// Inline style properties that get set one at a time, such as `width` or `color`
+    pub styles: Styles,
     /// Events that will get added to your real DOM element via `.addEventListener`
     pub events: Events,
     /// The children of this `VirtualNode`. So a <div> <em></em> </div> structure would
     /// have a parent div and one child, em.
     pub children: Vec<VirtualNode>,
+    /// Raw HTML that becomes the element's content instead of its `children`, which are
+    /// ignored. It is not escaped, see the `unsafe_inner_html` section of the book.
+    pub unsafe_inner_html: Option<String>,
+    /// The components that rendered this element as their root node.
+    pub components: Components,
+    /// Gets pointed at the real DOM element once it is created.
+    pub node_ref: Option<NodeRef>,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct VText {
     pub text: String,
 }
@@ -112,6 +149,21 @@ impl VirtualNode {
         VirtualNode::Text(VText::new(text.into()))
     }
 
+    /// Create a new portal that renders its children into the target element.
+    ///
+    /// ```ignore
+    /// use virtual_dom_rs::VirtualNode;
+    ///
+    /// let modal = VirtualNode::portal("body", html! { <div class="modal"></div> });
+    /// ```
+    pub fn portal<T, C>(target: T, children: C) -> Self
+    where
+        T: Into<String>,
+        C: Into<IterableNodes>,
+    {
+        VirtualNode::Portal(VPortal::new(target, children))
+    }
+
     /// Return a [`VElement`] reference, if this is an [`Element`] variant.
     ///
     /// [`VElement`]: struct.VElement.html
@@ -158,12 +210,43 @@ impl VirtualNode {
 
     /// Create and return a `CreatedNode` instance (containing a DOM `Node`
     /// together with potentially related closures) for this virtual node.
+    ///
+    /// Panics if the browser throws while creating the node.
     pub fn create_dom_node(&self) -> CreatedNode<Node> {
+        self.create_dom_node_with(&WebSysBackend::new())
+            .expect("Could not create DOM node")
+    }
+
+    /// Create and return a `CreatedNode` instance for this virtual node within the given
+    /// `DomBackend`.
+    pub fn create_dom_node_with<B: DomBackend>(
+        &self,
+        backend: &B,
+    ) -> Result<CreatedNode<B::Node>, B::Error> {
+        self.create_dom_node_within(backend, None)
+    }
+
+    /// Create and return a `CreatedNode` instance for this virtual node within the given
+    /// `DomBackend`, for a parent whose children are in the given namespace.
+    ///
+    /// When the parent's namespace is `None` we guess the namespace of elements based on
+    /// their tags. See `html_validation::element_namespace`.
+    pub fn create_dom_node_within<B: DomBackend>(
+        &self,
+        backend: &B,
+        parent_namespace: Option<&str>,
+    ) -> Result<CreatedNode<B::Node>, B::Error> {
         match self {
-            VirtualNode::Text(text_node) => {
-                CreatedNode::without_closures(text_node.create_text_node())
+            VirtualNode::Text(text_node) => Ok(CreatedNode::without_closures(
+                backend.create_text_node(&text_node.text),
+            )),
+            VirtualNode::Element(element_node) => {
+                element_node.create_element_node_within(backend, parent_namespace)
             }
-            VirtualNode::Element(element_node) => element_node.create_element_node().into(),
+            // The portal's children get created within its target by the `DomUpdater`
+            VirtualNode::Portal(_) => Ok(CreatedNode::without_closures(
+                backend.create_text_node(""),
+            )),
         }
     }
 
@@ -207,74 +290,111 @@ impl VElement {
         VElement {
             tag: tag.into(),
             attrs: HashMap::new(),
+            classes: Classes::new(),
+            styles: Styles::new(),
             events: Events(HashMap::new()),
             children: vec![],
+            unsafe_inner_html: None,
+            components: Components(vec![]),
+            node_ref: None,
+        }
+    }
+
+    /// The children that end up in the DOM. An element with `unsafe_inner_html` has none,
+    /// its raw HTML takes the place of its children.
+    ///
+    /// Patches index the nodes of a virtual dom depth first through these children.
+    pub fn rendered_children(&self) -> &[VirtualNode] {
+        match self.unsafe_inner_html {
+            Some(_) => &[],
+            None => &self.children,
         }
     }
 
     /// Build a DOM element by recursively creating DOM nodes for this element and it's
     /// children, it's children's children, etc.
+    ///
+    /// Panics if the browser throws while creating the element.
     pub fn create_element_node(&self) -> CreatedNode<Element> {
-        let document = web_sys::window().unwrap().document().unwrap();
+        let created_node = self
+            .create_element_node_with(&WebSysBackend::new())
+            .expect("Could not create element");
 
-        let element = if html_validation::is_svg_namespace(&self.tag) {
-            document
-                .create_element_ns(Some("http://www.w3.org/2000/svg"), &self.tag)
-                .unwrap()
+        CreatedNode {
+            node: created_node.node.unchecked_into(),
+            closures: created_node.closures,
+        }
+    }
+
+    /// Build an element within the given `DomBackend` by recursively creating nodes for this
+    /// element and it's children, it's children's children, etc.
+    pub fn create_element_node_with<B: DomBackend>(
+        &self,
+        backend: &B,
+    ) -> Result<CreatedNode<B::Node>, B::Error> {
+        self.create_element_node_within(backend, None)
+    }
+
+    /// Build an element within the given `DomBackend`, for a parent whose children are in
+    /// the given namespace.
+    ///
+    /// Elements inherit the namespace of their parent, so everything inside of an `<svg>`
+    /// is an SVG element until a `<foreignObject>`.
+    pub fn create_element_node_within<B: DomBackend>(
+        &self,
+        backend: &B,
+        parent_namespace: Option<&str>,
+    ) -> Result<CreatedNode<B::Node>, B::Error> {
+        let namespace = html_validation::element_namespace(&self.tag, parent_namespace);
+
+        let element = if namespace == html_validation::HTML_NAMESPACE {
+            backend.create_element(&self.tag)?
         } else {
-            document.create_element(&self.tag).unwrap()
+            backend.create_element_ns(namespace, &self.tag)?
         };
 
         let mut closures = HashMap::new();
 
-        self.attrs.iter().for_each(|(name, value)| {
-            if name == "unsafe_inner_html" {
-                element.set_inner_html(value);
+        for (name, value) in self.attrs.iter() {
+            backend.apply_attribute(&element, name, value)?;
+        }
 
-                return;
-            }
+        for token in self.classes.iter() {
+            backend.add_class(&element, token)?;
+        }
 
-            element
-                .set_attribute(name, value)
-                .expect("Set element attribute in create element");
-        });
+        for (name, value) in self.styles.iter() {
+            backend.set_style_property(&element, name, value)?;
+        }
 
         if self.events.0.len() > 0 {
             let unique_id = create_unique_identifier();
 
-            element
-                .set_attribute("data-vdom-id".into(), &unique_id.to_string())
-                .expect("Could not set attribute on element");
+            backend.set_attribute(&element, "data-vdom-id", &unique_id.to_string())?;
 
-            closures.insert(unique_id, vec![]);
+            let mut element_closures = vec![];
 
-            self.events.0.iter().for_each(|(onevent, callback)| {
+            for (onevent, callback) in self.events.0.iter() {
                 // onclick -> click
                 let event = &onevent[2..];
 
-                let current_elem: &EventTarget = element.dyn_ref().unwrap();
+                backend.add_event_listener(&element, event, callback)?;
+
+                element_closures.push(Rc::clone(callback));
+            }
 
-                current_elem
-                    .add_event_listener_with_callback(
-                        event,
-                        callback.as_ref().as_ref().unchecked_ref(),
-                    )
-                    .unwrap();
+            closures.insert(unique_id, element_closures);
+        }
 
-                closures
-                    .get_mut(&unique_id)
-                    .unwrap()
-                    .push(Rc::clone(callback));
-            });
+        if let Some(html) = &self.unsafe_inner_html {
+            backend.set_inner_html(&element, html);
         }
 
         let mut previous_node_was_text = false;
 
-        self.children.iter().for_each(|child| {
+        for child in self.rendered_children().iter() {
             match child {
                 VirtualNode::Text(text_node) => {
-                    let current_node = element.as_ref() as &web_sys::Node;
-
                     // We ensure that the text siblings are patched by preventing the browser from merging
                     // neighboring text nodes. Originally inspired by some of React's work from 2016.
                     //  -> https://reactjs.org/blog/2016/04/07/react-v15.html#major-changes
@@ -282,41 +402,47 @@ impl VElement {
                     //
                     // `ptns` = Percy text node separator
                     if previous_node_was_text {
-                        let separator = document.create_comment("ptns");
-                        current_node
-                            .append_child(separator.as_ref() as &web_sys::Node)
-                            .unwrap();
+                        let separator = backend.create_comment("ptns");
+                        backend.append_child(&element, &separator)?;
                     }
 
-                    current_node
-                        .append_child(&text_node.create_text_node())
-                        .unwrap();
+                    backend.append_child(&element, &backend.create_text_node(&text_node.text))?;
 
                     previous_node_was_text = true;
                 }
                 VirtualNode::Element(element_node) => {
                     previous_node_was_text = false;
 
-                    let child = element_node.create_element_node();
-                    let child_elem: Element = child.node;
+                    let child = element_node.create_element_node_within(
+                        backend,
+                        Some(html_validation::children_namespace(&self.tag, namespace)),
+                    )?;
 
                     closures.extend(child.closures);
 
-                    element.append_child(&child_elem).unwrap();
+                    backend.append_child(&element, &child.node)?;
+                }
+                VirtualNode::Portal(_) => {
+                    previous_node_was_text = false;
+
+                    let placeholder = child.create_dom_node_with(backend)?;
+                    backend.append_child(&element, &placeholder.node)?;
                 }
             }
-        });
+        }
+
+        if let Some(node_ref) = &self.node_ref {
+            backend.set_node_ref(node_ref, &element);
+        }
 
         if let Some(on_create_elem) = self.events.0.get("on_create_elem") {
-            let on_create_elem: &js_sys::Function =
-                on_create_elem.as_ref().as_ref().unchecked_ref();
-            on_create_elem.call1(&wasm_bindgen::JsValue::NULL, &element);
+            backend.call_elem_hook(on_create_elem, &element)?;
         }
 
-        CreatedNode {
+        Ok(CreatedNode {
             node: element,
             closures,
-        }
+        })
     }
 }
 
@@ -403,7 +529,11 @@ where
 ///
 /// html! { <div> { nodes } </div> }
 ///
-/// nodes can be a String .. VirtualNode .. Vec<VirtualNode> ... etc
+/// nodes can be a String .. VirtualNode .. Vec<VirtualNode> .. Option<VirtualNode> .. a number
+/// .. a View or a reference to one .. any iterator of VirtualNodes such as
+/// `items.iter().map(|item| html! { ... })` ... etc, see `BracedNodes`
+///
+/// `None` and empty iterators don't add any nodes, not even an empty text node.
 pub struct IterableNodes(Vec<VirtualNode>);
 
 impl IterableNodes {
@@ -416,6 +546,24 @@ impl IterableNodes {
     pub fn last(&mut self) -> &mut VirtualNode {
         self.0.last_mut().unwrap()
     }
+
+    /// Used by html-macro to insert space before the first node, if it's a text node.
+    ///
+    /// See `VirtualNode::insert_space_before_text`.
+    pub fn insert_space_before_text(&mut self) {
+        if let Some(first) = self.0.first_mut() {
+            first.insert_space_before_text();
+        }
+    }
+
+    /// Used by html-macro to insert space after the last node, if it's a text node.
+    ///
+    /// See `VirtualNode::insert_space_after_text`.
+    pub fn insert_space_after_text(&mut self) {
+        if let Some(last) = self.0.last_mut() {
+            last.insert_space_after_text();
+        }
+    }
 }
 
 impl IntoIterator for IterableNodes {
@@ -452,6 +600,110 @@ impl From<Vec<VirtualNode>> for IterableNodes {
     }
 }
 
+impl<T: Into<IterableNodes>> From<Option<T>> for IterableNodes {
+    fn from(other: Option<T>) -> Self {
+        match other {
+            Some(nodes) => nodes.into(),
+            None => IterableNodes(vec![]),
+        }
+    }
+}
+
+impl<V> From<&V> for IterableNodes
+where
+    V: View,
+{
+    fn from(v: &V) -> Self {
+        IterableNodes(vec![v.render()])
+    }
+}
+
+/// Numbers become text nodes via their `Display` implementation.
+macro_rules! iterable_nodes_from_display {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for IterableNodes {
+                fn from(other: $ty) -> Self {
+                    IterableNodes(vec![VirtualNode::text(other.to_string())])
+                }
+            }
+        )*
+    };
+}
+
+iterable_nodes_from_display!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+/// Used by the html! macro to turn the expression in a braced block into `IterableNodes`.
+///
+/// `IterableNodes::braced(nodes).iterable_nodes()` picks the first of these that applies, since
+/// method lookup only derefs to the next wrapper when the bounds of the current one aren't met:
+///
+/// - `BracedNodes` for anything that implements `IntoIterator<Item = VirtualNode>`, such as
+///   `items.iter().map(|item| html! { ... })`
+/// - `BracedView` for a `View`
+/// - `BracedInto` for anything that implements `Into<IterableNodes>`
+///
+/// These are inherent methods, so the macro only needs `IterableNodes` to be in scope.
+pub struct BracedNodes<T>(BracedView<T>);
+
+/// A braced block that holds a `View`. See `BracedNodes`.
+pub struct BracedView<T>(BracedInto<T>);
+
+/// A braced block that holds anything that implements `Into<IterableNodes>`. See `BracedNodes`.
+pub struct BracedInto<T>(Cell<Option<T>>);
+
+impl IterableNodes {
+    /// Wrap the expression of a braced block. See `BracedNodes`.
+    pub fn braced<T>(nodes: T) -> BracedNodes<T> {
+        BracedNodes(BracedView(BracedInto(Cell::new(Some(nodes)))))
+    }
+}
+
+impl<T> Deref for BracedNodes<T> {
+    type Target = BracedView<T>;
+
+    fn deref(&self) -> &BracedView<T> {
+        &self.0
+    }
+}
+
+impl<T> Deref for BracedView<T> {
+    type Target = BracedInto<T>;
+
+    fn deref(&self) -> &BracedInto<T> {
+        &self.0
+    }
+}
+
+impl<T> BracedInto<T> {
+    fn take(&self) -> T {
+        self.0.take().expect("Braced nodes are only taken once")
+    }
+}
+
+impl<T: IntoIterator<Item = VirtualNode>> BracedNodes<T> {
+    /// Collect the iterator of virtual nodes.
+    pub fn iterable_nodes(&self) -> IterableNodes {
+        IterableNodes(self.take().into_iter().collect())
+    }
+}
+
+impl<V: View> BracedView<V> {
+    /// Render the view.
+    pub fn iterable_nodes(&self) -> IterableNodes {
+        IterableNodes(vec![self.take().render()])
+    }
+}
+
+impl<T: Into<IterableNodes>> BracedInto<T> {
+    /// Convert the value into `IterableNodes`.
+    pub fn iterable_nodes(&self) -> IterableNodes {
+        self.take().into()
+    }
+}
+
 impl From<VText> for VirtualNode {
     fn from(other: VText) -> Self {
         VirtualNode::Text(other)
@@ -464,6 +716,12 @@ impl From<VElement> for VirtualNode {
     }
 }
 
+impl From<VPortal> for VirtualNode {
+    fn from(other: VPortal) -> Self {
+        VirtualNode::Portal(other)
+    }
+}
+
 impl From<&str> for VirtualNode {
     fn from(other: &str) -> Self {
         VirtualNode::text(other)
@@ -511,6 +769,7 @@ impl fmt::Debug for VirtualNode {
         match self {
             VirtualNode::Element(e) => write!(f, "Node::{:?}", e),
             VirtualNode::Text(t) => write!(f, "Node::{:?}", t),
+            VirtualNode::Portal(p) => write!(f, "Node::{:?}", p),
         }
     }
 }
@@ -519,8 +778,8 @@ impl fmt::Debug for VElement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Element(<{}>, attrs: {:?}, children: {:?})",
-            self.tag, self.attrs, self.children,
+            "Element(<{}>, attrs: {:?}, classes: {:?}, styles: {:?}, inner_html: {:?}, children: {:?})",
+            self.tag, self.attrs, self.classes, self.styles, self.unsafe_inner_html, self.children,
         )
     }
 }
@@ -537,12 +796,43 @@ impl fmt::Display for VElement {
         write!(f, "<{}", self.tag).unwrap();
 
         for (attr, value) in self.attrs.iter() {
+            // A `class` or `style` attribute gets combined with the class tokens or style
+            // properties below
+            if (attr == "class" && !self.classes.is_empty())
+                || (attr == "style" && !self.styles.is_empty())
+            {
+                continue;
+            }
+
             write!(f, r#" {}="{}""#, attr, value)?;
         }
 
+        if !self.classes.is_empty() {
+            let mut classes = Classes::from(self.attrs.get("class").map_or("", |c| c.as_str()));
+            classes.insert(self.classes.clone());
+
+            write!(f, r#" class="{}""#, classes)?;
+        }
+
+        if !self.styles.is_empty() {
+            match self.attrs.get("style") {
+                Some(style) => write!(
+                    f,
+                    r#" style="{}; {}""#,
+                    style.trim_end().trim_end_matches(';'),
+                    self.styles
+                )?,
+                None => write!(f, r#" style="{}""#, self.styles)?,
+            };
+        }
+
         write!(f, ">")?;
 
-        for child in self.children.iter() {
+        if let Some(html) = &self.unsafe_inner_html {
+            write!(f, "{}", html)?;
+        }
+
+        for child in self.rendered_children() {
             write!(f, "{}", child.to_string())?;
         }
 
@@ -567,18 +857,35 @@ impl fmt::Display for VirtualNode {
         match self {
             VirtualNode::Element(element) => write!(f, "{}", element),
             VirtualNode::Text(text) => write!(f, "{}", text),
+            // The portal's children belong to a different element
+            VirtualNode::Portal(_) => Ok(()),
         }
     }
 }
 
-/// Box<dyn AsRef<JsValue>>> is our js_sys::Closure. Stored this way to allow us to store
-/// any Closure regardless of the arguments.
-pub type DynClosure = Rc<dyn AsRef<JsValue>>;
+/// Rc<dyn EventHandler> is our js_sys::Closure (or a NativeClosure outside of the browser).
+/// Stored this way to allow us to store any Closure regardless of the arguments.
+pub type DynClosure = Rc<dyn EventHandler>;
 
 /// We need a custom implementation of fmt::Debug since JsValue doesn't
 /// implement debug.
+#[derive(Clone)]
 pub struct Events(pub HashMap<String, DynClosure>);
 
+impl Events {
+    /// Insert an event handler that can be called outside of the browser.
+    ///
+    /// The `html!` macro uses this when compiling for targets other than wasm32.
+    pub fn insert_native<F, A>(&mut self, name: &str, callback: F)
+    where
+        F: FnMut(A) + 'static,
+        A: JsCast,
+    {
+        self.0
+            .insert(name.to_string(), Rc::new(NativeClosure::new(callback)));
+    }
+}
+
 impl PartialEq for Events {
     // TODO: What should happen here..? And why?
     fn eq(&self, _rhs: &Self) -> bool {
@@ -626,4 +933,55 @@ mod tests {
 
         assert_eq!(node.to_string(), expected);
     }
+
+    #[test]
+    fn styles_to_string() {
+        let mut div = VElement::new("div");
+        div.styles.insert("width", "10px");
+        div.styles.insert("color", "red");
+
+        assert_eq!(
+            div.to_string(),
+            r#"<div style="width: 10px; color: red"></div>"#
+        );
+
+        div.attrs.insert("style".into(), "display: flex;".into());
+
+        assert_eq!(
+            div.to_string(),
+            r#"<div style="display: flex; width: 10px; color: red"></div>"#
+        );
+    }
+
+    #[test]
+    fn portal_children_are_not_rendered() {
+        let mut div = VElement::new("div");
+        div.children.push(VirtualNode::text("Hello"));
+        div.children
+            .push(VirtualNode::portal("body", VirtualNode::text("Modal")));
+
+        assert_eq!(div.to_string(), "<div>Hello</div>");
+    }
+
+    #[test]
+    fn inner_html_to_string() {
+        let mut div = VElement::new("div");
+        div.unsafe_inner_html = Some("<b>Raw</b> & unescaped".to_string());
+        div.children.push(VirtualNode::text("Ignored"));
+
+        assert_eq!(div.to_string(), "<div><b>Raw</b> & unescaped</div>");
+    }
+
+    #[test]
+    fn classes_to_string() {
+        let mut div = VElement::new("div");
+        div.classes.insert("big wide");
+        div.classes.insert("big");
+
+        assert_eq!(div.to_string(), r#"<div class="big wide"></div>"#);
+
+        div.attrs.insert("class".into(), "tall big".into());
+
+        assert_eq!(div.to_string(), r#"<div class="tall big wide"></div>"#);
+    }
 }
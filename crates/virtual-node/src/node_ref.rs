@@ -0,0 +1,88 @@
+//! References to the real DOM elements that were created from virtual elements.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+/// A handle to the real DOM element that was created for a `VElement`.
+///
+/// Give a `NodeRef` to an element in the `html!` macro and it will be filled in with the
+/// element when it gets created, then cleared when the element gets removed or replaced.
+///
+/// ```ignore
+/// let input_ref = NodeRef::new();
+///
+/// let mut dom_updater = DomUpdater::new(html! { <input ref=input_ref /> });
+///
+/// let input: HtmlInputElement = input_ref.get().unwrap().dyn_into().unwrap();
+/// input.focus();
+/// ```
+///
+/// Cloning a `NodeRef` is cheap. All clones point to the same element.
+#[derive(Clone, Default)]
+pub struct NodeRef(Rc<RefCell<Option<Element>>>);
+
+impl NodeRef {
+    /// Create an empty `NodeRef`.
+    pub fn new() -> Self {
+        NodeRef::default()
+    }
+
+    /// The element that this `NodeRef` currently points to, if it is in the DOM.
+    pub fn get(&self) -> Option<Element> {
+        self.0.borrow().clone()
+    }
+
+    /// Point this `NodeRef` at an element.
+    ///
+    /// This is called for you when the element gets created or patched.
+    pub fn set(&self, element: Element) {
+        *self.0.borrow_mut() = Some(element);
+    }
+
+    /// Stop pointing at the given element.
+    ///
+    /// Does nothing if the `NodeRef` was already moved to a different element, so that
+    /// removing an old element does not clear the ref of its replacement.
+    pub fn clear(&self, element: &Element) {
+        let mut current = self.0.borrow_mut();
+
+        let points_to_element = match current.as_ref() {
+            Some(current) => AsRef::<JsValue>::as_ref(current) == AsRef::<JsValue>::as_ref(element),
+            None => false,
+        };
+
+        if points_to_element {
+            *current = None;
+        }
+    }
+}
+
+impl PartialEq for NodeRef {
+    // Two refs are only equal if they're clones of each other.
+    fn eq(&self, rhs: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &rhs.0)
+    }
+}
+
+impl fmt::Debug for NodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NodeRef({:p})", Rc::as_ptr(&self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_are_equal() {
+        let node_ref = NodeRef::new();
+
+        assert_eq!(node_ref, node_ref.clone());
+        assert_ne!(node_ref, NodeRef::new());
+        assert!(node_ref.get().is_none());
+    }
+}
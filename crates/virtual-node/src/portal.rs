@@ -0,0 +1,47 @@
+use crate::{IterableNodes, VirtualNode};
+use std::fmt;
+
+/// Children that get rendered into a different element than the one that they were declared
+/// in, such as a modal that gets rendered into `document.body` while its state lives deep
+/// inside of a view.
+///
+/// A portal leaves an empty text node behind in its place so that the nodes that come after
+/// it keep their indices. Its children get created and patched within its target by the
+/// `DomUpdater`, and they don't get rendered by `to_string`.
+///
+/// ```ignore
+/// html! {
+///   <div>
+///     <button>Open</button>
+///     { VirtualNode::portal("#modals", html! { <div class="modal">Hello</div> }) }
+///   </div>
+/// }
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct VPortal {
+    /// Identifies the element that the children get appended to. In the browser this is a
+    /// CSS selector such as `body` or `#modals`, see `DomBackend::portal_target`.
+    pub target: String,
+    /// The nodes that get appended to the target element.
+    pub children: Vec<VirtualNode>,
+}
+
+impl VPortal {
+    /// Create a portal that renders its children into the target element.
+    pub fn new<T, C>(target: T, children: C) -> Self
+    where
+        T: Into<String>,
+        C: Into<IterableNodes>,
+    {
+        VPortal {
+            target: target.into(),
+            children: children.into().0,
+        }
+    }
+}
+
+impl fmt::Debug for VPortal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Portal({}, children: {:?})", self.target, self.children)
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt;
+
+/// The inline style properties of an element, such as `width` or `color`.
+///
+/// A `style` attribute is a single string, so changing one property rewrites all of the
+/// element's inline styles. Style properties are instead set and removed one at a time
+/// through the element's `CSSStyleDeclaration`, leaving any inline styles that were set by
+/// something else, such as an animation library, alone.
+///
+/// ```
+/// use virtual_node::Styles;
+///
+/// let mut styles = Styles::new();
+/// styles.insert("width", "50px");
+/// styles.insert("color", "red");
+///
+/// assert_eq!(styles.get("width"), Some("50px"));
+/// assert_eq!(styles.to_string(), "width: 50px; color: red");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Styles(Vec<(String, String)>);
+
+impl Styles {
+    /// Create an empty set of style properties.
+    pub fn new() -> Self {
+        Styles(vec![])
+    }
+
+    /// Set a style property, replacing its previous value if it was already set.
+    pub fn insert<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        let name = name.into();
+        let value = value.into();
+
+        match self.0.iter_mut().find(|(prop, _)| *prop == name) {
+            Some(prop) => prop.1 = value,
+            None => self.0.push((name, value)),
+        };
+    }
+
+    /// The value of a style property.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(prop, _)| prop == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Remove a style property, returning its value if it was set.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let idx = self.0.iter().position(|(prop, _)| prop == name)?;
+        Some(self.0.remove(idx).1)
+    }
+
+    /// The style properties and their values, in the order that they were first set.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// The number of style properties.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether or not there are no style properties.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// The order that properties were set in doesn't matter, only their values.
+impl PartialEq for Styles {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.len() == rhs.len()
+            && self
+                .iter()
+                .all(|(name, value)| rhs.get(name) == Some(value))
+    }
+}
+
+// Turn the properties into the value of a `style` attribute, such as `width: 50px; color: red`
+impl fmt::Display for Styles {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, (name, value)) in self.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "; ")?;
+            }
+
+            write!(f, "{}: {}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_replaces_existing_property() {
+        let mut styles = Styles::new();
+        styles.insert("width", "10px");
+        styles.insert("color", "red");
+        styles.insert("width", "20px");
+
+        assert_eq!(styles.len(), 2);
+        assert_eq!(styles.to_string(), "width: 20px; color: red");
+
+        assert_eq!(styles.remove("width"), Some("20px".to_string()));
+        assert_eq!(styles.remove("width"), None);
+        assert_eq!(styles.to_string(), "color: red");
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let mut a = Styles::new();
+        a.insert("width", "10px");
+        a.insert("color", "red");
+
+        let mut b = Styles::new();
+        b.insert("color", "red");
+        b.insert("width", "10px");
+
+        assert_eq!(a, b);
+
+        b.insert("width", "20px");
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,572 @@
+use crate::{VElement, VirtualNode};
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// A virtual dom that lives in a few flat buffers instead of a tree of individually allocated
+/// nodes, typically created with the `html_tree!` macro.
+///
+/// Nodes are stored depth first, so a node's index within the tree is the same index that
+/// patches use to identify it and walking the tree is a walk over a `Vec`. Tag and attribute
+/// names are `Cow<'static, str>`, so names that come from `html_tree!` are never allocated.
+/// Text and attribute values are written into one shared `String`.
+///
+/// A tree holds a single root node at index 0. Call `clear` in order to reuse a tree's buffers
+/// when building the next frame.
+///
+/// ```
+/// use virtual_node::VTree;
+///
+/// let mut tree = VTree::new();
+/// tree.open_element("div");
+/// tree.attr("id", "app");
+/// tree.text("Hello");
+/// tree.close_element();
+///
+/// assert_eq!(tree.to_string(), r#"<div id="app">Hello</div>"#);
+/// ```
+///
+/// Trees only hold elements, attributes and text. Event handlers, node refs, components and
+/// the other features of `VirtualNode` need `html!`.
+///
+/// Trees are diff-only, a `DomUpdater` keeps a `VirtualNode` up to date. Apply the patches from
+/// `diff_tree` with `patch` yourself, or turn a tree into a `VirtualNode` with
+/// `VirtualNode::from(&tree)`.
+#[derive(Debug, Default, Clone)]
+pub struct VTree {
+    nodes: Vec<TreeNode>,
+    attrs: Vec<TreeAttr>,
+    strings: String,
+    /// The elements that were opened but not yet closed
+    open_elements: Vec<usize>,
+    /// Whether or not the next text node gets a space inserted before it
+    space_before_text: bool,
+}
+
+#[derive(Debug, Clone)]
+struct TreeNode {
+    kind: TreeNodeKind,
+    /// The number of nodes within this node, so its next sibling is at `idx + 1 + descendants`
+    descendants: usize,
+}
+
+#[derive(Debug, Clone)]
+enum TreeNodeKind {
+    Element {
+        tag: Cow<'static, str>,
+        attrs: Range<usize>,
+    },
+    Text(Range<usize>),
+}
+
+#[derive(Debug, Clone)]
+struct TreeAttr {
+    name: Cow<'static, str>,
+    value: Range<usize>,
+}
+
+impl VTree {
+    /// Create an empty tree.
+    pub fn new() -> Self {
+        VTree::default()
+    }
+
+    /// Create an empty tree with room for the given number of nodes.
+    pub fn with_capacity(nodes: usize) -> Self {
+        VTree {
+            nodes: Vec::with_capacity(nodes),
+            attrs: Vec::with_capacity(nodes),
+            strings: String::with_capacity(nodes * 8),
+            ..VTree::default()
+        }
+    }
+
+    /// Remove every node while keeping the allocated buffers around for the next tree.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.attrs.clear();
+        self.strings.clear();
+        self.open_elements.clear();
+        self.space_before_text = false;
+    }
+
+    /// Open an element. Every node that gets pushed until the matching `close_element` is
+    /// one of its descendants.
+    pub fn open_element<T: Into<Cow<'static, str>>>(&mut self, tag: T) {
+        self.space_before_text = false;
+
+        let attrs_start = self.attrs.len();
+        self.open_elements.push(self.nodes.len());
+        self.nodes.push(TreeNode {
+            kind: TreeNodeKind::Element {
+                tag: tag.into(),
+                attrs: attrs_start..attrs_start,
+            },
+            descendants: 0,
+        });
+    }
+
+    /// Set an attribute of the most recently opened element.
+    ///
+    /// Attributes need to be set before any children get pushed into the element.
+    pub fn attr<N, V>(&mut self, name: N, value: V)
+    where
+        N: Into<Cow<'static, str>>,
+        V: fmt::Display,
+    {
+        let element_idx = *self
+            .open_elements
+            .last()
+            .expect("Attributes can only be set on an open element");
+        assert_eq!(
+            element_idx + 1,
+            self.nodes.len(),
+            "Attributes need to be set before any children"
+        );
+
+        let value = self.write_string(value);
+        self.attrs.push(TreeAttr {
+            name: name.into(),
+            value,
+        });
+
+        if let TreeNodeKind::Element { attrs, .. } = &mut self.nodes[element_idx].kind {
+            attrs.end = self.attrs.len();
+        }
+    }
+
+    /// Push a text node.
+    pub fn text<T: fmt::Display>(&mut self, text: T) {
+        let start = self.strings.len();
+        if self.space_before_text {
+            self.strings.push(' ');
+        }
+        self.space_before_text = false;
+
+        write!(self.strings, "{}", text).expect("Writing into a String");
+        let text = start..self.strings.len();
+
+        self.nodes.push(TreeNode {
+            kind: TreeNodeKind::Text(text),
+            descendants: 0,
+        });
+    }
+
+    /// Close the most recently opened element.
+    pub fn close_element(&mut self) {
+        self.space_before_text = false;
+
+        let element_idx = self
+            .open_elements
+            .pop()
+            .expect("Closing an element that was never opened");
+
+        self.nodes[element_idx].descendants = self.nodes.len() - element_idx - 1;
+    }
+
+    /// Copy every node of another tree, whose elements were all closed, into this one.
+    pub fn push_tree(&mut self, other: &VTree) {
+        debug_assert!(other.open_elements.is_empty(), "Pushing an unfinished tree");
+
+        if other.is_empty() {
+            return;
+        }
+
+        let space_before_text = match other.nodes[0].kind {
+            TreeNodeKind::Text(_) => self.space_before_text,
+            TreeNodeKind::Element { .. } => false,
+        };
+        if space_before_text {
+            self.strings.push(' ');
+        }
+        self.space_before_text = false;
+
+        let node_offset = self.nodes.len();
+        let attr_offset = self.attrs.len();
+        let string_offset = self.strings.len();
+        let shift = |range: &Range<usize>, offset: usize| range.start + offset..range.end + offset;
+
+        self.strings.push_str(&other.strings);
+        self.attrs.extend(other.attrs.iter().map(|attr| TreeAttr {
+            name: attr.name.clone(),
+            value: shift(&attr.value, string_offset),
+        }));
+        self.nodes.extend(other.nodes.iter().map(|node| {
+            let kind = match &node.kind {
+                TreeNodeKind::Element { tag, attrs } => TreeNodeKind::Element {
+                    tag: tag.clone(),
+                    attrs: shift(attrs, attr_offset),
+                },
+                TreeNodeKind::Text(text) => TreeNodeKind::Text(shift(text, string_offset)),
+            };
+
+            TreeNode {
+                kind,
+                descendants: node.descendants,
+            }
+        }));
+
+        // The inserted space belongs to the first text node
+        if space_before_text {
+            if let TreeNodeKind::Text(text) = &mut self.nodes[node_offset].kind {
+                text.start -= 1;
+            }
+        }
+    }
+
+    /// Insert a space before the next text node, as long as it is the next node that gets
+    /// pushed.
+    ///
+    /// Used by `html_tree!` for the same spacing around braced text that `html!` has.
+    pub fn insert_space_before_text(&mut self) {
+        self.space_before_text = true;
+    }
+
+    /// Append a space to the last node that was pushed since `start`, at the same depth, if
+    /// it is a text node.
+    ///
+    /// Used by `html_tree!` for the same spacing around braced text that `html!` has.
+    pub fn insert_space_after_text(&mut self, start: usize) {
+        if start >= self.nodes.len() {
+            return;
+        }
+
+        let mut last = start;
+        while self.next_sibling(last) < self.nodes.len() {
+            last = self.next_sibling(last);
+        }
+
+        // The text of the last node is at the end of the strings since nothing came after it
+        if let TreeNodeKind::Text(text) = &mut self.nodes[last].kind {
+            self.strings.push(' ');
+            text.end = self.strings.len();
+        }
+    }
+
+    /// The number of nodes within the tree.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether or not the tree has any nodes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The tag of the node at the given index, or `None` if it is a text node.
+    #[inline]
+    pub fn tag(&self, idx: usize) -> Option<&str> {
+        match &self.nodes[idx].kind {
+            TreeNodeKind::Element { tag, .. } => Some(tag),
+            TreeNodeKind::Text(_) => None,
+        }
+    }
+
+    /// The text of the node at the given index, or `None` if it is an element.
+    #[inline]
+    pub fn text_at(&self, idx: usize) -> Option<&str> {
+        match &self.nodes[idx].kind {
+            TreeNodeKind::Text(text) => Some(&self.strings[text.clone()]),
+            TreeNodeKind::Element { .. } => None,
+        }
+    }
+
+    /// The attribute names and values of the element at the given index, in the order that
+    /// they were set.
+    #[inline]
+    pub fn attrs(&self, idx: usize) -> impl Iterator<Item = (&str, &str)> {
+        let attrs = match &self.nodes[idx].kind {
+            TreeNodeKind::Element { attrs, .. } => &self.attrs[attrs.clone()],
+            TreeNodeKind::Text(_) => &[],
+        };
+
+        attrs
+            .iter()
+            .map(move |attr| (attr.name.as_ref(), &self.strings[attr.value.clone()]))
+    }
+
+    /// The value of an attribute of the element at the given index.
+    #[inline]
+    pub fn attr_value(&self, idx: usize, name: &str) -> Option<&str> {
+        self.attrs(idx)
+            .find(|(attr_name, _)| *attr_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// The indices of the children of the node at the given index.
+    #[inline]
+    pub fn children(&self, idx: usize) -> TreeChildren<'_> {
+        TreeChildren {
+            tree: self,
+            next: idx + 1,
+            end: idx + 1 + self.nodes[idx].descendants,
+        }
+    }
+
+    /// The number of nodes within the node at the given index.
+    #[inline]
+    pub fn descendant_count(&self, idx: usize) -> usize {
+        self.nodes[idx].descendants
+    }
+
+    /// Copy the node at the given index, along with its descendants, into a `VirtualNode`.
+    pub fn virtual_node(&self, idx: usize) -> VirtualNode {
+        match &self.nodes[idx].kind {
+            TreeNodeKind::Text(text) => VirtualNode::text(&self.strings[text.clone()]),
+            TreeNodeKind::Element { tag, .. } => {
+                let mut element = VElement::new(tag.as_ref());
+
+                for (name, value) in self.attrs(idx) {
+                    // `html!` keeps the class attribute as class tokens
+                    if name == "class" {
+                        element.classes.insert(value);
+                    } else {
+                        element.attrs.insert(name.to_string(), value.to_string());
+                    }
+                }
+
+                element.children = self
+                    .children(idx)
+                    .map(|child_idx| self.virtual_node(child_idx))
+                    .collect();
+
+                VirtualNode::Element(element)
+            }
+        }
+    }
+
+    #[inline]
+    fn next_sibling(&self, idx: usize) -> usize {
+        idx + 1 + self.nodes[idx].descendants
+    }
+
+    fn write_string<T: fmt::Display>(&mut self, value: T) -> Range<usize> {
+        let start = self.strings.len();
+        write!(self.strings, "{}", value).expect("Writing into a String");
+
+        start..self.strings.len()
+    }
+
+    fn fmt_node(&self, idx: usize, f: &mut fmt::Formatter) -> fmt::Result {
+        let tag = match &self.nodes[idx].kind {
+            TreeNodeKind::Text(text) => return write!(f, "{}", &self.strings[text.clone()]),
+            TreeNodeKind::Element { tag, .. } => tag,
+        };
+
+        write!(f, "<{}", tag)?;
+        for (name, value) in self.attrs(idx) {
+            write!(f, r#" {}="{}""#, name, value)?;
+        }
+        write!(f, ">")?;
+
+        for child_idx in self.children(idx) {
+            self.fmt_node(child_idx, f)?;
+        }
+
+        if !html_validation::is_self_closing(tag) {
+            write!(f, "</{}>", tag)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Iterates over the indices of the children of a node, see `VTree::children`.
+pub struct TreeChildren<'a> {
+    tree: &'a VTree,
+    next: usize,
+    end: usize,
+}
+
+impl<'a> Iterator for TreeChildren<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.next >= self.end {
+            return None;
+        }
+
+        let child_idx = self.next;
+        self.next = self.tree.next_sibling(child_idx);
+
+        Some(child_idx)
+    }
+}
+
+/// Used by the `html_tree!` macro for all braced child nodes, the `VTree` equivalent of
+/// `IterableNodes`.
+///
+/// html_tree! { <div> { nodes } </div> }
+///
+/// nodes can be a &str .. String .. VTree .. Vec<VTree> ... etc
+pub trait IntoTreeNodes {
+    /// Push the nodes into the tree.
+    fn push_into(self, tree: &mut VTree);
+}
+
+impl IntoTreeNodes for &str {
+    fn push_into(self, tree: &mut VTree) {
+        tree.text(self);
+    }
+}
+
+impl IntoTreeNodes for String {
+    fn push_into(self, tree: &mut VTree) {
+        tree.text(self);
+    }
+}
+
+impl IntoTreeNodes for &String {
+    fn push_into(self, tree: &mut VTree) {
+        tree.text(self);
+    }
+}
+
+impl IntoTreeNodes for VTree {
+    fn push_into(self, tree: &mut VTree) {
+        tree.push_tree(&self);
+    }
+}
+
+impl IntoTreeNodes for &VTree {
+    fn push_into(self, tree: &mut VTree) {
+        tree.push_tree(self);
+    }
+}
+
+impl<T: IntoTreeNodes> IntoTreeNodes for Vec<T> {
+    fn push_into(self, tree: &mut VTree) {
+        for nodes in self {
+            nodes.push_into(tree);
+        }
+    }
+}
+
+impl<T: IntoTreeNodes> IntoTreeNodes for Option<T> {
+    fn push_into(self, tree: &mut VTree) {
+        if let Some(nodes) = self {
+            nodes.push_into(tree);
+        }
+    }
+}
+
+impl From<&VTree> for VirtualNode {
+    fn from(tree: &VTree) -> Self {
+        tree.virtual_node(0)
+    }
+}
+
+impl PartialEq for VTree {
+    fn eq(&self, other: &VTree) -> bool {
+        self.len() == other.len()
+            && (0..self.len()).all(|idx| {
+                self.tag(idx) == other.tag(idx)
+                    && self.text_at(idx) == other.text_at(idx)
+                    && self.descendant_count(idx) == other.descendant_count(idx)
+                    && self.attrs(idx).eq(other.attrs(idx))
+            })
+    }
+}
+
+// Turn a VTree into an HTML string without creating any `VirtualNode`s
+impl fmt::Display for VTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        self.fmt_node(0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> VTree {
+        let mut tree = VTree::new();
+        tree.open_element("ul");
+        tree.attr("class", "list big");
+        for item in items {
+            tree.open_element("li");
+            tree.text(item);
+            tree.close_element();
+        }
+        tree.close_element();
+
+        tree
+    }
+
+    #[test]
+    fn indexes_nodes_depth_first() {
+        let tree = list(&["a", "b"]);
+
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.children(0).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(tree.tag(3), Some("li"));
+        assert_eq!(tree.text_at(4), Some("b"));
+        assert_eq!(tree.attr_value(0, "class"), Some("list big"));
+    }
+
+    #[test]
+    fn converts_into_virtual_node() {
+        let tree = list(&["a"]);
+
+        let mut expected = VElement::new("ul");
+        expected.classes.insert("list big");
+        let mut li = VElement::new("li");
+        li.children.push(VirtualNode::text("a"));
+        expected.children.push(li.into());
+
+        assert_eq!(VirtualNode::from(&tree), VirtualNode::Element(expected));
+    }
+
+    #[test]
+    fn to_string() {
+        let mut tree = VTree::new();
+        tree.open_element("div");
+        tree.open_element("br");
+        tree.close_element();
+        tree.text(5);
+        tree.close_element();
+
+        assert_eq!(tree.to_string(), "<div><br>5</div>");
+    }
+
+    #[test]
+    fn push_tree() {
+        let mut tree = VTree::new();
+        tree.open_element("div");
+        tree.attr("id", "outer");
+        tree.insert_space_before_text();
+        let start = tree.len();
+        tree.push_tree(&list(&["a"]));
+        tree.push_tree(&list(&["b"]));
+        tree.insert_space_after_text(start);
+        tree.close_element();
+
+        assert_eq!(tree.children(0).collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(tree.text_at(6), Some("b"));
+        assert_eq!(
+            tree.to_string(),
+            r#"<div id="outer"><ul class="list big"><li>a</li></ul><ul class="list big"><li>b</li></ul></div>"#
+        );
+    }
+
+    #[test]
+    fn space_around_text() {
+        let mut tree = VTree::new();
+        tree.open_element("div");
+        tree.insert_space_before_text();
+        let start = tree.len();
+        "Hello".push_into(&mut tree);
+        tree.insert_space_after_text(start);
+        tree.close_element();
+
+        assert_eq!(tree.text_at(1), Some(" Hello "));
+    }
+}
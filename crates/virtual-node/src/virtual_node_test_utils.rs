@@ -37,13 +37,18 @@ impl VirtualNode {
                     get_descendants(&mut descendants, child);
                 }
             }
+            VirtualNode::Portal(portal) => {
+                for child in portal.children.iter() {
+                    get_descendants(&mut descendants, child);
+                }
+            }
         }
 
         // Filter descendants
         descendants
             .into_iter()
             .filter(|vn: &&'a VirtualNode| match vn {
-                VirtualNode::Text(_) => false,
+                VirtualNode::Text(_) | VirtualNode::Portal(_) => false,
                 VirtualNode::Element(element_node) => match element_node.attrs.get("label") {
                     Some(label) => filter(label),
                     None => false,
@@ -85,6 +90,11 @@ fn get_descendants<'a>(descendants: &mut Vec<&'a VirtualNode>, node: &'a Virtual
                 get_descendants(descendants, child);
             }
         }
+        VirtualNode::Portal(portal) => {
+            for child in portal.children.iter() {
+                get_descendants(descendants, child);
+            }
+        }
     }
 }
 
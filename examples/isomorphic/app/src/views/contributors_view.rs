@@ -17,8 +17,6 @@ impl ContributorsView {
 
 impl View for ContributorsView {
     fn render(&self) -> VirtualNode {
-        let nav_bar = NavBarView::new(ActivePage::Contributors).render();
-
         let store = self.store.borrow();
         let contributors = store.contributors().to_owned();
         let contributors_list: Vec<VirtualNode> = match contributors {
@@ -43,7 +41,7 @@ impl View for ContributorsView {
 
         html! {
             <div>
-                { nav_bar }
+                <NavBarView active_page=ActivePage::Contributors />
                 <div>
                     <ul>
                         { contributors_list }
@@ -20,8 +20,6 @@ impl HomeView {
 
 impl View for HomeView {
     fn render(&self) -> VirtualNode {
-        let nav_bar = NavBarView::new(ActivePage::Home).render();
-
         let store = Rc::clone(&self.store);
 
         let click_count = self.store.borrow().click_count();
@@ -32,7 +30,7 @@ impl View for HomeView {
         html! {
         <div>
 
-          { nav_bar }
+          <NavBarView active_page=ActivePage::Home />
 
           <span> The button has been clicked: { click_component } times! </span>
           <button onclick=move|_: web_sys::Event| { store.borrow_mut().msg(&Msg::Click) }>
@@ -5,13 +5,7 @@ mod nav_bar_item_view;
 use self::nav_bar_item_view::NavBarItemView;
 
 pub struct NavBarView {
-    active_page: ActivePage,
-}
-
-impl NavBarView {
-    pub fn new(active_page: ActivePage) -> NavBarView {
-        NavBarView { active_page }
-    }
+    pub active_page: ActivePage,
 }
 
 pub enum ActivePage {
@@ -7,6 +7,7 @@ use log::Level;
 use std::cell::RefCell;
 use std::rc::Rc;
 use virtual_dom_rs::prelude::*;
+use virtual_dom_rs::{RecoveryPolicy, RequestAnimationFrame, Scheduler};
 use wasm_bindgen;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
@@ -15,23 +16,7 @@ use web_sys::Url;
 
 #[wasm_bindgen]
 pub struct Client {
-    app: App,
-    dom_updater: DomUpdater,
-}
-
-// Expose globals from JS for things such as request animation frame
-// that web sys doesn't seem to have yet
-//
-// TODO: Remove this and use RAF from Rust
-// https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.Window.html#method.request_animation_frame
-#[wasm_bindgen]
-extern "C" {
-    pub type GlobalJS;
-
-    pub static global_js: GlobalJS;
-
-    #[wasm_bindgen(method)]
-    pub fn update(this: &GlobalJS);
+    scheduler: Scheduler,
 }
 
 #[wasm_bindgen]
@@ -46,13 +31,6 @@ impl Client {
 
         let app = App::from_state_json(initial_state);
 
-        // TODO: Use request animation frame from web_sys
-        // https://rustwasm.github.io/wasm-bindgen/api/web_sys/struct.Window.html#method.request_animation_frame
-        app.store.borrow_mut().subscribe(Box::new(|| {
-            web_sys::console::log_1(&"Updating state".into());
-            global_js.update();
-        }));
-
         app.store.borrow_mut().set_after_route(Box::new(|new_path| {
             history().push_state_with_url(&JsValue::null(), "Rust Web App", Some(new_path));
         }));
@@ -72,17 +50,37 @@ impl Client {
         let root_node = document()
             .get_element_by_id("isomorphic-rust-web-app")
             .unwrap();
-        let dom_updater = DomUpdater::new_replace_mount(app.render(), root_node);
+        let mut dom_updater = DomUpdater::new_replace_mount(app.render(), root_node);
+        // If a browser extension messes with our DOM we'd rather re-render the whole page than
+        // crash.
+        dom_updater.set_recovery_policy(RecoveryPolicy::RecreateRoot);
 
         let store = Rc::clone(&app.store);
         intercept_relative_links(store);
 
-        Client { app, dom_updater }
+        let store = Rc::clone(&app.store);
+
+        // No matter how many messages we receive in one tick we'll only re-render once,
+        // during the next animation frame.
+        let scheduler = Scheduler::new(RequestAnimationFrame, move || {
+            dom_updater
+                .update(app.render())
+                .expect("Could not re-create the root node");
+        });
+
+        let scheduler_clone = scheduler.clone();
+        store.borrow_mut().subscribe(Box::new(move || {
+            web_sys::console::log_1(&"Updating state".into());
+            scheduler_clone.mark_dirty();
+        }));
+
+        Client { scheduler }
     }
 
+    /// Re-render right away instead of waiting for the next animation frame.
     pub fn render(&mut self) {
-        let vdom = self.app.render();
-        self.dom_updater.update(vdom);
+        self.scheduler.mark_dirty();
+        self.scheduler.flush();
     }
 }
 
@@ -4,4 +4,5 @@ cd $(git rev-parse --show-toplevel)
 
 cargo build --all && # Make sure examples compile
 cargo test --all &&
+cargo test -p mock-dom --features mock-dom/stats &&
 wasm-pack test crates/virtual-dom-rs --firefox --headless